@@ -1,5 +1,5 @@
 use anyhow::Result;
-use apex_hardware::{AsyncDevice, FrameBuffer};
+use apex_hardware::{AsyncDevice, FrameBuffer, Orientation};
 use gamesense::raw_client::{
     BindGameEvent, FrameContainer, GameEvent, Heartbeat, RawGameSenseClient, RegisterGame,
     RemoveEvent, RemoveGame, Screen, ScreenFrameData, ScreenHandler, Sendable,
@@ -29,6 +29,7 @@ pub const HEARTBEAT: Heartbeat = Heartbeat { game: GAME };
 #[derive(Debug, Clone)]
 pub struct Engine {
     client: RawGameSenseClient,
+    orientation: Orientation,
 }
 
 impl Engine {
@@ -58,7 +59,15 @@ impl Engine {
         .await?;
         info!("{}", x);
 
-        Ok(Self { client })
+        Ok(Self {
+            client,
+            orientation: Orientation::default(),
+        })
+    }
+
+    pub fn with_orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
     }
 
     pub async fn heartbeat(&self) -> Result<()> {
@@ -68,6 +77,8 @@ impl Engine {
 }
 
 impl AsyncDevice for Engine {
+    type Frame = FrameBuffer;
+
     type ClearResult<'a> = impl Future<Output = Result<()>> + 'a;
     type DrawResult<'a> = impl Future<Output = Result<()>> + 'a;
     type ShutdownResult<'a> = impl Future<Output = Result<()>> + 'a;
@@ -75,6 +86,7 @@ impl AsyncDevice for Engine {
     #[allow(clippy::needless_lifetimes)]
     fn draw<'this>(&'this mut self, display: &'this FrameBuffer) -> Self::DrawResult<'this> {
         async {
+            let display = display.oriented(self.orientation);
             let screen = display.framebuffer.as_raw_slice();
 
             let event = GameEvent {
@@ -1,8 +1,9 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use bitvec::prelude::*;
 use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
 #[cfg(feature = "async")]
 use std::future::Future;
+use std::{convert::TryFrom, fmt};
 
 const FB_SIZE: usize = 40 * 128 / 8 + 2;
 
@@ -30,18 +31,100 @@ impl FrameBuffer {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Inverts every pixel, e.g. to grab the user's attention.
+    pub fn invert(&mut self) {
+        for mut pixel in self.framebuffer[8..8 + 128 * 40].iter_mut() {
+            *pixel = !*pixel;
+        }
+    }
+
+    /// Returns a copy of this `FrameBuffer` with the given orientation
+    /// applied. This is meant to be the very last step before the image is
+    /// sent to a device so widgets never have to care about it.
+    pub fn oriented(&self, orientation: Orientation) -> Self {
+        let mut buffer = *self;
+        if orientation == Orientation::UpsideDown {
+            // The pixels are stored row by row so rotating the image by 180° is the same as
+            // reversing their order. The header byte stays where it is.
+            buffer.framebuffer[8..8 + 128 * 40].reverse();
+        }
+        buffer
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+/// The orientation of the physical display, e.g. for keyboards that are
+/// mounted upside down.
+pub enum Orientation {
+    /// The content is displayed as drawn
+    #[default]
+    Normal,
+    /// The content is rotated by 180°
+    UpsideDown,
+}
+
+impl TryFrom<i64> for Orientation {
+    type Error = anyhow::Error;
+
+    fn try_from(degrees: i64) -> Result<Self, Self::Error> {
+        match degrees {
+            0 => Ok(Orientation::Normal),
+            180 => Ok(Orientation::UpsideDown),
+            _ => Err(anyhow!(
+                "Unsupported orientation: {}°, only 0° and 180° are supported!",
+                degrees
+            )),
+        }
+    }
+}
+
+/// Returned when the keyboard is gone for good, e.g. because it was
+/// unplugged, as opposed to a write that merely failed.
+#[derive(Debug, Copy, Clone)]
+pub struct Disconnected;
+
+impl fmt::Display for Disconnected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("The keyboard was disconnected")
+    }
+}
+
+impl std::error::Error for Disconnected {}
+
+/// How writes to the keyboard went so far.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct WriteStats {
+    /// How many writes had to be retried
+    pub retries: u64,
+    /// How often the device had to be opened again
+    pub reconnects: u64,
 }
 
 /// This trait represents a device that can receive new images to be displayed.
-pub trait Device {
-    /// Sends a `FrameBuffer` to the device.
-    fn draw(&mut self, display: &FrameBuffer) -> Result<()>;
+///
+/// Devices are generic over the color of their pixels so displays with more
+/// than one bit per pixel can be supported later on. All of the currently
+/// supported devices have 1-bit displays which is why `C` defaults to
+/// `BinaryColor` and `FrameBuffer` is the only `Frame` at the moment.
+pub trait Device<C: PixelColor = BinaryColor> {
+    /// The type of image this device can display.
+    type Frame: DrawTarget<Color = C>;
+
+    /// Sends a `Frame` to the device.
+    fn draw(&mut self, display: &Self::Frame) -> Result<()>;
     /// Convenience method for clearing the whole screen.
     /// Most implementations will send an empty `FrameBuffer` to `Device::draw`
     /// but there may be more efficient ways for some devices to implement here.
     fn clear(&mut self) -> Result<()>;
 
     fn shutdown(&mut self) -> Result<()>;
+
+    /// How writes went so far, devices that never retry don't have to count
+    /// anything.
+    fn write_stats(&self) -> WriteStats {
+        WriteStats::default()
+    }
 }
 
 impl Drawable for FrameBuffer {
@@ -98,7 +181,10 @@ impl DrawTarget for FrameBuffer {
 }
 
 #[cfg(feature = "async")]
-pub trait AsyncDevice {
+pub trait AsyncDevice<C: PixelColor = BinaryColor> {
+    /// The type of image this device can display.
+    type Frame: DrawTarget<Color = C>;
+
     type DrawResult<'a>: Future<Output = Result<()>> + 'a
     where
         Self: 'a;
@@ -111,18 +197,25 @@ pub trait AsyncDevice {
         Self: 'a;
 
     #[allow(clippy::needless_lifetimes)]
-    fn draw<'this>(&'this mut self, display: &'this FrameBuffer) -> Self::DrawResult<'this>;
+    fn draw<'this>(&'this mut self, display: &'this Self::Frame) -> Self::DrawResult<'this>;
     #[allow(clippy::needless_lifetimes)]
     fn clear<'this>(&'this mut self) -> Self::ClearResult<'this>;
     #[allow(clippy::needless_lifetimes)]
     fn shutdown<'this>(&'this mut self) -> Self::ShutdownResult<'this>;
+
+    /// See [`Device::write_stats`].
+    fn write_stats(&self) -> WriteStats {
+        WriteStats::default()
+    }
 }
 
 #[cfg(feature = "async")]
-impl<T: Device> AsyncDevice for T
+impl<C: PixelColor, T: Device<C>> AsyncDevice<C> for T
 where
     T: 'static,
 {
+    type Frame = <T as Device<C>>::Frame;
+
     type ClearResult<'a> = impl Future<Output = Result<()>> + 'a
     where
         Self: 'a;
@@ -134,20 +227,77 @@ where
         Self: 'a;
 
     #[allow(clippy::needless_lifetimes)]
-    fn draw<'this>(&'this mut self, display: &'this FrameBuffer) -> Self::DrawResult<'this> {
-        let x = <Self as Device>::draw(self, display);
+    fn draw<'this>(&'this mut self, display: &'this Self::Frame) -> Self::DrawResult<'this> {
+        let x = <Self as Device<C>>::draw(self, display);
         async { x }
     }
 
     #[allow(clippy::needless_lifetimes)]
     fn clear<'this>(&'this mut self) -> Self::ClearResult<'this> {
-        let x = <Self as Device>::clear(self);
+        let x = <Self as Device<C>>::clear(self);
         async { x }
     }
 
     #[allow(clippy::needless_lifetimes)]
     fn shutdown<'this>(&'this mut self) -> Self::ShutdownResult<'this> {
-        let x = <Self as Device>::shutdown(self);
+        let x = <Self as Device<C>>::shutdown(self);
         async { x }
     }
+
+    fn write_stats(&self) -> WriteStats {
+        <Self as Device<C>>::write_stats(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(buffer: &FrameBuffer, x: usize, y: usize) -> bool {
+        buffer.framebuffer[8 + x + y * 128]
+    }
+
+    #[test]
+    fn upside_down_rotates_by_180_degrees() {
+        let mut buffer = FrameBuffer::new();
+        // Neither mirrored horizontally nor vertically so every mistake shows
+        let lit = [(0, 0), (3, 1), (127, 2), (10, 39), (64, 20)];
+        for (x, y) in lit {
+            buffer.framebuffer.set(8 + x + y * 128, true);
+        }
+
+        let rotated = buffer.oriented(Orientation::UpsideDown);
+        for y in 0..40 {
+            for x in 0..128 {
+                assert_eq!(
+                    pixel(&rotated, x, y),
+                    lit.contains(&(127 - x, 39 - y)),
+                    "Pixel at ({}, {})",
+                    x,
+                    y
+                );
+            }
+        }
+        assert_eq!(rotated.framebuffer.as_raw_slice()[0], 0x61);
+    }
+
+    #[test]
+    fn upside_down_twice_is_the_original() {
+        let mut buffer = FrameBuffer::new();
+        Pixel(Point::new(5, 7), BinaryColor::On)
+            .draw(&mut buffer)
+            .unwrap();
+        Pixel(Point::new(100, 38), BinaryColor::On)
+            .draw(&mut buffer)
+            .unwrap();
+
+        let twice = buffer
+            .oriented(Orientation::UpsideDown)
+            .oriented(Orientation::UpsideDown);
+        assert_eq!(twice.framebuffer, buffer.framebuffer);
+        assert_eq!(
+            buffer.oriented(Orientation::Normal).framebuffer,
+            buffer.framebuffer
+        );
+    }
 }
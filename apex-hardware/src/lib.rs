@@ -7,6 +7,6 @@ pub use bitvec::prelude::BitVec;
 pub use device::AsyncDevice;
 pub use device::Device;
 #[cfg(feature = "usb")]
-pub use usb::USBDevice;
+pub use usb::{USBDevice, DEFAULT_RETRIES};
 
-pub use device::FrameBuffer;
+pub use device::{Disconnected, FrameBuffer, Orientation, WriteStats};
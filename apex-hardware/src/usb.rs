@@ -1,16 +1,41 @@
-use crate::{device::FrameBuffer, Device};
+use crate::{
+    device::{Disconnected, FrameBuffer, Orientation, WriteStats},
+    Device,
+};
 use anyhow::{anyhow, Result};
 use embedded_graphics::{
     pixelcolor::BinaryColor,
     prelude::*,
     primitives::{PrimitiveStyleBuilder, Rectangle, StyledDrawable},
 };
-use hidapi::{HidApi, HidDevice};
+use hidapi::{HidApi, HidDevice, HidError};
 use num_enum::TryFromPrimitive;
+use std::{thread, time::Duration};
 
 /// The SteelSeries vendor ID used to identify the USB devices
 pub static STEELSERIES_VENDOR_ID: u16 = 0x1038;
 
+/// How often a failed write is retried unless configured otherwise
+pub const DEFAULT_RETRIES: u32 = 2;
+/// How long to wait before the first retry, this doubles with every attempt
+const RETRY_BACKOFF: Duration = Duration::from_millis(1);
+/// How long all retries of a single write may wait in total. Writes block
+/// the runtime so this has to stay well below the length of a tick.
+const MAX_BACKOFF: Duration = Duration::from_millis(5);
+
+/// Whether `error` means the device is gone rather than busy. hidapi only
+/// hands us the message of the platform, `ENODEV` on Linux and
+/// `ERROR_DEVICE_NOT_CONNECTED` on Windows are the ones we can recognize.
+fn is_disconnected(error: &HidError) -> bool {
+    match error {
+        HidError::HidApiError { message } => {
+            let message = message.to_lowercase();
+            message.contains("no such device") || message.contains("not connected")
+        }
+        _ => false,
+    }
+}
+
 #[repr(u16)]
 #[derive(Debug, Eq, PartialEq, TryFromPrimitive)]
 /// This enum contains the product IDs of currently supported devices
@@ -28,10 +53,24 @@ enum SupportedDevice {
 pub struct USBDevice {
     /// An exclusive handle to the Keyboard.
     handle: HidDevice,
+    /// The orientation that is applied to every image before it's sent.
+    orientation: Orientation,
+    /// How often a failed write is retried before giving up on the frame.
+    retries: u32,
+    stats: WriteStats,
 }
 
 impl USBDevice {
     pub fn try_connect() -> Result<Self> {
+        Ok(Self {
+            handle: Self::open()?,
+            orientation: Orientation::default(),
+            retries: DEFAULT_RETRIES,
+            stats: WriteStats::default(),
+        })
+    }
+
+    fn open() -> Result<HidDevice> {
         let api = HidApi::new()?;
 
         // Get all supported devices by SteelSeries
@@ -46,9 +85,50 @@ impl USBDevice {
             .ok_or_else(|| anyhow!("No supported SteelSeries device found!"))?;
 
         // This requires udev rules to be setup properly.
-        let handle = device.open_device(&api)?;
+        Ok(device.open_device(&api)?)
+    }
+
+    pub fn with_orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
 
-        Ok(Self { handle })
+    /// Sets how often a failed write is retried. Busy hubs occasionally
+    /// drop a report, retrying right away usually gets it through.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Sends a report, retrying with a short backoff if that fails. If the
+    /// device is gone or retrying didn't help we open it again once, it might
+    /// have been replugged or reset by the hub.
+    fn send(&mut self, report: &[u8]) -> Result<()> {
+        let mut attempt = 0;
+        let mut waited = Duration::ZERO;
+        loop {
+            let error = match self.handle.send_feature_report(report) {
+                Ok(()) => return Ok(()),
+                Err(error) => error,
+            };
+
+            // There's no point in retrying if the device is gone
+            if is_disconnected(&error) || attempt >= self.retries {
+                break;
+            }
+            let backoff = (RETRY_BACKOFF * 2_u32.pow(attempt.min(8))).min(MAX_BACKOFF - waited);
+            thread::sleep(backoff);
+            waited += backoff;
+            attempt += 1;
+            self.stats.retries += 1;
+        }
+
+        self.handle = Self::open().map_err(|_| Disconnected)?;
+        self.stats.reconnects += 1;
+        self.handle
+            .send_feature_report(report)
+            .map_err(|_| Disconnected)?;
+        Ok(())
     }
 
     pub fn fill(&mut self) -> Result<()> {
@@ -63,10 +143,11 @@ impl USBDevice {
 }
 
 impl Device for USBDevice {
+    type Frame = FrameBuffer;
+
     fn draw(&mut self, display: &FrameBuffer) -> Result<()> {
-        Ok(self
-            .handle
-            .send_feature_report(display.framebuffer.as_raw_slice())?)
+        let display = display.oriented(self.orientation);
+        self.send(display.framebuffer.as_raw_slice())
     }
 
     fn clear(&mut self) -> Result<()> {
@@ -77,4 +158,8 @@ impl Device for USBDevice {
     fn shutdown(&mut self) -> Result<()> {
         Ok(())
     }
+
+    fn write_stats(&self) -> WriteStats {
+        self.stats
+    }
 }
@@ -14,6 +14,7 @@ impl InputManager {
         let modifiers = vec![Modifier::ALT, Modifier::SHIFT];
 
         let sender2 = sender.clone();
+        let sender3 = sender.clone();
 
         hkm.register(
             Hotkey {
@@ -28,7 +29,7 @@ impl InputManager {
         )?;
         hkm.register(
             Hotkey {
-                modifiers,
+                modifiers: modifiers.clone(),
                 keys: vec![Key::D],
             },
             move || {
@@ -37,6 +38,17 @@ impl InputManager {
                     .expect("Failed to send command!");
             },
         )?;
+        hkm.register(
+            Hotkey {
+                modifiers,
+                keys: vec![Key::P],
+            },
+            move || {
+                sender3
+                    .send(Command::TogglePin)
+                    .expect("Failed to send command!");
+            },
+        )?;
 
         Ok(Self { _hkm: hkm })
     }
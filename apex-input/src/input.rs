@@ -2,5 +2,12 @@
 pub enum Command {
     PreviousSource,
     NextSource,
+    /// Stay on the current source until unpinned
+    Pin,
+    Unpin,
+    TogglePin,
+    /// Blank the display until woken up, e.g. while the session is locked
+    Sleep,
+    Wake,
     Shutdown,
 }
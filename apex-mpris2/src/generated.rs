@@ -260,3 +260,44 @@ impl dbus::message::SignalArgs for MediaPlayer2PlayerSeeked {
     const INTERFACE: &'static str = "org.mpris.MediaPlayer2.Player";
     const NAME: &'static str = "Seeked";
 }
+
+pub trait MediaPlayer2TrackList {
+    fn get_tracks_metadata(
+        &self,
+        track_ids: Vec<dbus::Path>,
+    ) -> nonblock::MethodReply<Vec<arg::PropMap>>;
+    fn tracks(&self) -> nonblock::MethodReply<Vec<dbus::Path<'static>>>;
+    fn can_edit_tracks(&self) -> nonblock::MethodReply<bool>;
+}
+
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target = T>> MediaPlayer2TrackList
+    for nonblock::Proxy<'a, C>
+{
+    fn get_tracks_metadata(
+        &self,
+        track_ids: Vec<dbus::Path>,
+    ) -> nonblock::MethodReply<Vec<arg::PropMap>> {
+        self.method_call(
+            "org.mpris.MediaPlayer2.TrackList",
+            "GetTracksMetadata",
+            (track_ids,),
+        )
+        .and_then(|r: (Vec<arg::PropMap>,)| Ok(r.0))
+    }
+
+    fn tracks(&self) -> nonblock::MethodReply<Vec<dbus::Path<'static>>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(
+            &self,
+            "org.mpris.MediaPlayer2.TrackList",
+            "Tracks",
+        )
+    }
+
+    fn can_edit_tracks(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(
+            &self,
+            "org.mpris.MediaPlayer2.TrackList",
+            "CanEditTracks",
+        )
+    }
+}
@@ -1,4 +1,6 @@
 #![feature(type_alias_impl_trait, async_iterator)]
 mod generated;
 mod player;
+#[cfg(feature = "tags")]
+mod tags;
 pub use player::{Metadata, Player, MPRIS2};
@@ -1,4 +1,6 @@
-use crate::generated::MediaPlayer2Player;
+use crate::generated::{MediaPlayer2Player, MediaPlayer2TrackList};
+#[cfg(feature = "tags")]
+use crate::tags::{self, TagCache};
 use anyhow::{anyhow, Result};
 use apex_music::{AsyncPlayer, Metadata as MetadataTrait, PlaybackStatus, PlayerEvent, Progress};
 use async_stream::stream;
@@ -7,6 +9,7 @@ use dbus::{
     message::MatchRule,
     nonblock::{Proxy, SyncConnection},
     strings::BusName,
+    Message,
 };
 use dbus_tokio::connection;
 use futures_core::stream::Stream;
@@ -14,8 +17,42 @@ use futures_util::StreamExt;
 use std::{future::Future, sync::Arc, time::Duration};
 use tokio::{task::JoinHandle, time, time::MissedTickBehavior};
 
+/// How often an event is emitted even if no player reported any changes
+const FALLBACK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Translates a `PropertiesChanged` signal into the events for the properties
+/// that changed.
+fn properties_changed(msg: &Message) -> Vec<PlayerEvent> {
+    let (interface, changed, invalidated) = match msg.read3::<String, PropMap, Vec<String>>() {
+        Ok(body) => body,
+        Err(_) => return vec![PlayerEvent::Properties],
+    };
+
+    if interface != "org.mpris.MediaPlayer2.Player" {
+        return vec![PlayerEvent::Properties];
+    }
+
+    let mut events = Vec::new();
+    for property in changed.keys().chain(invalidated.iter()) {
+        let event = match property.as_str() {
+            "Metadata" => PlayerEvent::TrackChanged,
+            "PlaybackStatus" => PlayerEvent::PlaybackStatusChanged,
+            "Position" => PlayerEvent::PositionChanged,
+            _ => PlayerEvent::Properties,
+        };
+        if !events.contains(&event) {
+            events.push(event);
+        }
+    }
+    events
+}
+
 #[derive(Clone)]
-pub struct Player<'a>(Proxy<'a, Arc<SyncConnection>>);
+pub struct Player<'a> {
+    proxy: Proxy<'a, Arc<SyncConnection>>,
+    #[cfg(feature = "tags")]
+    tags: Option<TagCache>,
+}
 
 #[derive(Debug)]
 pub struct Metadata(PropMap);
@@ -27,12 +64,10 @@ impl MetadataTrait for Metadata {
             .ok_or_else(|| anyhow!("Couldn't get title!"))
     }
 
-    fn artists(&self) -> Result<String> {
-        Ok(
-            ::dbus::arg::prop_cast::<Vec<String>>(&self.0, "xesam:artist")
-                .ok_or_else(|| anyhow!("Couldn't get artist!"))?
-                .join(", "),
-        )
+    fn artists(&self) -> Result<Vec<String>> {
+        ::dbus::arg::prop_cast::<Vec<String>>(&self.0, "xesam:artist")
+            .cloned()
+            .ok_or_else(|| anyhow!("Couldn't get artist!"))
     }
 
     fn length(&self) -> Result<u64> {
@@ -40,11 +75,30 @@ impl MetadataTrait for Metadata {
             .copied()
             .ok_or_else(|| anyhow!("Couldn't get length!"))
     }
+
+    fn art_url(&self) -> Result<String> {
+        ::dbus::arg::prop_cast::<String>(&self.0, "mpris:artUrl")
+            .filter(|url| !url.is_empty())
+            .cloned()
+            .ok_or_else(|| anyhow!("Couldn't get art URL!"))
+    }
+}
+
+/// The id of the track as it's listed in the track list. It's supposed to
+/// be an object path but some players send a plain string.
+fn track_id(metadata: &PropMap) -> Option<dbus::Path<'static>> {
+    ::dbus::arg::prop_cast::<dbus::Path<'static>>(metadata, "mpris:trackid")
+        .cloned()
+        .or_else(|| {
+            ::dbus::arg::prop_cast::<String>(metadata, "mpris:trackid")
+                .and_then(|id| dbus::Path::new(id.clone()).ok())
+        })
 }
 
 pub struct MPRIS2 {
     handle: JoinHandle<()>,
     conn: Arc<SyncConnection>,
+    read_tags: bool,
 }
 
 impl MPRIS2 {
@@ -56,9 +110,28 @@ impl MPRIS2 {
             panic!("Lost connection to D-Bus: {}", err);
         });
 
-        Ok(Self { handle, conn })
+        Ok(Self {
+            handle,
+            conn,
+            read_tags: false,
+        })
+    }
+
+    /// Allows reading the tags of local files to fill in metadata the players
+    /// don't report themselves. This does nothing unless the `tags` feature is
+    /// enabled.
+    pub fn with_file_tags(mut self, read_tags: bool) -> Self {
+        self.read_tags = read_tags;
+        self
+    }
+
+    fn player(&self, name: String) -> Player<'_> {
+        Player::new(name, self.conn.clone()).with_file_tags(self.read_tags)
     }
 
+    /// Subscribes to the signals of all MPRIS players and turns them into
+    /// events. Players that don't emit signals properly are covered by a slow
+    /// fallback timer.
     #[allow(unreachable_code, unused_variables)]
     pub async fn stream(&self) -> Result<impl Stream<Item = PlayerEvent>> {
         let mr = MatchRule::new()
@@ -75,22 +148,36 @@ impl MPRIS2 {
 
         let (seek_match, mut seek_stream) = self.conn.add_match(mr).await?.msg_stream();
 
+        // Players come and go with their name on the bus
+        let mr = MatchRule::new()
+            .with_sender("org.freedesktop.DBus")
+            .with_interface("org.freedesktop.DBus")
+            .with_member("NameOwnerChanged");
+
+        let (owner_match, mut owner_stream) = self.conn.add_match(mr).await?.msg_stream();
+
+        let mut timer = time::interval(FALLBACK_INTERVAL);
+        timer.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
         Ok(stream! {
             loop {
-                let mut timer = time::interval(time::Duration::from_millis(100));
-                timer.set_missed_tick_behavior(MissedTickBehavior::Skip);
-                // First timer tick elapses instantaneously
-                timer.tick().await;
-
                 tokio::select! {
                     msg = seek_stream.next() => {
-                        if let Some(_) = msg {
+                        if msg.is_some() {
                             yield PlayerEvent::Seeked;
                         }
                     },
                     msg = meta_stream.next() => {
-                        if let Some(_) = msg {
-                            yield PlayerEvent::Properties;
+                        if let Some(msg) = msg {
+                            for event in properties_changed(&msg) {
+                                yield event;
+                            }
+                        }
+                    },
+                    msg = owner_stream.next() => {
+                        let name = msg.and_then(|msg| msg.read1::<String>().ok());
+                        if matches!(name, Some(name) if name.starts_with("org.mpris.MediaPlayer2.")) {
+                            yield PlayerEvent::PlayerChanged;
                         }
                     },
                     _ = timer.tick() => {
@@ -98,9 +185,10 @@ impl MPRIS2 {
                     }
                 }
             }
-            // The signal handler will unregister if those two are dropped so we never drop them ;)
+            // The signal handler will unregister if those are dropped so we never drop them ;)
             drop(seek_match);
             drop(meta_match);
+            drop(owner_match);
         })
     }
 
@@ -125,6 +213,16 @@ impl MPRIS2 {
         Ok(result)
     }
 
+    /// Every player on the bus, whether it's playing or not.
+    pub async fn players(&self) -> Result<Vec<Player<'_>>> {
+        Ok(self
+            .list_names()
+            .await?
+            .into_iter()
+            .map(|name| self.player(name))
+            .collect())
+    }
+
     pub async fn wait_for_player(&self, name: Option<Arc<String>>) -> Result<Player<'_>> {
         let mut interval = time::interval(Duration::from_secs(5));
         interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
@@ -141,12 +239,12 @@ impl MPRIS2 {
                 // We have a player preference, let's check if it exists
                 if let Some(player) = names.into_iter().find(|p| p.contains(name)) {
                     // Hell yeah, we found a player
-                    return Ok(Player::new(player, self.conn.clone()));
+                    return Ok(self.player(player));
                 }
             } else {
                 // Let's try to find a player that's either playing or paused
                 for name in names {
-                    let player = Player::new(name, self.conn.clone());
+                    let player = self.player(name);
 
                     match player.playback_status().await {
                         // Something is playing or paused right now, let's use that
@@ -175,12 +273,28 @@ impl Drop for MPRIS2 {
 
 impl<'a> Player<'a> {
     pub fn new(path: impl Into<BusName<'a>>, conn: Arc<SyncConnection>) -> Self {
-        Self(Proxy::new(
-            path.into(),
-            "/org/mpris/MediaPlayer2",
-            Duration::from_secs(2),
-            conn,
-        ))
+        Self {
+            proxy: Proxy::new(
+                path.into(),
+                "/org/mpris/MediaPlayer2",
+                Duration::from_secs(2),
+                conn,
+            ),
+            #[cfg(feature = "tags")]
+            tags: None,
+        }
+    }
+
+    /// Fills in missing metadata with the tags of the file that's being played.
+    #[cfg(feature = "tags")]
+    pub fn with_file_tags(mut self, read_tags: bool) -> Self {
+        self.tags = read_tags.then(TagCache::default);
+        self
+    }
+
+    #[cfg(not(feature = "tags"))]
+    pub fn with_file_tags(self, _read_tags: bool) -> Self {
+        self
     }
 
     pub async fn progress(&self) -> Result<Progress<Metadata>> {
@@ -199,6 +313,9 @@ impl<'a> AsyncPlayer for Player<'a> {
     where
         Self: 'b;
     type NameFuture<'b> = impl Future<Output = String> + 'b
+    where
+        Self: 'b;
+    type NextTracksFuture<'b> = impl Future<Output = Result<Vec<Self::Metadata>>> + 'b
     where
         Self: 'b;
     type PlaybackStatusFuture<'b> = impl Future<Output = Result<PlaybackStatus>> + 'b
@@ -210,13 +327,21 @@ impl<'a> AsyncPlayer for Player<'a> {
 
     #[allow(clippy::needless_lifetimes)]
     fn metadata<'this>(&'this self) -> Self::MetadataFuture<'this> {
-        async { Ok(Metadata(self.0.metadata().await?)) }
+        async {
+            #[allow(unused_mut)]
+            let mut metadata = self.proxy.metadata().await?;
+            #[cfg(feature = "tags")]
+            if let Some(cache) = &self.tags {
+                tags::backfill(&mut metadata, cache);
+            }
+            Ok(Metadata(metadata))
+        }
     }
 
     #[allow(clippy::needless_lifetimes)]
     fn playback_status<'this>(&'this self) -> Self::PlaybackStatusFuture<'this> {
         async {
-            let status = self.0.playback_status().await?;
+            let status = self.proxy.playback_status().await?;
 
             match status.as_str() {
                 "Playing" => Ok(PlaybackStatus::Playing),
@@ -229,11 +354,41 @@ impl<'a> AsyncPlayer for Player<'a> {
 
     #[allow(clippy::needless_lifetimes)]
     fn name<'this>(&'this self) -> Self::NameFuture<'this> {
-        async { self.0.destination.to_string() }
+        async { self.proxy.destination.to_string() }
     }
 
     #[allow(clippy::needless_lifetimes)]
     fn position<'this>(&'this self) -> Self::PositionFuture<'this> {
-        async { Ok(self.0.position().await?) }
+        async { Ok(self.proxy.position().await?) }
+    }
+
+    #[allow(clippy::needless_lifetimes)]
+    fn next_tracks<'this>(&'this self, n: usize) -> Self::NextTracksFuture<'this> {
+        async move {
+            // Only a few players implement the optional TrackList interface, the call
+            // simply fails for the others
+            let tracks = self
+                .proxy
+                .tracks()
+                .await
+                .map_err(|_| anyhow!("The player doesn't expose its queue"))?;
+
+            // The track list contains the current track as well, usually right at the
+            // start. If it's not in there we can't tell what comes next
+            let current = track_id(&self.proxy.metadata().await?);
+            let Some(start) = current
+                .and_then(|current| tracks.iter().position(|track| *track == current))
+                .map(|index| index + 1)
+            else {
+                return Ok(Vec::new());
+            };
+            let next = tracks.into_iter().skip(start).take(n).collect::<Vec<_>>();
+            if next.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            let metadata = self.proxy.get_tracks_metadata(next).await?;
+            Ok(metadata.into_iter().map(Metadata).collect())
+        }
     }
 }
@@ -0,0 +1,159 @@
+use apex_music::file_path;
+use dbus::arg::{prop_cast, PropMap, RefArg, Variant};
+use lofty::{Accessor, AudioFile, Picture};
+use std::{
+    collections::hash_map::DefaultHasher,
+    env, fs,
+    hash::{Hash, Hasher},
+    os::unix::fs::DirBuilderExt,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+/// The tags that were read from a file, everything is optional as files can
+/// be just as sparse as the players themselves.
+#[derive(Debug, Clone, Default)]
+pub struct FileTags {
+    title: Option<String>,
+    artists: Option<Vec<String>>,
+    album: Option<String>,
+    genres: Option<Vec<String>>,
+    track_number: Option<i32>,
+    /// Length in microseconds
+    length: Option<u64>,
+    /// A `file://` URL pointing to the extracted artwork
+    art_url: Option<String>,
+}
+
+/// Remembers the tags of the current track so we don't read the file again
+/// on every update.
+pub type TagCache = Arc<Mutex<Option<(String, FileTags)>>>;
+
+/// Where extracted artwork is kept, e.g. `~/.cache/apex-tux/art`. This is
+/// only readable by the current user so nobody else can plant a picture
+/// there.
+fn cache_dir() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?
+        .join("apex-tux")
+        .join("art");
+
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&dir)
+        .ok()?;
+    Some(dir)
+}
+
+/// Writes the artwork into the cache directory and returns its URL. The file
+/// name is derived from the track and the picture so the same picture isn't
+/// written over and over again, while a changed one gets a file of its own.
+fn extract_artwork(url: &str, picture: &Picture) -> Option<String> {
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    picture.data().hash(&mut hasher);
+
+    let extension = match picture.mime_type().as_str() {
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/bmp" => "bmp",
+        "image/gif" => "gif",
+        _ => "img",
+    };
+    let path = cache_dir()?.join(format!("{:x}.{}", hasher.finish(), extension));
+
+    if !path.exists() {
+        // Written next to it first so a half written picture is never picked up
+        let partial = path.with_extension("part");
+        fs::write(&partial, picture.data()).ok()?;
+        fs::rename(&partial, &path).ok()?;
+    }
+
+    Some(format!("file://{}", path.display()))
+}
+
+fn read(url: &str) -> FileTags {
+    let file = match file_path(url).and_then(|path| lofty::read_from_path(path).ok()) {
+        Some(file) => file,
+        None => return FileTags::default(),
+    };
+
+    let length = file.properties().duration().as_micros() as u64;
+    let mut tags = FileTags {
+        length: (length > 0).then_some(length),
+        ..FileTags::default()
+    };
+
+    if let Some(tag) = file.primary_tag().or_else(|| file.first_tag()) {
+        tags.title = tag.title().map(|title| title.to_string());
+        tags.artists = tag.artist().map(|artist| vec![artist.to_string()]);
+        tags.album = tag.album().map(|album| album.to_string());
+        tags.genres = tag.genre().map(|genre| vec![genre.to_string()]);
+        tags.track_number = tag.track().map(|track| track as i32);
+        tags.art_url = tag
+            .pictures()
+            .first()
+            .and_then(|picture| extract_artwork(url, picture));
+    }
+
+    tags
+}
+
+fn insert(metadata: &mut PropMap, key: &str, value: Option<impl RefArg + 'static>) {
+    if let Some(value) = value {
+        metadata.insert(key.to_string(), Variant(Box::new(value)));
+    }
+}
+
+/// Fills in whatever the player left out with the tags from the file that's
+/// currently being played. Values reported over DBus always win, so this only
+/// ever adds missing entries.
+pub fn backfill(metadata: &mut PropMap, cache: &TagCache) {
+    let url = match prop_cast::<String>(metadata, "xesam:url") {
+        Some(url) if url.starts_with("file://") => url.clone(),
+        _ => return,
+    };
+
+    let tags = {
+        let mut cache = cache.lock().expect("Tag cache is poisoned!");
+        match &*cache {
+            Some((cached, tags)) if *cached == url => tags.clone(),
+            _ => {
+                let tags = read(&url);
+                *cache = Some((url, tags.clone()));
+                tags
+            }
+        }
+    };
+
+    let missing_string = |metadata: &PropMap, key: &str| {
+        prop_cast::<String>(metadata, key).is_none_or(String::is_empty)
+    };
+    let missing_list = |metadata: &PropMap, key: &str| {
+        prop_cast::<Vec<String>>(metadata, key).is_none_or(Vec::is_empty)
+    };
+
+    if missing_string(metadata, "xesam:title") {
+        insert(metadata, "xesam:title", tags.title);
+    }
+    if missing_list(metadata, "xesam:artist") {
+        insert(metadata, "xesam:artist", tags.artists);
+    }
+    if missing_string(metadata, "xesam:album") {
+        insert(metadata, "xesam:album", tags.album);
+    }
+    if missing_list(metadata, "xesam:genre") {
+        insert(metadata, "xesam:genre", tags.genres);
+    }
+    if prop_cast::<i32>(metadata, "xesam:trackNumber").is_none() {
+        insert(metadata, "xesam:trackNumber", tags.track_number);
+    }
+    if prop_cast::<u64>(metadata, "mpris:length").is_none() {
+        insert(metadata, "mpris:length", tags.length);
+    }
+    if missing_string(metadata, "mpris:artUrl") {
+        insert(metadata, "mpris:artUrl", tags.art_url);
+    }
+}
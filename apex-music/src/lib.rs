@@ -1,5 +1,7 @@
 #![feature(type_alias_impl_trait)]
 mod player;
+mod url;
 pub use player::{
     AsyncMetadata, AsyncPlayer, Metadata, PlaybackStatus, Player, PlayerEvent, Progress,
 };
+pub use url::file_path;
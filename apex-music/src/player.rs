@@ -9,17 +9,34 @@ pub enum PlaybackStatus {
     Playing,
 }
 
-#[derive(Clone, Debug)]
+/// Something happened that might change what's displayed. All backends emit
+/// the same events, backends that can't tell what changed fall back to
+/// `Timer`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum PlayerEvent {
+    /// The position within the track was changed manually
     Seeked,
+    /// A property changed that doesn't have its own event
     Properties,
+    /// The metadata changed, most likely because a new track started
+    TrackChanged,
+    /// The player started, paused or stopped playing
+    PlaybackStatusChanged,
+    /// The player reported a new position
+    PositionChanged,
+    /// A player appeared or disappeared
+    PlayerChanged,
+    /// Emitted every now and then in case a change slipped through
     Timer,
 }
 
 pub trait Metadata {
     fn title(&self) -> Result<String>;
-    fn artists(&self) -> Result<String>;
+    /// Every artist on its own, how they're joined is up to the display
+    fn artists(&self) -> Result<Vec<String>>;
     fn length(&self) -> Result<u64>;
+    /// A `file://` or `http(s)://` URL pointing to the album art
+    fn art_url(&self) -> Result<String>;
 }
 
 pub trait Player {
@@ -28,6 +45,11 @@ pub trait Player {
     fn position(&self) -> Result<i64>;
     fn name(&self) -> String;
     fn playback_status(&self) -> Result<PlaybackStatus>;
+    /// Up to `n` tracks that are going to be played after the current one.
+    /// Players that don't expose their queue have nothing coming up.
+    fn next_tracks(&self, _n: usize) -> Result<Vec<Self::Metadata>> {
+        Ok(vec![])
+    }
 }
 
 pub struct Progress<T: Metadata + Sized> {
@@ -55,6 +77,10 @@ pub trait AsyncPlayer {
     where
         Self: 'a;
 
+    type NextTracksFuture<'a>: Future<Output = Result<Vec<Self::Metadata>>> + 'a
+    where
+        Self: 'a;
+
     #[allow(clippy::needless_lifetimes)]
     fn metadata<'this>(&'this self) -> Self::MetadataFuture<'this>;
 
@@ -66,6 +92,11 @@ pub trait AsyncPlayer {
 
     #[allow(clippy::needless_lifetimes)]
     fn position<'this>(&'this self) -> Self::PositionFuture<'this>;
+
+    /// Up to `n` tracks that are going to be played after the current one.
+    /// Players that don't expose their queue return an error.
+    #[allow(clippy::needless_lifetimes)]
+    fn next_tracks<'this>(&'this self, n: usize) -> Self::NextTracksFuture<'this>;
 }
 
 impl<T: Player + Sized> AsyncPlayer for T {
@@ -75,6 +106,9 @@ impl<T: Player + Sized> AsyncPlayer for T {
     where
         T: 'a;
     type NameFuture<'a> = impl Future<Output = String>
+    where
+        T: 'a;
+    type NextTracksFuture<'a> = impl Future<Output = Result<Vec<Self::Metadata>>>
     where
         T: 'a;
     type PlaybackStatusFuture<'a> = impl Future<Output = Result<PlaybackStatus>>
@@ -107,13 +141,19 @@ impl<T: Player + Sized> AsyncPlayer for T {
         let position = <Self as Player>::position(self);
         async { position }
     }
+
+    #[allow(clippy::needless_lifetimes)]
+    fn next_tracks<'this>(&'this self, n: usize) -> Self::NextTracksFuture<'this> {
+        let tracks = <Self as Player>::next_tracks(self, n);
+        async { tracks }
+    }
 }
 
 pub trait AsyncMetadata {
     type TitleFuture<'a>: Future<Output = Result<String>> + 'a
     where
         Self: 'a;
-    type ArtistsFuture<'a>: Future<Output = Result<String>> + 'a
+    type ArtistsFuture<'a>: Future<Output = Result<Vec<String>>> + 'a
     where
         Self: 'a;
     type LengthFuture<'a>: Future<Output = Result<u64>> + 'a
@@ -132,7 +172,7 @@ pub trait AsyncMetadata {
 
 /// Blanket implementation for non-async Metadata sources
 impl<T: Metadata + Sized> AsyncMetadata for T {
-    type ArtistsFuture<'a> = impl Future<Output = Result<String>> + 'a
+    type ArtistsFuture<'a> = impl Future<Output = Result<Vec<String>>> + 'a
     where
         T: 'a;
     type LengthFuture<'a> = impl Future<Output = Result<u64>> + 'a
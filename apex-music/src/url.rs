@@ -0,0 +1,36 @@
+use std::path::PathBuf;
+
+/// Turns a `file://` URL into a path, undoing the percent encoding. Returns
+/// `None` for any other kind of URL.
+pub fn file_path(url: &str) -> Option<PathBuf> {
+    let path = url.strip_prefix("file://")?;
+    let mut bytes = Vec::with_capacity(path.len());
+
+    let mut iter = path.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let high = (iter.next()? as char).to_digit(16)?;
+            let low = (iter.next()? as char).to_digit(16)?;
+            bytes.push((high * 16 + low) as u8);
+        } else {
+            bytes.push(byte);
+        }
+    }
+
+    Some(PathBuf::from(String::from_utf8(bytes).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_file_urls() {
+        assert_eq!(
+            file_path("file:///music/Sigur%20R%C3%B3s/01.flac"),
+            Some(PathBuf::from("/music/Sigur Rós/01.flac"))
+        );
+        assert_eq!(file_path("file:///broken%2"), None);
+        assert_eq!(file_path("https://example.com/cover.jpg"), None);
+    }
+}
@@ -42,6 +42,8 @@ impl Simulator {
                                 sender.send(Command::PreviousSource)?;
                             } else if keycode == Keycode::Right {
                                 sender.send(Command::NextSource)?;
+                            } else if keycode == Keycode::P {
+                                sender.send(Command::TogglePin)?;
                             }
                             Ok::<(), anyhow::Error>(())
                         }
@@ -65,6 +67,8 @@ impl Simulator {
 }
 
 impl Device for Simulator {
+    type Frame = FrameBuffer;
+
     fn draw(&mut self, display: &FrameBuffer) -> Result<()> {
         self.sender.send(*display)?;
         Ok(())
@@ -0,0 +1,96 @@
+use anyhow::{anyhow, Result};
+use std::{sync::OnceLock, thread};
+use tokio::sync::mpsc;
+use windows::Win32::{
+    Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, WPARAM},
+    UI::{
+        Input::KeyboardAndMouse::{VK_MEDIA_NEXT_TRACK, VK_MEDIA_PLAY_PAUSE, VK_MEDIA_PREV_TRACK},
+        WindowsAndMessaging::{
+            CallNextHookEx, GetMessageW, SetWindowsHookExW, HC_ACTION, HHOOK, KBDLLHOOKSTRUCT, MSG,
+            WH_KEYBOARD_LL, WM_KEYDOWN, WM_SYSKEYDOWN,
+        },
+    },
+};
+
+/// Where the hook sends the keys it caught and whether it lets them through
+/// to Windows afterwards. The hook procedure can't capture anything so this
+/// has to be global, there's only ever one hook.
+static HOOK: OnceLock<(mpsc::UnboundedSender<MediaKey>, bool)> = OnceLock::new();
+
+/// The media keys found on most keyboards.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MediaKey {
+    PlayPause,
+    Next,
+    Previous,
+}
+
+impl MediaKey {
+    fn from_virtual_key(code: u32) -> Option<Self> {
+        match code {
+            code if code == u32::from(VK_MEDIA_PLAY_PAUSE.0) => Some(MediaKey::PlayPause),
+            code if code == u32::from(VK_MEDIA_NEXT_TRACK.0) => Some(MediaKey::Next),
+            code if code == u32::from(VK_MEDIA_PREV_TRACK.0) => Some(MediaKey::Previous),
+            _ => None,
+        }
+    }
+}
+
+unsafe extern "system" fn keyboard_hook(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    let pressed = wparam.0 == WM_KEYDOWN as usize || wparam.0 == WM_SYSKEYDOWN as usize;
+    if code == HC_ACTION as i32 && pressed {
+        // For low level keyboard hooks `lparam` always points to a `KBDLLHOOKSTRUCT`
+        let event = &*(lparam.0 as *const KBDLLHOOKSTRUCT);
+        if let (Some(key), Some((sender, passthrough))) =
+            (MediaKey::from_virtual_key(event.vkCode), HOOK.get())
+        {
+            let _ = sender.send(key);
+            if !passthrough {
+                // Anything but zero keeps the key from reaching other applications
+                return LRESULT(1);
+            }
+        }
+    }
+
+    CallNextHookEx(HHOOK::default(), code, wparam, lparam)
+}
+
+/// Catches the media keys with a low level keyboard hook before Windows
+/// hands them to whatever app it thinks is playing.
+#[derive(Debug)]
+pub struct MediaKeys;
+
+impl MediaKeys {
+    /// Installs the hook on a thread of its own, the hook only works while
+    /// that thread keeps pumping messages. With `passthrough` the keys still
+    /// reach Windows as well, otherwise they're swallowed.
+    pub fn install(passthrough: bool) -> Result<mpsc::UnboundedReceiver<MediaKey>> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        HOOK.set((sender, passthrough))
+            .map_err(|_| anyhow!("The media keys are already hooked"))?;
+
+        let (installed, result) = std::sync::mpsc::channel();
+        thread::Builder::new()
+            .name(String::from("media-keys"))
+            .spawn(move || {
+                // Low level hooks don't need a module handle since they aren't
+                // injected into other processes
+                let hook = unsafe {
+                    SetWindowsHookExW(WH_KEYBOARD_LL, Some(keyboard_hook), HINSTANCE::default(), 0)
+                };
+                let failed = hook.is_err();
+                let _ = installed.send(hook.map(|_| ()));
+                if failed {
+                    return;
+                }
+
+                let mut message = MSG::default();
+                while unsafe { GetMessageW(&mut message, HWND::default(), 0, 0) }.as_bool() {}
+            })?;
+
+        result
+            .recv()?
+            .map_err(|e| anyhow!("Couldn't hook the keyboard: {}", e))?;
+        Ok(receiver)
+    }
+}
@@ -1,3 +1,7 @@
 #![feature(type_alias_impl_trait, async_iterator)]
+mod keys;
 mod music;
-pub use music::{Metadata, Player};
+mod session;
+pub use keys::{MediaKey, MediaKeys};
+pub use music::{Metadata, Player, SelectedSession};
+pub use session::SessionLock;
@@ -1,26 +1,75 @@
+use crate::MediaKey;
 use anyhow::{anyhow, Result};
 use apex_music::{AsyncPlayer, Metadata as MetadataTrait, PlaybackStatus, PlayerEvent, Progress};
 use futures_core::stream::Stream;
 use std::future::Future;
 
 use async_stream::stream;
-use std::time::Duration;
-use tokio::time::MissedTickBehavior;
-use windows::Media::{
-    Control,
-    Control::{
-        GlobalSystemMediaTransportControlsSession,
-        GlobalSystemMediaTransportControlsSessionManager,
-        GlobalSystemMediaTransportControlsSessionMediaProperties,
-        GlobalSystemMediaTransportControlsSessionPlaybackInfo,
-        GlobalSystemMediaTransportControlsSessionPlaybackStatus,
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::{sync::Notify, time::MissedTickBehavior};
+use windows::{
+    Foundation::{DateTime, EventRegistrationToken, TimeSpan, TypedEventHandler},
+    Media::{
+        Control,
+        Control::{
+            GlobalSystemMediaTransportControlsSession,
+            GlobalSystemMediaTransportControlsSessionManager,
+            GlobalSystemMediaTransportControlsSessionMediaProperties,
+            GlobalSystemMediaTransportControlsSessionPlaybackInfo,
+            GlobalSystemMediaTransportControlsSessionPlaybackStatus,
+        },
     },
 };
 
+/// Windows counts time in ticks of 100 nanoseconds
+const TICKS_PER_MICROSECOND: i64 = 10;
+/// Seconds between the Windows epoch (1601-01-01) and the UNIX epoch
+const WINDOWS_EPOCH_OFFSET: i64 = 11_644_473_600;
+/// A better session has to stay better for this long before we switch to it,
+/// otherwise two players that briefly both report playing make us flip-flop
+const SWITCH_HYSTERESIS: Duration = Duration::from_secs(2);
+
+fn micros(span: TimeSpan) -> i64 {
+    span.Duration / TICKS_PER_MICROSECOND
+}
+
+/// Returns how many microseconds passed between `time` and `now`.
+fn micros_since(time: DateTime, now: SystemTime) -> i64 {
+    let now = now
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_micros() as i64);
+    let time = time.UniversalTime / TICKS_PER_MICROSECOND - WINDOWS_EPOCH_OFFSET * 1_000_000;
+    (now - time).max(0)
+}
+
+/// Maps a timeline to the position within the track at `now`. Apps only update
+/// their timeline every couple of seconds, the position is the one from
+/// `last_updated` so the time since then has to be added while playing.
+fn timeline_position(
+    start: TimeSpan,
+    position: TimeSpan,
+    last_updated: DateTime,
+    playing: bool,
+    now: SystemTime,
+) -> i64 {
+    let position = micros(position) - micros(start);
+    let position = if playing {
+        position + micros_since(last_updated, now)
+    } else {
+        position
+    };
+
+    position.max(0)
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct Metadata {
     title: String,
-    artists: String,
+    artists: Vec<String>,
+    length: u64,
 }
 
 impl MetadataTrait for Metadata {
@@ -28,17 +77,136 @@ impl MetadataTrait for Metadata {
         Ok(self.title.clone())
     }
 
-    fn artists(&self) -> Result<String> {
+    fn artists(&self) -> Result<Vec<String>> {
         Ok(self.artists.clone())
     }
 
-    fn length(&self) -> Result<i64> {
-        Ok(0)
+    fn length(&self) -> Result<u64> {
+        Ok(self.length)
+    }
+
+    fn art_url(&self) -> Result<String> {
+        // The thumbnail is only available as a stream, not as a URL
+        Err(anyhow!("Album art isn't supported on Windows yet"))
+    }
+}
+
+/// Which session is displayed and which one might replace it.
+#[derive(Default)]
+struct Selection {
+    /// The app id and the session that's being displayed
+    session: Option<(String, GlobalSystemMediaTransportControlsSession)>,
+    /// The app id of a better session and since when it's been the better one
+    candidate: Option<(String, Instant)>,
+}
+
+/// Unsubscribes from the session manager events once the stream is dropped.
+struct Subscription {
+    session_manager: GlobalSystemMediaTransportControlsSessionManager,
+    sessions_changed: EventRegistrationToken,
+    current_changed: EventRegistrationToken,
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let _ = self
+            .session_manager
+            .RemoveSessionsChanged(self.sessions_changed);
+        let _ = self
+            .session_manager
+            .RemoveCurrentSessionChanged(self.current_changed);
     }
 }
 
 pub struct Player {
     session_manager: GlobalSystemMediaTransportControlsSessionManager,
+    /// Parts of app ids in the order they're preferred in, e.g. `Spotify`
+    priority: Vec<String>,
+    /// Shared with the [`SelectedSession`] handles
+    selection: Arc<Mutex<Selection>>,
+}
+
+/// The session a [`Player`] picked, e.g. to send it the media keys from
+/// another thread. It follows the player's choice for as long as the player's
+/// [`Player::stream`] is polled.
+#[derive(Clone)]
+pub struct SelectedSession {
+    selection: Arc<Mutex<Selection>>,
+}
+
+impl SelectedSession {
+    pub async fn press(&self, key: MediaKey) -> Result<()> {
+        let session = self
+            .selection
+            .lock()
+            .unwrap()
+            .session
+            .as_ref()
+            .map(|(_, session)| session.clone())
+            .ok_or_else(|| anyhow!("No session is displayed"))?;
+        let operation = match key {
+            MediaKey::PlayPause => session.TryTogglePlayPauseAsync(),
+            MediaKey::Next => session.TrySkipNextAsync(),
+            MediaKey::Previous => session.TrySkipPreviousAsync(),
+        }
+        .map_err(|e| anyhow!("Couldn't control the session: {}", e))?;
+
+        if operation.await? {
+            Ok(())
+        } else {
+            Err(anyhow!("The player ignored {:?}", key))
+        }
+    }
+}
+
+fn is_playing(session: &GlobalSystemMediaTransportControlsSession) -> bool {
+    session
+        .GetPlaybackInfo()
+        .and_then(|playback| playback.PlaybackStatus())
+        .map_or(false, |status| {
+            status == GlobalSystemMediaTransportControlsSessionPlaybackStatus::Playing
+        })
+}
+
+async fn session_metadata(session: &GlobalSystemMediaTransportControlsSession) -> Result<Metadata> {
+    let properties = session
+        .TryGetMediaPropertiesAsync()
+        .map_err(|e| anyhow!("Couldn't get media properties: {}", e))?
+        .await?;
+    let title = properties.Title()?.to_string_lossy();
+    // Multiple artists arrive joined into one string, semicolons are the only
+    // separator that doesn't show up in the names themselves
+    let artists = properties
+        .Artist()?
+        .to_string_lossy()
+        .split(';')
+        .map(str::trim)
+        .filter(|artist| !artist.is_empty())
+        .map(String::from)
+        .collect();
+    // Not every app reports a timeline in which case the length is simply unknown
+    let length = session
+        .GetTimelineProperties()
+        .and_then(|timeline| Ok(micros(timeline.EndTime()?) - micros(timeline.StartTime()?)))
+        .map_or(0, |length| length.max(0) as u64);
+    Ok(Metadata {
+        title,
+        artists,
+        length,
+    })
+}
+
+fn session_status(session: &GlobalSystemMediaTransportControlsSession) -> Result<PlaybackStatus> {
+    let playback: GlobalSystemMediaTransportControlsSessionPlaybackInfo =
+        session.GetPlaybackInfo().map_err(|_| anyhow!("Windows"))?;
+
+    let status = playback.PlaybackStatus().map_err(|_| anyhow!("Windows"))?;
+
+    Ok(match status {
+        GlobalSystemMediaTransportControlsSessionPlaybackStatus::Playing => PlaybackStatus::Playing,
+        GlobalSystemMediaTransportControlsSessionPlaybackStatus::Paused => PlaybackStatus::Paused,
+        _ => PlaybackStatus::Stopped,
+    })
 }
 
 impl Player {
@@ -49,13 +217,125 @@ impl Player {
                 .get()
                 .map_err(|_| anyhow!("Windows"))?;
 
-        Ok(Self { session_manager })
+        Ok(Self {
+            session_manager,
+            priority: Vec::new(),
+            selection: Arc::new(Mutex::new(Selection::default())),
+        })
     }
 
-    pub fn current_session(&self) -> Result<GlobalSystemMediaTransportControlsSession> {
-        self.session_manager
+    /// Sessions whose app id contains one of these are preferred over all
+    /// others, earlier entries over later ones. The matching ignores case.
+    pub fn with_priority(mut self, priority: Vec<String>) -> Self {
+        self.priority = priority
+            .into_iter()
+            .map(|entry| entry.to_lowercase())
+            .collect();
+        self
+    }
+
+    fn sessions(&self) -> Result<Vec<(String, GlobalSystemMediaTransportControlsSession)>> {
+        let sessions = self
+            .session_manager
+            .GetSessions()
+            .map_err(|e| anyhow!("Couldn't get sessions: {}", e))?;
+
+        Ok(sessions
+            .into_iter()
+            .filter_map(|session| {
+                let id = session.SourceAppUserModelId().ok()?.to_string_lossy();
+                Some((id, session))
+            })
+            .collect())
+    }
+
+    /// Lower is better. Players from the priority list come first, then
+    /// whatever is playing and then the session Windows itself considers
+    /// current.
+    fn rank(
+        &self,
+        id: &str,
+        session: &GlobalSystemMediaTransportControlsSession,
+        current: Option<&str>,
+    ) -> (usize, bool, bool) {
+        let id_lowercase = id.to_lowercase();
+        let position = self
+            .priority
+            .iter()
+            .position(|entry| id_lowercase.contains(entry.as_str()))
+            .unwrap_or(self.priority.len());
+
+        (position, !is_playing(session), current != Some(id))
+    }
+
+    /// Re-evaluates which session is displayed and returns whether it
+    /// changed. A session that went away is replaced right away, a session
+    /// that's still around only once another one has been the better choice
+    /// for [`SWITCH_HYSTERESIS`].
+    fn select(&self) -> Result<bool> {
+        let current = self
+            .session_manager
             .GetCurrentSession()
-            .map_err(|e| anyhow!("Couldn't get current session: {}", e))
+            .and_then(|session| session.SourceAppUserModelId())
+            .ok()
+            .map(|id| id.to_string_lossy());
+
+        let sessions = self.sessions()?;
+        let best = sessions
+            .iter()
+            .min_by_key(|(id, session)| self.rank(id, session, current.as_deref()))
+            .cloned();
+
+        let mut selection = self.selection.lock().unwrap();
+        let (best_id, best_session) = match best {
+            Some(best) => best,
+            None => {
+                selection.candidate = None;
+                return Ok(selection.session.take().is_some());
+            }
+        };
+
+        let chosen = selection.session.as_ref().map(|(id, _)| id.clone());
+        let still_open = chosen
+            .as_ref()
+            .map_or(false, |chosen| sessions.iter().any(|(id, _)| id == chosen));
+
+        if chosen.as_ref() == Some(&best_id) {
+            selection.candidate = None;
+            return Ok(false);
+        }
+
+        if still_open {
+            let since = match &selection.candidate {
+                Some((id, since)) if *id == best_id => *since,
+                _ => {
+                    selection.candidate = Some((best_id, Instant::now()));
+                    return Ok(false);
+                }
+            };
+            if since.elapsed() < SWITCH_HYSTERESIS {
+                return Ok(false);
+            }
+        }
+
+        selection.session = Some((best_id, best_session));
+        selection.candidate = None;
+        Ok(true)
+    }
+
+    pub fn current_session(&self) -> Result<GlobalSystemMediaTransportControlsSession> {
+        if let Some((_, session)) = &self.selection.lock().unwrap().session {
+            return Ok(session.clone());
+        }
+
+        self.select()?;
+        self.selection
+            .lock()
+            .unwrap()
+            .session
+            .as_ref()
+            .map(|(_, session)| session.clone())
+            .ok_or_else(|| anyhow!("Couldn't get current session: no session is active"))
     }
 
     pub async fn media_properties(
@@ -70,6 +350,38 @@ impl Player {
         Ok(x)
     }
 
+    /// Every session Windows knows about with its app id, status, track and
+    /// whether it's the one that's displayed. Sessions that don't answer are
+    /// left out.
+    pub async fn list(&self) -> Result<Vec<(String, PlaybackStatus, Metadata, bool)>> {
+        let displayed = self
+            .current_session()
+            .and_then(|session| Ok(session.SourceAppUserModelId()?.to_string_lossy()))
+            .ok();
+
+        let mut sessions = Vec::new();
+        for (id, session) in self.sessions()? {
+            let (status, metadata) =
+                match (session_status(&session), session_metadata(&session).await) {
+                    (Ok(status), Ok(metadata)) => (status, metadata),
+                    _ => continue,
+                };
+            let shown = displayed.as_deref() == Some(id.as_str());
+            sessions.push((id, status, metadata, shown));
+        }
+
+        Ok(sessions)
+    }
+
+    /// Sends a media key to the session that's displayed. Windows would pick
+    /// the session on its own which isn't necessarily the same one.
+    /// A handle to the session this player picks.
+    pub fn selected(&self) -> SelectedSession {
+        SelectedSession {
+            selection: self.selection.clone(),
+        }
+    }
+
     pub async fn progress(&self) -> Result<Progress<Metadata>> {
         Ok(Progress {
             metadata: self.metadata().await?,
@@ -78,18 +390,54 @@ impl Player {
         })
     }
 
-    #[allow(unreachable_code, unused_variables)]
-    pub async fn stream(&self) -> Result<impl Stream<Item = PlayerEvent>> {
+    /// Yields [`PlayerEvent::PlayerChanged`] whenever a different session
+    /// is chosen and [`PlayerEvent::Timer`] in between, Windows doesn't tell
+    /// us about track changes within a session.
+    pub async fn stream(&self) -> Result<impl Stream<Item = PlayerEvent> + '_> {
+        let changed = Arc::new(Notify::new());
+
+        let notify = changed.clone();
+        let sessions_changed = self
+            .session_manager
+            .SessionsChanged(&TypedEventHandler::new(move |_, _| {
+                notify.notify_one();
+                Ok(())
+            }))
+            .map_err(|e| anyhow!("Couldn't subscribe to session changes: {}", e))?;
+        let notify = changed.clone();
+        let current_changed = self
+            .session_manager
+            .CurrentSessionChanged(&TypedEventHandler::new(move |_, _| {
+                notify.notify_one();
+                Ok(())
+            }))
+            .map_err(|e| anyhow!("Couldn't subscribe to session changes: {}", e))?;
+        let subscription = Subscription {
+            session_manager: self.session_manager.clone(),
+            sessions_changed,
+            current_changed,
+        };
+
         let mut timer = tokio::time::interval(Duration::from_millis(100));
         timer.set_missed_tick_behavior(MissedTickBehavior::Skip);
         Ok(stream! {
+            let _subscription = subscription;
             loop {
-                timer.tick().await;
-                yield PlayerEvent::Timer;
+                // The timer also takes care of switching once the hysteresis is over
+                tokio::select! {
+                    _ = timer.tick() => {},
+                    _ = changed.notified() => {},
+                }
+
+                match self.select() {
+                    Ok(true) => yield PlayerEvent::PlayerChanged,
+                    _ => yield PlayerEvent::Timer,
+                }
             }
         })
     }
 }
+
 impl AsyncPlayer for Player {
     type Metadata = Metadata;
 
@@ -97,6 +445,9 @@ impl AsyncPlayer for Player {
     where
         Self: 'b;
     type NameFuture<'b> = impl Future<Output = String> + 'b
+    where
+        Self: 'b;
+    type NextTracksFuture<'b> = impl Future<Output = Result<Vec<Self::Metadata>>> + 'b
     where
         Self: 'b;
     type PlaybackStatusFuture<'b> = impl Future<Output = Result<PlaybackStatus>> + 'b
@@ -108,50 +459,128 @@ impl AsyncPlayer for Player {
 
     #[allow(clippy::needless_lifetimes)]
     fn metadata<'this>(&'this self) -> Self::MetadataFuture<'this> {
-        async {
-            let session = self.media_properties().await?;
-            let title = session.Title()?.to_string_lossy();
-            let artists = session.Artist()?.to_string_lossy();
-            Ok(Metadata { title, artists })
-        }
+        async { session_metadata(&self.current_session()?).await }
     }
 
     #[allow(clippy::needless_lifetimes)]
     fn playback_status<'this>(&'this self) -> Self::PlaybackStatusFuture<'this> {
         async {
-            let session = self.current_session();
-            let session = match session {
-                Ok(session) => session,
-                Err(_) => return Ok(PlaybackStatus::Stopped),
-            };
-
-            let playback: GlobalSystemMediaTransportControlsSessionPlaybackInfo =
-                session.GetPlaybackInfo().map_err(|_| anyhow!("Windows"))?;
-
-            let status = playback.PlaybackStatus().map_err(|_| anyhow!("Windows"))?;
-
-            Ok(match status {
-                GlobalSystemMediaTransportControlsSessionPlaybackStatus::Playing => {
-                    PlaybackStatus::Playing
-                }
-                GlobalSystemMediaTransportControlsSessionPlaybackStatus::Paused => {
-                    PlaybackStatus::Paused
-                }
-                _ => PlaybackStatus::Stopped,
-            })
+            match self.current_session() {
+                Ok(session) => session_status(&session),
+                Err(_) => Ok(PlaybackStatus::Stopped),
+            }
         }
     }
 
     #[allow(clippy::needless_lifetimes)]
     fn name<'this>(&'this self) -> Self::NameFuture<'this> {
-        // There might be a Windows API to find the name of the player but the user most
-        // likely will never see this anyway
-        async { String::from("windows-api") }
+        async {
+            // The application user model id is the closest thing to a player name Windows
+            // exposes, e.g. `Spotify.exe` or `SpotifyAB.SpotifyMusic_zpdnekdrzrea0!Spotify`
+            self.current_session()
+                .and_then(|session| {
+                    session
+                        .SourceAppUserModelId()
+                        .map_err(|e| anyhow!("Couldn't get app id: {}", e))
+                })
+                .map(|id| id.to_string_lossy())
+                .unwrap_or_else(|_| String::from("windows-api"))
+        }
     }
 
     #[allow(clippy::needless_lifetimes)]
     fn position<'this>(&'this self) -> Self::PositionFuture<'this> {
-        // TODO: Find the API for this?
-        async { Ok(0) }
+        async {
+            let session = self.current_session()?;
+            let timeline = session
+                .GetTimelineProperties()
+                .map_err(|e| anyhow!("Couldn't get timeline properties: {}", e))?;
+            let playing = matches!(self.playback_status().await?, PlaybackStatus::Playing);
+
+            Ok(timeline_position(
+                timeline.StartTime()?,
+                timeline.Position()?,
+                timeline.LastUpdatedTime()?,
+                playing,
+                SystemTime::now(),
+            ))
+        }
+    }
+
+    #[allow(clippy::needless_lifetimes)]
+    fn next_tracks<'this>(&'this self, _n: usize) -> Self::NextTracksFuture<'this> {
+        // The session manager only knows about the track that's playing right now
+        async { Err(anyhow!("Windows doesn't expose the queue of a player")) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A Windows timestamp `seconds` after the UNIX epoch, and the same point
+    /// in time
+    fn at(seconds: u64) -> (DateTime, SystemTime) {
+        let ticks = (WINDOWS_EPOCH_OFFSET + seconds as i64) * 1_000_000 * TICKS_PER_MICROSECOND;
+        (
+            DateTime {
+                UniversalTime: ticks,
+            },
+            UNIX_EPOCH + Duration::from_secs(seconds),
+        )
+    }
+
+    fn seconds(seconds: i64) -> TimeSpan {
+        TimeSpan {
+            Duration: seconds * 1_000_000 * TICKS_PER_MICROSECOND,
+        }
+    }
+
+    #[test]
+    fn maps_the_timeline_to_the_position_within_the_track() {
+        let (updated, then) = at(1_700_000_000);
+
+        // The timeline doesn't have to start at zero
+        assert_eq!(
+            timeline_position(seconds(5), seconds(65), updated, false, then),
+            60_000_000
+        );
+        // Playing tracks moved on since the app last updated the timeline
+        assert_eq!(
+            timeline_position(
+                seconds(5),
+                seconds(65),
+                updated,
+                true,
+                then + Duration::from_secs(3)
+            ),
+            63_000_000
+        );
+        // Paused ones didn't
+        assert_eq!(
+            timeline_position(
+                seconds(5),
+                seconds(65),
+                updated,
+                false,
+                then + Duration::from_secs(3)
+            ),
+            60_000_000
+        );
+        // A clock that's behind the app never moves the position backwards
+        assert_eq!(
+            timeline_position(
+                seconds(0),
+                seconds(10),
+                updated,
+                true,
+                then - Duration::from_secs(30)
+            ),
+            10_000_000
+        );
+        assert_eq!(
+            timeline_position(seconds(10), seconds(0), updated, false, then),
+            0
+        );
     }
 }
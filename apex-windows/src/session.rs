@@ -0,0 +1,124 @@
+use anyhow::{anyhow, Result};
+use std::{sync::OnceLock, thread};
+use tokio::sync::mpsc;
+use windows::{
+    w,
+    Win32::{
+        Foundation::{HWND, LPARAM, LRESULT, WPARAM},
+        System::{
+            LibraryLoader::GetModuleHandleW,
+            RemoteDesktop::{WTSRegisterSessionNotification, NOTIFY_FOR_THIS_SESSION},
+        },
+        UI::WindowsAndMessaging::{
+            CreateWindowExW, DefWindowProcW, DispatchMessageW, GetMessageW, RegisterClassW, HMENU,
+            HWND_MESSAGE, MSG, WINDOW_EX_STYLE, WINDOW_STYLE, WM_WTSSESSION_CHANGE, WNDCLASSW,
+        },
+    },
+};
+
+/// The `wparam` of `WM_WTSSESSION_CHANGE` when the session was locked or
+/// unlocked, from wtsapi32.h
+const WTS_SESSION_LOCK: usize = 0x7;
+const WTS_SESSION_UNLOCK: usize = 0x8;
+
+/// Where the window procedure sends whether the session is locked. It can't
+/// capture anything so this has to be global, there's only ever one window.
+static SESSION: OnceLock<mpsc::UnboundedSender<bool>> = OnceLock::new();
+
+unsafe extern "system" fn window_procedure(
+    window: HWND,
+    message: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if message == WM_WTSSESSION_CHANGE {
+        let locked = match wparam.0 {
+            WTS_SESSION_LOCK => Some(true),
+            WTS_SESSION_UNLOCK => Some(false),
+            _ => None,
+        };
+        if let (Some(locked), Some(sender)) = (locked, SESSION.get()) {
+            let _ = sender.send(locked);
+        }
+        return LRESULT(0);
+    }
+
+    DefWindowProcW(window, message, wparam, lparam)
+}
+
+/// Tells when the session gets locked and unlocked.
+#[derive(Debug)]
+pub struct SessionLock;
+
+impl SessionLock {
+    /// Registers for session notifications with a hidden message-only window
+    /// on a thread of its own, the notifications only arrive while that
+    /// thread keeps pumping messages. The receiver gets `true` whenever the
+    /// session is locked and `false` whenever it's unlocked.
+    pub fn watch() -> Result<mpsc::UnboundedReceiver<bool>> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        SESSION
+            .set(sender)
+            .map_err(|_| anyhow!("The session is already watched"))?;
+
+        let (registered, result) = std::sync::mpsc::channel();
+        thread::Builder::new()
+            .name(String::from("session-lock"))
+            .spawn(move || {
+                let window = match unsafe { create_window() } {
+                    Ok(window) => window,
+                    Err(e) => {
+                        let _ = registered.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = registered.send(Ok(()));
+
+                let mut message = MSG::default();
+                while unsafe { GetMessageW(&mut message, window, 0, 0) }.as_bool() {
+                    unsafe { DispatchMessageW(&message) };
+                }
+            })?;
+
+        result
+            .recv()?
+            .map_err(|e| anyhow!("Couldn't watch the session: {}", e))?;
+        Ok(receiver)
+    }
+}
+
+unsafe fn create_window() -> Result<HWND> {
+    let instance = GetModuleHandleW(None)?;
+    let class = WNDCLASSW {
+        lpfnWndProc: Some(window_procedure),
+        hInstance: instance,
+        lpszClassName: w!("apex-tux-session"),
+        ..WNDCLASSW::default()
+    };
+    if RegisterClassW(&class) == 0 {
+        return Err(windows::core::Error::from_win32().into());
+    }
+
+    let window = CreateWindowExW(
+        WINDOW_EX_STYLE::default(),
+        w!("apex-tux-session"),
+        w!("apex-tux"),
+        WINDOW_STYLE::default(),
+        0,
+        0,
+        0,
+        0,
+        HWND_MESSAGE,
+        HMENU::default(),
+        instance,
+        None,
+    );
+    if window.0 == 0 {
+        return Err(windows::core::Error::from_win32().into());
+    }
+
+    if !WTSRegisterSessionNotification(window, NOTIFY_FOR_THIS_SESSION).as_bool() {
+        return Err(windows::core::Error::from_win32().into());
+    }
+    Ok(window)
+}
@@ -0,0 +1,201 @@
+//! A tiny HTTP API so other instances can mirror what's shown here.
+//!
+//! There's only one endpoint for now, `GET /nowplaying`, which answers with
+//! the track the music player is playing as JSON or `null` if nothing is
+//! playing:
+//! ```text
+//! $ curl http://127.0.0.1:7879/nowplaying
+//! {"player":"Lollypop","title":"Blue","artists":["Joni Mitchell"],"position":61000,"length":180000,"playing":true}
+//! ```
+#[cfg(any(feature = "dbus-support", target_os = "windows"))]
+use crate::backend::{self, Player};
+use anyhow::Result;
+#[cfg(any(feature = "dbus-support", target_os = "windows"))]
+use apex_music::{Metadata, PlaybackStatus};
+use config::Config;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+#[cfg(any(feature = "dbus-support", target_os = "windows"))]
+use std::sync::Arc;
+#[cfg(any(feature = "dbus-support", target_os = "windows"))]
+use tokio::time::{self, Duration, MissedTickBehavior};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::watch,
+    task::JoinHandle,
+};
+
+/// Only reachable from this machine unless configured otherwise
+const DEFAULT_ADDRESS: &str = "127.0.0.1:7879";
+/// How often the player is asked what it's playing, in milliseconds
+#[cfg(any(feature = "dbus-support", target_os = "windows"))]
+const POLL_INTERVAL: u64 = 1000;
+
+/// What `GET /nowplaying` answers with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NowPlaying {
+    pub player: String,
+    pub title: String,
+    pub artists: Vec<String>,
+    /// How far into the track the player is, in milliseconds
+    pub position: u64,
+    /// The length of the track in milliseconds, zero if it's unknown
+    pub length: u64,
+    /// `false` while paused or stopped
+    pub playing: bool,
+}
+
+/// Serves the HTTP API until it's dropped.
+#[derive(Debug)]
+pub struct ApiServer {
+    handle: JoinHandle<()>,
+}
+
+impl ApiServer {
+    pub async fn bind(config: &Config) -> Result<Self> {
+        let address = config
+            .get_str("api.address")
+            .unwrap_or_else(|_| String::from(DEFAULT_ADDRESS));
+        let listener = TcpListener::bind(&address).await?;
+        info!("Serving the HTTP API on {}", address);
+
+        let (sender, receiver) = watch::channel(None);
+        #[cfg(any(feature = "dbus-support", target_os = "windows"))]
+        track(config, sender);
+        // Without a music backend there's never anything playing
+        #[cfg(not(any(feature = "dbus-support", target_os = "windows")))]
+        drop(sender);
+
+        let handle = tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        tokio::spawn(handle_client(stream, receiver.clone()));
+                    }
+                    Err(e) => {
+                        error!("HTTP API failed: {}", e);
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(Self { handle })
+    }
+}
+
+impl Drop for ApiServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Follows the music player on a thread of its own and publishes what it's
+/// playing. The music source only runs while it's on the display, the API
+/// has to know what's playing all the time though. The players aren't
+/// necessarily `Send` which is why this doesn't simply spawn a task.
+#[cfg(any(feature = "dbus-support", target_os = "windows"))]
+fn track(config: &Config, sender: watch::Sender<Option<NowPlaying>>) {
+    // The same player the music source is showing
+    let backends = backend::configured(config);
+    let name = config.get_str("mpris2.preferred_player").ok();
+    let priority = backend::configured_priority(config);
+
+    let spawned = std::thread::Builder::new()
+        .name(String::from("api-tracker"))
+        .spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    error!("Can't follow the music player: {}", e);
+                    return;
+                }
+            };
+
+            runtime.block_on(async move {
+                let player = Player::auto(&backends).await.with_priority(priority);
+                let name = name.map(Arc::new);
+                let mut interval = time::interval(Duration::from_millis(POLL_INTERVAL));
+                interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+                // The server is gone once all receivers have been dropped
+                while !sender.is_closed() {
+                    sender.send_replace(None);
+                    let Ok(session) = player.wait_for_player(name.clone()).await else {
+                        interval.tick().await;
+                        continue;
+                    };
+
+                    while !sender.is_closed() {
+                        interval.tick().await;
+                        // The player went away, look for another one
+                        let Ok(progress) = session.progress().await else {
+                            break;
+                        };
+
+                        let metadata = &progress.metadata;
+                        let now_playing = NowPlaying {
+                            player: session.name().await,
+                            title: metadata.title().unwrap_or_default(),
+                            artists: metadata.artists().unwrap_or_default(),
+                            position: progress.position.max(0) as u64 / 1000,
+                            length: metadata.length().unwrap_or_default() / 1000,
+                            playing: matches!(progress.status, PlaybackStatus::Playing),
+                        };
+                        sender.send_if_modified(|current| {
+                            let modified = current.as_ref() != Some(&now_playing);
+                            *current = Some(now_playing);
+                            modified
+                        });
+                    }
+                }
+            });
+        });
+
+    if let Err(e) = spawned {
+        error!("Can't follow the music player: {}", e);
+    }
+}
+
+fn response(status: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: \
+         close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+/// Answers a single request, every connection is closed afterwards.
+async fn handle_client(stream: TcpStream, receiver: watch::Receiver<Option<NowPlaying>>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    let Ok(Some(request)) = lines.next_line().await else {
+        return;
+    };
+    // The headers don't matter to us but they have to be read before answering
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.is_empty() {
+            break;
+        }
+    }
+
+    let mut parts = request.split_whitespace();
+    let reply = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/nowplaying")) => {
+            let body =
+                serde_json::to_string(&*receiver.borrow()).unwrap_or_else(|_| String::from("null"));
+            response("200 OK", &body)
+        }
+        (Some(_), Some("/nowplaying")) => response("405 Method Not Allowed", "null"),
+        _ => response("404 Not Found", "null"),
+    };
+
+    let _ = writer.write_all(reply.as_bytes()).await;
+}
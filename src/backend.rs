@@ -0,0 +1,416 @@
+//! Picks the music backend at runtime.
+//!
+//! Every backend has its own types, this module wraps them up so the music
+//! source doesn't have to care which one it's talking to. Backends are tried
+//! in the configured order and if none of them can be constructed the
+//! [`NullPlayer`] is used, which simply never finds anything to play.
+use anyhow::{anyhow, Result};
+use apex_music::{AsyncPlayer, Metadata, PlaybackStatus, PlayerEvent, Progress};
+use config::Config;
+use futures::{future, stream, stream::LocalBoxStream, StreamExt};
+use log::{info, warn};
+use std::{convert::TryFrom, sync::Arc};
+
+/// The order backends are tried in unless configured otherwise
+pub const DEFAULT_BACKENDS: [Backend; 2] = [Backend::Mpris2, Backend::Windows];
+
+/// The music backends that can be chosen from in the config. Not every
+/// backend is available in every build.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Backend {
+    Mpris2,
+    Windows,
+    Null,
+}
+
+impl TryFrom<String> for Backend {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "mpris2" | "mpris" => Ok(Backend::Mpris2),
+            "windows" => Ok(Backend::Windows),
+            "null" | "none" => Ok(Backend::Null),
+            _ => Err(anyhow!("Unknown music backend: {}", value)),
+        }
+    }
+}
+
+/// Reads the backends to try from `mpris2.backends`, every source that
+/// follows the music player uses the same ones.
+pub fn configured(config: &Config) -> Vec<Backend> {
+    match config.get_array("mpris2.backends") {
+        Ok(backends) => backends
+            .into_iter()
+            .filter_map(|backend| {
+                backend
+                    .into_str()
+                    .map_err(anyhow::Error::from)
+                    .and_then(Backend::try_from)
+                    .map_err(|e| warn!("Skipping music backend: {}", e))
+                    .ok()
+            })
+            .collect(),
+        Err(_) => DEFAULT_BACKENDS.to_vec(),
+    }
+}
+
+/// The players to prefer in order, `mpris2.preferred_player` first and then
+/// `mpris2.player_priority`. Every source that follows the music player uses
+/// the same order.
+pub fn configured_priority(config: &Config) -> Vec<String> {
+    config
+        .get_str("mpris2.preferred_player")
+        .into_iter()
+        .chain(
+            config
+                .get_array("mpris2.player_priority")
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|name| name.into_str().ok()),
+        )
+        .collect()
+}
+
+/// A backend that never finds a player, used when nothing else works.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct NullPlayer;
+
+/// The backend that was chosen.
+pub enum Player {
+    #[cfg(all(feature = "dbus-support", target_os = "linux"))]
+    Mpris2(apex_mpris2::MPRIS2),
+    #[cfg(target_os = "windows")]
+    Windows(apex_windows::Player),
+    Null(NullPlayer),
+}
+
+impl std::fmt::Debug for Player {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Player {
+    /// Constructs the first backend in `backends` that works and falls back
+    /// to the [`NullPlayer`] if none of them do.
+    pub async fn auto(backends: &[Backend]) -> Self {
+        for backend in backends {
+            match Self::new(*backend).await {
+                Ok(player) => {
+                    info!("Using the {} music backend", player.name());
+                    return player;
+                }
+                Err(e) => warn!("Skipping the {:?} music backend: {}", backend, e),
+            }
+        }
+
+        warn!("No music backend is available, music won't be shown");
+        Player::Null(NullPlayer)
+    }
+
+    async fn new(backend: Backend) -> Result<Self> {
+        match backend {
+            #[cfg(all(feature = "dbus-support", target_os = "linux"))]
+            Backend::Mpris2 => Ok(Player::Mpris2(apex_mpris2::MPRIS2::new().await?)),
+            #[cfg(target_os = "windows")]
+            Backend::Windows => Ok(Player::Windows(apex_windows::Player::new()?)),
+            Backend::Null => Ok(Player::Null(NullPlayer)),
+            #[allow(unreachable_patterns)]
+            _ => Err(anyhow!("Not available in this build")),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            #[cfg(all(feature = "dbus-support", target_os = "linux"))]
+            Player::Mpris2(_) => "MPRIS2",
+            #[cfg(target_os = "windows")]
+            Player::Windows(_) => "Windows",
+            Player::Null(_) => "null",
+        }
+    }
+
+    /// Whether to fill in missing metadata from the tags of local files. This
+    /// only affects the MPRIS2 backend.
+    #[cfg(target_os = "linux")]
+    pub fn with_file_tags(self, read_file_tags: bool) -> Self {
+        match self {
+            Player::Mpris2(mpris) => Player::Mpris2(mpris.with_file_tags(read_file_tags)),
+            player @ Player::Null(_) => player,
+        }
+    }
+
+    /// The order players are preferred in, each entry is matched against
+    /// part of the app id. This only affects the Windows backend, MPRIS2 only
+    /// knows about a single preferred player.
+    #[allow(unused_variables, clippy::needless_pass_by_value)]
+    pub fn with_priority(self, priority: Vec<String>) -> Self {
+        match self {
+            #[cfg(target_os = "windows")]
+            Player::Windows(player) => Player::Windows(player.with_priority(priority)),
+            player => player,
+        }
+    }
+
+    /// The session the Windows backend picked, other threads can use it to
+    /// control the player that's displayed.
+    #[cfg(target_os = "windows")]
+    pub fn selected_session(&self) -> Option<apex_windows::SelectedSession> {
+        match self {
+            Player::Windows(player) => Some(player.selected()),
+            Player::Null(_) => None,
+        }
+    }
+
+    /// The events of all players known to the backend.
+    pub async fn stream(&self) -> Result<LocalBoxStream<'_, PlayerEvent>> {
+        Ok(match self {
+            #[cfg(all(feature = "dbus-support", target_os = "linux"))]
+            Player::Mpris2(mpris) => mpris.stream().await?.boxed_local(),
+            #[cfg(target_os = "windows")]
+            Player::Windows(player) => player.stream().await?.boxed_local(),
+            Player::Null(_) => stream::pending().boxed_local(),
+        })
+    }
+
+    /// Waits until a player shows up, preferring the one called `name`.
+    #[allow(unused_variables)]
+    pub async fn wait_for_player(&self, name: Option<Arc<String>>) -> Result<Session<'_>> {
+        match self {
+            #[cfg(all(feature = "dbus-support", target_os = "linux"))]
+            Player::Mpris2(mpris) => Ok(Session::Mpris2(mpris.wait_for_player(name).await?)),
+            // The Windows backend picks the session itself using the priority list
+            #[cfg(target_os = "windows")]
+            Player::Windows(player) => Ok(Session::Windows(player)),
+            Player::Null(_) => future::pending().await,
+        }
+    }
+}
+
+/// A player the backend found, whether it's displayed or not.
+#[derive(Debug)]
+pub struct Listing {
+    pub name: String,
+    /// Missing if the player wouldn't say
+    pub status: Option<PlaybackStatus>,
+    pub metadata: Option<TrackMetadata>,
+    /// Whether it's the player the music source would show
+    pub displayed: bool,
+}
+
+impl Player {
+    /// Every player the backend knows about right now. `name` is the
+    /// preferred player, just like for [`Player::wait_for_player`].
+    #[allow(unused_variables)]
+    pub async fn list(&self, name: Option<&str>) -> Result<Vec<Listing>> {
+        match self {
+            #[cfg(all(feature = "dbus-support", target_os = "linux"))]
+            Player::Mpris2(mpris) => {
+                let mut listings = Vec::new();
+                for player in mpris.players().await? {
+                    listings.push(Listing {
+                        name: player.name().await,
+                        status: player.playback_status().await.ok(),
+                        metadata: player.metadata().await.ok().map(TrackMetadata::Mpris2),
+                        displayed: false,
+                    });
+                }
+
+                // The same choice `wait_for_player` makes
+                let displayed = match name {
+                    Some(name) => listings
+                        .iter()
+                        .position(|player| player.name.contains(name)),
+                    None => listings.iter().position(|player| {
+                        matches!(
+                            player.status,
+                            Some(PlaybackStatus::Playing | PlaybackStatus::Paused)
+                        )
+                    }),
+                };
+                if let Some(listing) = displayed.and_then(|index| listings.get_mut(index)) {
+                    listing.displayed = true;
+                }
+                Ok(listings)
+            }
+            #[cfg(target_os = "windows")]
+            Player::Windows(player) => Ok(player
+                .list()
+                .await?
+                .into_iter()
+                .map(|(name, status, metadata, displayed)| Listing {
+                    name,
+                    status: Some(status),
+                    metadata: Some(TrackMetadata::Windows(metadata)),
+                    displayed,
+                })
+                .collect()),
+            Player::Null(_) => Ok(Vec::new()),
+        }
+    }
+}
+
+/// The player that is currently being displayed.
+pub enum Session<'a> {
+    #[cfg(all(feature = "dbus-support", target_os = "linux"))]
+    Mpris2(apex_mpris2::Player<'a>),
+    #[cfg(target_os = "windows")]
+    Windows(&'a apex_windows::Player),
+}
+
+impl Session<'_> {
+    pub async fn name(&self) -> String {
+        match self {
+            #[cfg(all(feature = "dbus-support", target_os = "linux"))]
+            Session::Mpris2(player) => player.name().await,
+            #[cfg(target_os = "windows")]
+            Session::Windows(player) => player.name().await,
+        }
+    }
+
+    pub async fn progress(&self) -> Result<Progress<TrackMetadata>> {
+        match self {
+            #[cfg(all(feature = "dbus-support", target_os = "linux"))]
+            Session::Mpris2(player) => {
+                let progress = player.progress().await?;
+                Ok(Progress {
+                    metadata: TrackMetadata::Mpris2(progress.metadata),
+                    position: progress.position,
+                    status: progress.status,
+                })
+            }
+            #[cfg(target_os = "windows")]
+            Session::Windows(player) => {
+                let progress = player.progress().await?;
+                Ok(Progress {
+                    metadata: TrackMetadata::Windows(progress.metadata),
+                    position: progress.position,
+                    status: progress.status,
+                })
+            }
+        }
+    }
+}
+
+impl Session<'_> {
+    /// Up to `n` tracks that are going to be played after the current one,
+    /// fails if the player doesn't say.
+    pub async fn next_tracks(&self, n: usize) -> Result<Vec<TrackMetadata>> {
+        match self {
+            #[cfg(all(feature = "dbus-support", target_os = "linux"))]
+            Session::Mpris2(player) => Ok(player
+                .next_tracks(n)
+                .await?
+                .into_iter()
+                .map(TrackMetadata::Mpris2)
+                .collect()),
+            #[cfg(target_os = "windows")]
+            Session::Windows(player) => Ok(player
+                .next_tracks(n)
+                .await?
+                .into_iter()
+                .map(TrackMetadata::Windows)
+                .collect()),
+        }
+    }
+}
+
+impl std::fmt::Debug for Session<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Session")
+    }
+}
+
+/// The metadata of whichever backend is in use.
+#[derive(Debug)]
+pub enum TrackMetadata {
+    #[cfg(all(feature = "dbus-support", target_os = "linux"))]
+    Mpris2(apex_mpris2::Metadata),
+    #[cfg(target_os = "windows")]
+    Windows(apex_windows::Metadata),
+}
+
+impl Metadata for TrackMetadata {
+    fn title(&self) -> Result<String> {
+        match self {
+            #[cfg(all(feature = "dbus-support", target_os = "linux"))]
+            TrackMetadata::Mpris2(metadata) => metadata.title(),
+            #[cfg(target_os = "windows")]
+            TrackMetadata::Windows(metadata) => metadata.title(),
+        }
+    }
+
+    fn artists(&self) -> Result<Vec<String>> {
+        match self {
+            #[cfg(all(feature = "dbus-support", target_os = "linux"))]
+            TrackMetadata::Mpris2(metadata) => metadata.artists(),
+            #[cfg(target_os = "windows")]
+            TrackMetadata::Windows(metadata) => metadata.artists(),
+        }
+    }
+
+    fn length(&self) -> Result<u64> {
+        match self {
+            #[cfg(all(feature = "dbus-support", target_os = "linux"))]
+            TrackMetadata::Mpris2(metadata) => metadata.length(),
+            #[cfg(target_os = "windows")]
+            TrackMetadata::Windows(metadata) => metadata.length(),
+        }
+    }
+
+    fn art_url(&self) -> Result<String> {
+        match self {
+            #[cfg(all(feature = "dbus-support", target_os = "linux"))]
+            TrackMetadata::Mpris2(metadata) => metadata.art_url(),
+            #[cfg(target_os = "windows")]
+            TrackMetadata::Windows(metadata) => metadata.art_url(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::{File, FileFormat};
+
+    #[test]
+    fn parses_backend_names() {
+        let parse = |name: &str| Backend::try_from(name.to_string()).ok();
+        assert_eq!(parse("mpris2"), Some(Backend::Mpris2));
+        assert_eq!(parse("MPRIS"), Some(Backend::Mpris2));
+        assert_eq!(parse("Windows"), Some(Backend::Windows));
+        assert_eq!(parse("none"), Some(Backend::Null));
+        assert_eq!(parse("null"), Some(Backend::Null));
+        assert_eq!(parse("spotify"), None);
+    }
+
+    #[test]
+    fn skips_unknown_backends() {
+        let mut config = Config::default();
+        config
+            .merge(File::from_str(
+                "mpris2.backends = [\"windows\", \"spotify\", \"null\"]",
+                FileFormat::Toml,
+            ))
+            .unwrap();
+        assert_eq!(configured(&config), [Backend::Windows, Backend::Null]);
+        assert_eq!(configured(&Config::default()), DEFAULT_BACKENDS);
+    }
+
+    #[test]
+    fn the_preferred_player_comes_first() {
+        let mut config = Config::default();
+        config
+            .merge(File::from_str(
+                "[mpris2]\npreferred_player = \"spotify\"\nplayer_priority = [\"vlc\", \"mpv\"]",
+                FileFormat::Toml,
+            ))
+            .unwrap();
+        assert_eq!(configured_priority(&config), ["spotify", "vlc", "mpv"]);
+        assert_eq!(
+            configured_priority(&Config::default()),
+            Vec::<String>::new()
+        );
+    }
+}
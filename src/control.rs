@@ -0,0 +1,239 @@
+use crate::{
+    instance,
+    providers::{badges, layer, pomodoro},
+    render::scheduler,
+    status::InstanceStatus,
+};
+use anyhow::{anyhow, Result};
+use apex_input::Command;
+use config::Config;
+use log::{error, info};
+use std::{convert::TryFrom, path::PathBuf};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+    sync::broadcast,
+    task::JoinHandle,
+};
+
+/// A request that was received over the control socket.
+///
+/// The protocol is line based, every line is one request and will be answered
+/// with either `ok` or `error: <reason>`, except for `status` which answers
+/// with a line of JSON. For example
+/// ```text
+/// $ echo "layer Gaming" | nc -U $XDG_RUNTIME_DIR/apex-tux.sock
+/// ok
+/// ```
+#[derive(Debug, Clone)]
+enum Request {
+    /// Forward a command to the scheduler
+    Command(Command),
+    /// Set or clear the name of the active keyboard layer
+    Layer(Option<String>),
+    /// Count a completed focus session, e.g. sent by a timer when it runs out
+    SessionCompleted,
+    /// Report what's on the display
+    Status,
+    /// Set the unread count of a service, e.g. `badge mail 3`
+    Badge(String, u32),
+}
+
+impl TryFrom<&str> for Request {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let (command, argument) = match value.split_once(' ') {
+            Some((command, argument)) => (command, Some(argument.trim())),
+            None => (value, None),
+        };
+
+        Ok(match (command, argument) {
+            ("next", None) => Request::Command(Command::NextSource),
+            ("previous", None) => Request::Command(Command::PreviousSource),
+            ("pin", None) => Request::Command(Command::Pin),
+            ("unpin", None) => Request::Command(Command::Unpin),
+            ("sleep", None) => Request::Command(Command::Sleep),
+            ("wake", None) => Request::Command(Command::Wake),
+            ("layer", Some("")) => return Err(anyhow!("Usage: layer [<name>]")),
+            ("layer", argument) => Request::Layer(argument.map(String::from)),
+            ("pomodoro", None) => Request::SessionCompleted,
+            ("status", None) => Request::Status,
+            ("badge", Some(argument)) => {
+                let (service, count) = argument
+                    .split_once(' ')
+                    .ok_or_else(|| anyhow!("Usage: badge <service> <count>"))?;
+                let count = count
+                    .trim()
+                    .parse()
+                    .map_err(|_| anyhow!("Not a count: {}", count))?;
+                Request::Badge(service.to_string(), count)
+            }
+            _ => return Err(anyhow!("Unknown command: {}", value)),
+        })
+    }
+}
+
+impl Request {
+    /// Carries out the request and returns the reply.
+    fn dispatch(self, sender: &broadcast::Sender<Command>) -> Result<String> {
+        match self {
+            Request::Command(command) => {
+                sender.send(command)?;
+            }
+            Request::Layer(name) => layer::set_layer(name),
+            Request::SessionCompleted => pomodoro::session_completed(),
+            Request::Badge(service, count) => badges::set_count(&service, count),
+            Request::Status => {
+                let state = scheduler::display_state();
+                return Ok(serde_json::to_string(&InstanceStatus {
+                    source: state.source.map(String::from),
+                    pinned: state.pinned,
+                    brightness: state.brightness,
+                    retries: state.writes.retries,
+                    reconnects: state.writes.reconnects,
+                })?);
+            }
+        }
+        Ok(String::from("ok"))
+    }
+}
+
+fn socket_path(config: &Config) -> Result<PathBuf> {
+    match config.get_str("control.path") {
+        Ok(path) => Ok(PathBuf::from(path)),
+        Err(_) => Ok(instance::runtime_dir()?.join("apex-tux.sock")),
+    }
+}
+
+/// Sends a single request to the running instance and returns its reply.
+pub async fn query(config: &Config, request: &str) -> Result<String> {
+    let path = socket_path(config)?;
+    let stream = UnixStream::connect(&path)
+        .await
+        .map_err(|e| anyhow!("Can't reach {}: {}", path.display(), e))?;
+    let (reader, mut writer) = stream.into_split();
+    writer
+        .write_all(format!("{}\n", request).as_bytes())
+        .await?;
+
+    let reply = BufReader::new(reader)
+        .lines()
+        .next_line()
+        .await?
+        .ok_or_else(|| anyhow!("The instance hung up without replying"))?;
+    match reply.strip_prefix("error: ") {
+        Some(e) => Err(anyhow!("{}", e)),
+        None => Ok(reply),
+    }
+}
+
+/// A UNIX socket that lets external tools control a running instance.
+#[derive(Debug)]
+pub struct ControlSocket {
+    path: PathBuf,
+    handle: JoinHandle<()>,
+}
+
+impl ControlSocket {
+    pub fn bind(config: &Config, sender: broadcast::Sender<Command>) -> Result<Self> {
+        let path = socket_path(config)?;
+
+        // An instance that didn't shut down properly may have left its socket behind,
+        // one that's still running answers though and keeps it
+        if path.exists() {
+            if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+                return Err(anyhow!(
+                    "Another instance is listening on {} already",
+                    path.display()
+                ));
+            }
+            std::fs::remove_file(&path)?;
+        }
+
+        let listener = UnixListener::bind(&path)?;
+        info!("Listening for commands on {}", path.display());
+
+        let handle = tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        tokio::spawn(handle_client(stream, sender.clone()));
+                    }
+                    Err(e) => {
+                        error!("Control socket failed: {}", e);
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(Self { path, handle })
+    }
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        self.handle.abort();
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+async fn handle_client(stream: UnixStream, sender: broadcast::Sender<Command>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        let reply = match Request::try_from(line.trim()).and_then(|r| r.dispatch(&sender)) {
+            Ok(reply) => format!("{}\n", reply),
+            Err(e) => format!("error: {}\n", e),
+        };
+
+        if writer.write_all(reply.as_bytes()).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::{File, FileFormat};
+
+    #[test]
+    fn layers_need_a_name_unless_cleared() {
+        assert!(matches!(
+            Request::try_from("layer Gaming"),
+            Ok(Request::Layer(Some(name))) if name == "Gaming"
+        ));
+        assert!(matches!(
+            Request::try_from("layer"),
+            Ok(Request::Layer(None))
+        ));
+        assert!(Request::try_from("layer ").is_err());
+    }
+
+    #[tokio::test]
+    async fn leaves_the_socket_of_a_running_instance_alone() {
+        let path = std::env::temp_dir().join(format!("apex-tux-test-{}.sock", std::process::id()));
+        let mut config = Config::default();
+        config
+            .merge(File::from_str(
+                &format!("control.path = {:?}", path.display().to_string()),
+                FileFormat::Toml,
+            ))
+            .unwrap();
+        let (sender, _commands) = broadcast::channel(1);
+
+        let running = ControlSocket::bind(&config, sender.clone()).unwrap();
+        assert!(ControlSocket::bind(&config, sender.clone()).is_err());
+        assert_eq!(query(&config, "pin").await.unwrap(), "ok");
+
+        drop(running);
+
+        // A socket that was left behind is replaced, nobody listens on it anymore
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+        assert!(ControlSocket::bind(&config, sender).is_ok());
+    }
+}
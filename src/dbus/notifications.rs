@@ -1,4 +1,5 @@
 use crate::{
+    providers::badges,
     render::{
         notifications::{Icon, Notification, NotificationBuilder, NotificationProvider},
         scheduler::NotificationWrapper,
@@ -161,6 +162,9 @@ impl NotificationProvider for Dbus {
                 if let NotificationType::Unsupported = &ty {
                     continue;
                 } else {
+                    if let NotificationType::Discord { .. } = &ty {
+                        badges::count_notification("discord");
+                    }
                     if let Ok(notif) = ty.render() {
                         yield notif;
                     }
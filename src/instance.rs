@@ -0,0 +1,123 @@
+use anyhow::{anyhow, Result};
+use config::Config;
+use fs2::FileExt;
+use std::{
+    env, fs,
+    fs::{File, OpenOptions},
+    io,
+    io::Write,
+    path::PathBuf,
+};
+
+/// Returns the directory for runtime files like sockets and lock files. That's
+/// `$XDG_RUNTIME_DIR` if it's set, which only the user can access, or a
+/// directory of our own in the temporary directory that's just as private.
+pub fn runtime_dir() -> Result<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_RUNTIME_DIR") {
+        return Ok(PathBuf::from(dir));
+    }
+    private_temp_dir()
+}
+
+#[cfg(unix)]
+fn private_temp_dir() -> Result<PathBuf> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+
+    let uid = unsafe { libc::getuid() };
+    let dir = env::temp_dir().join(format!("apex-tux-{}", uid));
+    match fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Err(e) if e.kind() != io::ErrorKind::AlreadyExists => return Err(e.into()),
+        _ => {}
+    }
+
+    // Anybody can create it before we do, it's only ours if nobody else can get in
+    let metadata = fs::symlink_metadata(&dir)?;
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o077 != 0 {
+        return Err(anyhow!(
+            "{} doesn't belong to us alone, set XDG_RUNTIME_DIR instead",
+            dir.display()
+        ));
+    }
+    Ok(dir)
+}
+
+/// The temporary directory already belongs to the user on Windows
+#[cfg(not(unix))]
+fn private_temp_dir() -> Result<PathBuf> {
+    Ok(env::temp_dir())
+}
+
+/// Returns the directory for files that have to survive a restart, e.g.
+/// `~/.local/share/apex-tux`.
+pub fn data_dir() -> PathBuf {
+    env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .unwrap_or_else(env::temp_dir)
+        .join("apex-tux")
+}
+
+/// Makes sure only one instance talks to the device at a time.
+///
+/// The lock is held for as long as this value is alive. If we crash the
+/// operating system releases it for us so there are no stale locks to clean
+/// up.
+#[derive(Debug)]
+pub struct InstanceLock {
+    _file: File,
+}
+
+impl InstanceLock {
+    pub fn acquire(config: &Config) -> Result<Self> {
+        let path = match config.get_str("general.lock_file") {
+            Ok(path) => PathBuf::from(path),
+            Err(_) => runtime_dir()?.join("apex-tux.lock"),
+        };
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            // The PID of the instance that holds the lock has to survive until it's ours
+            .truncate(false)
+            .open(&path)?;
+
+        if file.try_lock_exclusive().is_err() {
+            // The other instance writes its PID into the lock file, some platforms won't
+            // let us read it while it's locked though
+            let pid = fs::read_to_string(&path).unwrap_or_default();
+            let pid = match pid.trim() {
+                "" => String::from("unknown"),
+                pid => pid.to_string(),
+            };
+
+            return Err(anyhow!(
+                "Another instance is already running (PID: {})! Set `general.single_instance` to \
+                 false if you want to run multiple instances.",
+                pid
+            ));
+        }
+
+        file.set_len(0)?;
+        write!(file, "{}", std::process::id())?;
+
+        Ok(Self { _file: file })
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn the_fallback_runtime_dir_is_private() {
+        let dir = private_temp_dir().unwrap();
+        assert_eq!(
+            fs::metadata(&dir).unwrap().permissions().mode() & 0o777,
+            0o700
+        );
+        // It's fine to use it again once it's there
+        assert_eq!(private_temp_dir().unwrap(), dir);
+    }
+}
@@ -0,0 +1,81 @@
+//! Blanks the display while the session is locked.
+//!
+//! With `display.sleep_on_lock` the scheduler is put to sleep as soon as the
+//! session gets locked and woken up again once it's unlocked. On Linux this
+//! follows the `ActiveChanged` signal of the screen saver, which GNOME and KDE
+//! both send when locking the screen. On Windows it's the session
+//! notifications of the terminal services.
+use anyhow::Result;
+use apex_input::Command;
+use config::Config;
+#[cfg(target_os = "linux")]
+use dbus::{message::MatchRule, strings::Member, MessageType};
+#[cfg(target_os = "linux")]
+use dbus_tokio::connection;
+#[cfg(target_os = "linux")]
+use futures::StreamExt;
+use log::{info, warn};
+use tokio::sync::broadcast;
+
+/// Watches the session if that's enabled. Nothing happens otherwise.
+pub fn spawn(config: &Config, commands: broadcast::Sender<Command>) {
+    if !config.get_bool("display.sleep_on_lock").unwrap_or(false) {
+        return;
+    }
+
+    tokio::spawn(async move {
+        if let Err(e) = watch(commands).await {
+            warn!("Can't tell when the session is locked: {}", e);
+        }
+    });
+}
+
+fn forward(commands: &broadcast::Sender<Command>, locked: bool) {
+    let command = if locked {
+        info!("The session was locked");
+        Command::Sleep
+    } else {
+        info!("The session was unlocked");
+        Command::Wake
+    };
+    // This only fails once the scheduler is gone, i.e. while shutting down
+    let _ = commands.send(command);
+}
+
+#[cfg(target_os = "linux")]
+async fn watch(commands: broadcast::Sender<Command>) -> Result<()> {
+    let (resource, conn) = connection::new_session_sync()?;
+    tokio::spawn(async {
+        let err = resource.await;
+        warn!("Lost connection to D-Bus: {}", err);
+    });
+
+    // The interface differs between desktops, e.g. `org.freedesktop.ScreenSaver`
+    // and `org.gnome.ScreenSaver`, so it's checked below instead
+    let mut rule = MatchRule::new();
+    rule.msg_type = Some(MessageType::Signal);
+    rule.member = Some(Member::from("ActiveChanged"));
+    let (_incoming, mut signals) = conn.add_match(rule).await?.msg_stream();
+
+    info!("Sleeping while the session is locked");
+    while let Some(message) = signals.next().await {
+        let screensaver = message
+            .interface()
+            .is_some_and(|interface| interface.ends_with(".ScreenSaver"));
+        if let (true, Some(active)) = (screensaver, message.get1::<bool>()) {
+            forward(&commands, active);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+async fn watch(commands: broadcast::Sender<Command>) -> Result<()> {
+    let mut sessions = apex_windows::SessionLock::watch()?;
+
+    info!("Sleeping while the session is locked");
+    while let Some(locked) = sessions.recv().await {
+        forward(&commands, locked);
+    }
+    Ok(())
+}
@@ -28,14 +28,36 @@
 extern crate embedded_graphics;
 
 use anyhow::Result;
+use clap::{Parser, Subcommand};
 use log::warn;
 
+#[cfg(feature = "http")]
+mod api;
 // This is kind of pointless on non-Linux platforms
+#[cfg(any(feature = "dbus-support", target_os = "windows"))]
+mod backend;
+#[cfg(unix)]
+mod control;
 #[cfg(all(feature = "dbus-support", target_os = "linux"))]
 mod dbus;
 
+mod instance;
+#[cfg(any(
+    all(feature = "dbus-support", target_os = "linux"),
+    target_os = "windows"
+))]
+mod lock;
+#[cfg(target_os = "windows")]
+mod mediakeys;
+#[cfg(feature = "http")]
+mod net;
 mod providers;
 mod render;
+mod secrets;
+mod settings;
+#[cfg(feature = "stats")]
+mod stats;
+mod status;
 
 #[cfg(all(feature = "simulator", feature = "usb"))]
 compile_error!(
@@ -45,48 +67,184 @@ compile_error!(
 #[cfg(feature = "simulator")]
 use apex_simulator::Simulator;
 
-use crate::render::{scheduler, scheduler::Scheduler};
+use crate::{
+    instance::InstanceLock,
+    render::{
+        clock::SystemClock, iconpack::IconRegistry, scheduler, scheduler::Scheduler,
+        text::MissingGlyphs,
+    },
+};
 #[cfg(all(feature = "engine"))]
 use apex_engine::Engine;
 use apex_hardware::AsyncDevice;
+#[cfg(any(feature = "usb", feature = "engine"))]
+use apex_hardware::Orientation;
 #[cfg(all(feature = "usb", target_os = "linux", not(feature = "engine")))]
-use apex_hardware::USBDevice;
+use apex_hardware::{USBDevice, DEFAULT_RETRIES};
 use log::{info, LevelFilter};
 use simplelog::{Config as LoggerConfig, SimpleLogger};
 use tokio::sync::broadcast;
 
 use apex_input::Command;
 
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Opts {
+    #[command(subcommand)]
+    subcmd: Option<SubCommand>,
+}
+
+#[derive(Debug, Subcommand)]
+enum SubCommand {
+    /// Check `settings.toml` for mistakes
+    Config {
+        /// Print a fully commented example configuration instead
+        #[arg(long)]
+        example: bool,
+    },
+    /// Manage the credentials kept in the keyring of the OS
+    Secret {
+        #[command(subcommand)]
+        action: SecretAction,
+    },
+    /// List the music players that are open, the displayed one is marked
+    Players {
+        /// Print JSON for scripts instead
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show what the running instance is displaying
+    Status {
+        /// Print JSON for scripts instead
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum SecretAction {
+    /// Store a secret, it's read from the terminal so it doesn't end up in your
+    /// shell history
+    Set {
+        /// The name to refer to it by in settings.toml, e.g.
+        /// `apex-tux/openweather`
+        name: String,
+    },
+}
+
 #[tokio::main]
 #[allow(clippy::missing_errors_doc)]
 pub async fn main() -> Result<()> {
+    let opts: Opts = Opts::parse();
+
+    if let Some(SubCommand::Config { example: true }) = opts.subcmd {
+        print!("{}", settings::EXAMPLE);
+        return Ok(());
+    }
+
+    if let Some(SubCommand::Secret {
+        action: SecretAction::Set { name },
+    }) = &opts.subcmd
+    {
+        return secrets::store(name);
+    }
+
+    // Their output is meant for scripts, log messages would only get in the way
+    match &opts.subcmd {
+        Some(SubCommand::Players { json }) => {
+            return status::print_players(&settings::load()?, *json).await;
+        }
+        Some(SubCommand::Status { json }) => {
+            return status::print_status(&settings::load()?, *json).await;
+        }
+        _ => {}
+    }
+
     SimpleLogger::init(LevelFilter::Info, LoggerConfig::default())?;
 
+    let file = settings::load_file()?;
+    let problems = settings::validate(&file);
+    let settings = settings::with_environment(file)?;
+    if let Some(SubCommand::Config { .. }) = opts.subcmd {
+        if problems.is_empty() {
+            println!("No problems found in settings.toml");
+        }
+        for problem in problems {
+            println!("{}", problem);
+        }
+        return Ok(());
+    }
+    for problem in problems {
+        warn!("settings.toml: {}", problem);
+    }
+
+    // This has to happen before we touch the device, otherwise two instances will
+    // fight over it
+    let _lock = if settings.get_bool("general.single_instance").unwrap_or(true) {
+        Some(InstanceLock::acquire(&settings)?)
+    } else {
+        None
+    };
+
+    #[cfg(feature = "http")]
+    net::configure(&settings);
+    IconRegistry::load(&settings);
+    MissingGlyphs::configure(&settings);
+
+    #[cfg(any(feature = "usb", feature = "engine"))]
+    let orientation = orientation(&settings);
+
     // This channel is used to send commands to the scheduler
     let (tx, rx) = broadcast::channel::<Command>(100);
     #[cfg(all(feature = "usb", target_family = "unix", not(feature = "engine")))]
-    let mut device = USBDevice::try_connect()?;
+    let mut device = USBDevice::try_connect()?
+        .with_orientation(orientation)
+        .with_retries(
+            settings
+                .get_int("display.write_retries")
+                .map_or(DEFAULT_RETRIES, |retries| retries.max(0) as u32),
+        );
 
     #[cfg(any(feature = "usb", feature = "engine"))]
     let hkm = apex_input::InputManager::new(tx.clone());
 
-    #[cfg(all(feature = "engine"))]
-    let mut device = Engine::new().await?;
+    #[cfg(feature = "engine")]
+    let mut device = Engine::new().await?.with_orientation(orientation);
 
-    let mut settings = config::Config::default();
-    settings
-        // Add in `./settings.toml`
-        .merge(config::File::with_name("settings"))?
-        // Add in settings from the environment (with a prefix of APEX)
-        // Eg.. `APEX_DEBUG=1 ./target/app` would set the `debug` key
-        .merge(config::Environment::with_prefix("APEX_"))?;
+    #[cfg(unix)]
+    let _control = if settings.get_bool("control.enabled").unwrap_or(true) {
+        control::ControlSocket::bind(&settings, tx.clone())
+            .map_err(|e| warn!("Failed to set up the control socket: {}", e))
+            .ok()
+    } else {
+        None
+    };
+
+    #[cfg(feature = "http")]
+    let _api = if settings.get_bool("api.enabled").unwrap_or(false) {
+        api::ApiServer::bind(&settings)
+            .await
+            .map_err(|e| warn!("Failed to set up the HTTP API: {}", e))
+            .ok()
+    } else {
+        None
+    };
+
+    #[cfg(target_os = "windows")]
+    mediakeys::spawn(&settings);
+
+    #[cfg(any(
+        all(feature = "dbus-support", target_os = "linux"),
+        target_os = "windows"
+    ))]
+    lock::spawn(&settings, tx.clone());
 
     #[cfg(feature = "simulator")]
     let mut device = Simulator::connect(tx.clone());
 
     device.clear().await?;
 
-    let mut scheduler = Scheduler::new(device);
+    let mut scheduler = Scheduler::new(device, SystemClock::shared());
 
     ctrlc::set_handler(move || {
         info!("Ctrl + C received, shutting down!");
@@ -99,3 +257,16 @@ pub async fn main() -> Result<()> {
     drop(hkm);
     Ok(())
 }
+
+/// How the display is mounted, upright unless `display.orientation` says
+/// otherwise.
+#[cfg(any(feature = "usb", feature = "engine"))]
+fn orientation(settings: &config::Config) -> Orientation {
+    settings
+        .get_int("display.orientation")
+        .map_or(Ok(Orientation::default()), Orientation::try_from)
+        .unwrap_or_else(|e| {
+            warn!("{}", e);
+            Orientation::default()
+        })
+}
@@ -0,0 +1,76 @@
+//! Sends the media keys to the player that's on the display.
+//!
+//! Windows hands the media keys to whichever session it considers current,
+//! which isn't necessarily the one the music source is showing. With
+//! `mediakeys.enabled` the keys are caught by a keyboard hook and sent to the
+//! session the music source picked.
+use anyhow::anyhow;
+use apex_windows::{MediaKey, MediaKeys, SelectedSession};
+use config::Config;
+use lazy_static::lazy_static;
+use log::{error, info, warn};
+use std::sync::Mutex;
+use tokio::sync::mpsc;
+
+lazy_static! {
+    static ref DISPLAYED: Mutex<Option<SelectedSession>> = Mutex::new(None);
+}
+
+/// Called by the music source once it has a player, the keys go to whichever
+/// session it picks from then on.
+pub fn follow(session: SelectedSession) {
+    *DISPLAYED.lock().unwrap() = Some(session);
+}
+
+/// Hooks the media keys if they're enabled. Nothing happens otherwise.
+pub fn spawn(config: &Config) {
+    if !config.get_bool("mediakeys.enabled").unwrap_or(false) {
+        return;
+    }
+
+    let passthrough = config.get_bool("mediakeys.passthrough").unwrap_or(false);
+    let keys = match MediaKeys::install(passthrough) {
+        Ok(keys) => keys,
+        Err(e) => {
+            warn!("Failed to set up the media keys: {}", e);
+            return;
+        }
+    };
+
+    // The futures of WinRT operations aren't necessarily `Send`, so this gets a
+    // thread of its own
+    let spawned = std::thread::Builder::new()
+        .name(String::from("media-keys-router"))
+        .spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    error!("Can't route the media keys: {}", e);
+                    return;
+                }
+            };
+
+            runtime.block_on(route(keys));
+        });
+
+    match spawned {
+        Ok(_) => info!("Media keys are sent to the displayed player"),
+        Err(e) => error!("Can't route the media keys: {}", e),
+    }
+}
+
+async fn route(mut keys: mpsc::UnboundedReceiver<MediaKey>) {
+    while let Some(key) = keys.recv().await {
+        let session = DISPLAYED.lock().unwrap().clone();
+        let result = match session {
+            Some(session) => session.press(key).await,
+            None => Err(anyhow!("The music source isn't running")),
+        };
+        if let Err(e) = result {
+            warn!("Couldn't send {:?} to the player: {}", key, e);
+        }
+    }
+}
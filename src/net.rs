@@ -0,0 +1,154 @@
+use crate::render::clock::SharedClock;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use config::Config;
+use lazy_static::lazy_static;
+use log::warn;
+use reqwest::{header, Client, ClientBuilder, RequestBuilder, Response, StatusCode};
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+};
+use tokio::{
+    sync::Semaphore,
+    time,
+    time::{Duration, Instant, Interval, MissedTickBehavior},
+};
+
+static APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"),);
+
+/// How many requests may be in flight at the same time unless configured
+/// otherwise
+const DEFAULT_MAX_CONCURRENT: usize = 2;
+/// The maximum amount of milliseconds a source's first request is delayed by
+/// unless configured otherwise
+const DEFAULT_JITTER: u64 = 5000;
+/// How long a source is paused if it's rate limited without telling us for
+/// how long
+const DEFAULT_RETRY_AFTER: u64 = 60;
+
+static MAX_CONCURRENT: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_CONCURRENT);
+static JITTER: AtomicU64 = AtomicU64::new(DEFAULT_JITTER);
+
+lazy_static! {
+    static ref CLIENT: Client = ClientBuilder::new()
+        .user_agent(APP_USER_AGENT)
+        .build()
+        .expect("Failed to build the HTTP client!");
+    static ref BUDGET: Semaphore = Semaphore::new(MAX_CONCURRENT.load(Ordering::SeqCst));
+}
+
+/// Reads `net.max_concurrent` and `net.jitter` from the config. This has to
+/// be called before the first request is made.
+pub fn configure(config: &Config) {
+    if let Ok(max) = config.get_int("net.max_concurrent") {
+        MAX_CONCURRENT.store(max.max(1) as usize, Ordering::SeqCst);
+    }
+    if let Ok(jitter) = config.get_int("net.jitter") {
+        JITTER.store(jitter.max(0) as u64, Ordering::SeqCst);
+    }
+}
+
+/// The HTTP client that's shared by all networked sources.
+pub fn client() -> &'static Client {
+    &CLIENT
+}
+
+/// Returns a random delay between zero and the configured jitter.
+fn jitter() -> Duration {
+    let max = JITTER.load(Ordering::SeqCst);
+    if max == 0 {
+        return Duration::ZERO;
+    }
+    // `RandomState` is seeded randomly which is plenty for spreading out a few
+    // requests
+    let random = RandomState::new().build_hasher().finish();
+    Duration::from_millis(random % max)
+}
+
+/// Creates an interval for refetching data every `period`. The first tick is
+/// delayed by a random amount so sources that are enabled together don't all
+/// hit the network at the same time on startup.
+pub fn interval(period: Duration, clock: &SharedClock) -> Interval {
+    let mut interval = time::interval_at(clock.now() + jitter(), period);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    interval
+}
+
+/// Parses a `Retry-After` header which is either a number of seconds or a
+/// HTTP date.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(header::RETRY_AFTER)?.to_str().ok()?;
+
+    if let Ok(seconds) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    let seconds = (date.with_timezone(&Utc) - Utc::now()).num_seconds().max(0);
+    Some(Duration::from_secs(seconds as u64))
+}
+
+/// Sends requests on behalf of a single source while sticking to the shared
+/// request budget.
+///
+/// If the server tells us to slow down the source is paused for as long as
+/// requested, other sources aren't affected by this.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    name: &'static str,
+    paused_until: Option<Instant>,
+    clock: SharedClock,
+}
+
+impl RateLimiter {
+    pub fn new(name: &'static str, clock: SharedClock) -> Self {
+        Self {
+            name,
+            paused_until: None,
+            clock,
+        }
+    }
+
+    /// Sends the request once there's room in the request budget. Requests
+    /// fail right away while the source is paused.
+    pub async fn send(&mut self, request: RequestBuilder) -> Result<Response> {
+        if let Some(until) = self.paused_until {
+            let now = self.clock.now();
+            if until > now {
+                return Err(anyhow!(
+                    "{} is rate limited for another {}s",
+                    self.name,
+                    (until - now).as_secs()
+                ));
+            }
+            self.paused_until = None;
+        }
+
+        let response = {
+            let _permit = BUDGET.acquire().await?;
+            request.send().await?
+        };
+
+        let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE {
+            let pause = retry_after(&response).or_else(|| {
+                // A 503 without `Retry-After` is most likely just an outage
+                (status == StatusCode::TOO_MANY_REQUESTS)
+                    .then(|| Duration::from_secs(DEFAULT_RETRY_AFTER))
+            });
+
+            if let Some(pause) = pause {
+                warn!(
+                    "{} is being rate limited, pausing it for {}s",
+                    self.name,
+                    pause.as_secs()
+                );
+                self.paused_until = Some(self.clock.now() + pause);
+            }
+        }
+
+        Ok(response.error_for_status()?)
+    }
+}
@@ -0,0 +1,285 @@
+use crate::render::{
+    clock::SharedClock,
+    display::{Content, ContentProvider},
+    glyph::Glyph,
+    iconpack,
+    scheduler::{ContentWrapper, CONTENT_PROVIDERS, TICK_LENGTH},
+};
+use anyhow::Result;
+use apex_hardware::FrameBuffer;
+use async_stream::try_stream;
+use config::Config;
+use embedded_graphics::{
+    geometry::{Point, Size},
+    mono_font::{ascii, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    text::{renderer::TextRenderer, Baseline, Text},
+    Drawable,
+};
+use futures::Stream;
+use lazy_static::lazy_static;
+use linkme::distributed_slice;
+use log::{info, warn};
+use std::collections::HashMap;
+use tokio::{
+    sync::watch,
+    time,
+    time::{Duration, MissedTickBehavior},
+};
+
+type Badges = HashMap<String, Badge>;
+
+lazy_static! {
+    // The unread counts by service. Counts are either pushed by external tools via the
+    // control socket, e.g. a mail checker, or counted from the notifications we see.
+    static ref BADGES: (watch::Sender<Badges>, watch::Receiver<Badges>) =
+        watch::channel(HashMap::new());
+}
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+struct Badge {
+    count: u32,
+    /// Counted from notifications, nobody tells us when those were read so
+    /// they're cleared once they were on the display for a bit
+    counted: bool,
+}
+
+/// Sets the unread count of a service, e.g. the number of unread mails.
+pub fn set_count(service: &str, count: u32) {
+    BADGES.0.send_modify(|badges| {
+        badges.insert(
+            service.to_lowercase(),
+            Badge {
+                count,
+                counted: false,
+            },
+        );
+    });
+}
+
+/// Counts another notification of a service.
+#[cfg(all(feature = "dbus-support", target_os = "linux"))]
+pub fn count_notification(service: &str) {
+    BADGES.0.send_modify(|badges| {
+        let badge = badges.entry(service.to_lowercase()).or_default();
+        badge.count = badge.count.saturating_add(1);
+        badge.counted = true;
+    });
+}
+
+/// Clears the counts that came from notifications.
+fn clear_seen() {
+    BADGES.0.send_if_modified(|badges| {
+        let mut changed = false;
+        for badge in badges.values_mut().filter(|badge| badge.counted) {
+            changed |= badge.count != 0;
+            badge.count = 0;
+        }
+        changed
+    });
+}
+
+#[doc(hidden)]
+#[distributed_slice(CONTENT_PROVIDERS)]
+pub static PROVIDER_INIT: fn(&Config, &SharedClock) -> Result<Box<dyn ContentWrapper>> =
+    register_callback;
+
+/// More badges don't fit next to each other with a three digit count
+const MAX_SERVICES: usize = 5;
+/// How long counted notifications have to be on the display before they
+/// count as read
+const SEEN_AFTER: Duration = Duration::from_secs(3);
+
+const MAIL_GLYPH: Glyph = Glyph::new(
+    &[
+        0b1111_1111,
+        0b1100_0011,
+        0b1010_0101,
+        0b1001_1001,
+        0b1000_0001,
+        0b1000_0001,
+        0b1111_1111,
+    ],
+    8,
+);
+
+const BELL_GLYPH: Glyph = Glyph::new(
+    &[
+        0b0001_1000,
+        0b0011_1100,
+        0b0111_1110,
+        0b0111_1110,
+        0b0111_1110,
+        0b1111_1111,
+        0b0000_0000,
+        0b0001_1000,
+    ],
+    8,
+);
+
+const BUBBLE_GLYPH: Glyph = Glyph::new(
+    &[
+        0b0111_1110,
+        0b1111_1111,
+        0b1111_1111,
+        0b1111_1111,
+        0b0111_1110,
+        0b0011_0000,
+        0b0010_0000,
+    ],
+    8,
+);
+
+#[doc(hidden)]
+#[allow(clippy::unnecessary_wraps)]
+fn register_callback(config: &Config, clock: &SharedClock) -> Result<Box<dyn ContentWrapper>> {
+    info!("Registering badges display source.");
+
+    let mut services = config
+        .get_array("badges.services")
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|service| {
+            service
+                .into_str()
+                .map_err(|e| warn!("Skipping badge: {}", e))
+                .ok()
+        })
+        .map(|service| service.to_lowercase())
+        .collect::<Vec<_>>();
+    if services.len() > MAX_SERVICES {
+        warn!("Only {} badges fit onto the display", MAX_SERVICES);
+        services.truncate(MAX_SERVICES);
+    }
+
+    Ok(Box::new(BadgesSource {
+        services,
+        hide_empty: config.get_bool("badges.hide_empty").unwrap_or(false),
+        clock: clock.clone(),
+    }))
+}
+
+fn centered(buffer: &mut FrameBuffer, text: &str, center: i32, y: i32) -> Result<()> {
+    let style = MonoTextStyle::new(&ascii::FONT_8X13_BOLD, BinaryColor::On);
+    let metrics = style.measure_string(text, Point::zero(), Baseline::Top);
+    let x = center - (metrics.bounding_box.size.width / 2) as i32;
+    Text::with_baseline(text, Point::new(x, y), style, Baseline::Top).draw(buffer)?;
+    Ok(())
+}
+
+/// Draws the icon of a service centered on `center`. Services we don't have
+/// an icon for get the start of their name instead.
+fn draw_icon(buffer: &mut FrameBuffer, service: &str, center: i32) -> Result<()> {
+    let position = Point::new(center - 4, 6);
+    if let Some(icon) = iconpack::registry().get(service, Size::new(8, 8)) {
+        icon.draw_at(position, buffer)?;
+        return Ok(());
+    }
+
+    let glyph = match service {
+        "mail" | "email" => MAIL_GLYPH,
+        "github" => BELL_GLYPH,
+        "discord" | "chat" => BUBBLE_GLYPH,
+        _ => {
+            let style = MonoTextStyle::new(&ascii::FONT_6X10, BinaryColor::On);
+            let name = service.chars().take(4).collect::<String>();
+            let metrics = style.measure_string(&name, Point::zero(), Baseline::Top);
+            let x = center - (metrics.bounding_box.size.width / 2) as i32;
+            Text::with_baseline(&name, Point::new(x, 5), style, Baseline::Top).draw(buffer)?;
+            return Ok(());
+        }
+    };
+    glyph.at(position).draw(buffer)?;
+    Ok(())
+}
+
+/// A row of unread counts from several services at once.
+#[derive(Debug, Clone)]
+struct BadgesSource {
+    /// The services in the order they're shown
+    services: Vec<String>,
+    /// Whether services without anything unread are left out
+    hide_empty: bool,
+    clock: SharedClock,
+}
+
+impl BadgesSource {
+    fn render(&self, badges: &Badges) -> Result<FrameBuffer> {
+        let mut buffer = FrameBuffer::new();
+        let shown = self
+            .services
+            .iter()
+            .map(|service| (service, badges.get(service).map_or(0, |badge| badge.count)))
+            .filter(|(_, count)| !self.hide_empty || *count > 0)
+            .collect::<Vec<_>>();
+
+        if shown.is_empty() {
+            centered(&mut buffer, "All caught up", 128 / 2, (40 - 13) / 2)?;
+            return Ok(buffer);
+        }
+
+        let width = 128 / shown.len() as i32;
+        for (i, (service, count)) in shown.into_iter().enumerate() {
+            let center = width * i as i32 + width / 2;
+            draw_icon(&mut buffer, service, center)?;
+            let count = if count > 99 {
+                String::from("99+")
+            } else {
+                count.to_string()
+            };
+            centered(&mut buffer, &count, center, 20)?;
+        }
+
+        Ok(buffer)
+    }
+}
+
+impl ContentProvider for BadgesSource {
+    type ContentStream<'a> = impl Stream<Item = Result<Content>> + 'a;
+
+    // This needs to be enabled until full GAT support is here
+    #[allow(clippy::needless_lifetimes)]
+    fn stream<'this>(&'this mut self) -> Result<Self::ContentStream<'this>> {
+        let tick = Duration::from_millis(TICK_LENGTH as u64);
+        let mut interval = time::interval(tick);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut badges = BADGES.1.clone();
+
+        Ok(try_stream! {
+            let mut image = Some(self.render(&badges.borrow_and_update())?);
+            let mut shown_since = self.clock.now();
+            let mut last_tick = self.clock.now();
+
+            loop {
+                // `?` doesn't work within `select!` so it only picks what to do
+                let changed = tokio::select! {
+                    _ = interval.tick() => false,
+                    Ok(()) = badges.changed() => true,
+                };
+
+                if changed {
+                    // New counts get their full time on the display as well
+                    shown_since = self.clock.now();
+                    image = Some(self.render(&badges.borrow())?);
+                } else {
+                    // We aren't polled while another source is on the display, a gap
+                    // means we just came back
+                    let now = self.clock.now();
+                    if now - last_tick > tick * 4 {
+                        shown_since = now;
+                    }
+                    last_tick = now;
+
+                    if now - shown_since >= SEEN_AFTER {
+                        clear_seen();
+                    }
+                    yield image.take().map_or(Content::Unchanged, Content::Frame);
+                }
+            }
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "badges"
+    }
+}
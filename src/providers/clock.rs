@@ -1,14 +1,19 @@
 use crate::{
-    render::{display::ContentProvider, scheduler::ContentWrapper},
+    render::{
+        clock::SharedClock,
+        display::{Content, ContentProvider},
+        scheduler::ContentWrapper,
+        segments::SevenSegment,
+    },
     scheduler::CONTENT_PROVIDERS,
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use apex_hardware::FrameBuffer;
 use async_stream::try_stream;
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, Timelike};
 use config::Config;
 use embedded_graphics::{
-    geometry::Point,
+    geometry::{Point, Size},
     mono_font::{ascii, MonoTextStyle},
     pixelcolor::BinaryColor,
     text::{renderer::TextRenderer, Baseline, Text},
@@ -16,7 +21,8 @@ use embedded_graphics::{
 };
 use futures::Stream;
 use linkme::distributed_slice;
-use log::info;
+use log::{info, warn};
+use std::convert::TryFrom;
 use tokio::{
     time,
     time::{Duration, MissedTickBehavior},
@@ -24,7 +30,8 @@ use tokio::{
 
 #[doc(hidden)]
 #[distributed_slice(CONTENT_PROVIDERS)]
-pub static PROVIDER_INIT: fn(&Config) -> Result<Box<dyn ContentWrapper>> = register_callback;
+pub static PROVIDER_INIT: fn(&Config, &SharedClock) -> Result<Box<dyn ContentWrapper>> =
+    register_callback;
 
 #[derive(Debug, Copy, Clone)]
 /// Represents the options a user can choose for the clock format
@@ -37,9 +44,33 @@ enum ClockFormat {
     Locale,
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+/// How the time is drawn
+enum ClockStyle {
+    /// Regular text along with the seconds
+    Text,
+    /// Large seven segment digits that fill the display, without the seconds
+    Segments,
+}
+
+impl TryFrom<String> for ClockStyle {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "text" => Ok(ClockStyle::Text),
+            "segments" => Ok(ClockStyle::Segments),
+            _ => Err(anyhow!("Unknown clock style: {}", value)),
+        }
+    }
+}
+
+/// Digits as tall as the display with a bit of room at the top and bottom
+const SEGMENTS: SevenSegment = SevenSegment::new(Size::new(22, 36), 4, 4);
+
 #[doc(hidden)]
 #[allow(clippy::unnecessary_wraps)]
-fn register_callback(config: &Config) -> Result<Box<dyn ContentWrapper>> {
+fn register_callback(config: &Config, clock: &SharedClock) -> Result<Box<dyn ContentWrapper>> {
     info!("Registering Clock display source.");
 
     let clock_format = match config.get_bool("clock.twelve_hour") {
@@ -47,17 +78,58 @@ fn register_callback(config: &Config) -> Result<Box<dyn ContentWrapper>> {
         Ok(false) => ClockFormat::TwentyFour,
         _ => ClockFormat::Locale,
     };
+    let style = config
+        .get_str("clock.style")
+        .map_or(Ok(ClockStyle::Text), ClockStyle::try_from)
+        .unwrap_or_else(|e| {
+            warn!("{}", e);
+            ClockStyle::Text
+        });
+    let blink_colon = config.get_bool("clock.blink_colon").unwrap_or(false);
 
-    Ok(Box::new(Clock { clock_format }))
+    Ok(Box::new(Clock {
+        clock_format,
+        style,
+        blink_colon,
+        clock: clock.clone(),
+    }))
 }
 
 pub struct Clock {
     clock_format: ClockFormat,
+    style: ClockStyle,
+    /// Whether the colon between the large digits blinks with the seconds
+    blink_colon: bool,
+    clock: SharedClock,
 }
 
 impl Clock {
+    /// The hours and minutes in large digits, there's no room for anything
+    /// else. Locales don't get a say here as the digits can't show AM or PM.
+    fn render_segments(&self, local: DateTime<Local>) -> Result<FrameBuffer> {
+        let hours = match self.clock_format {
+            ClockFormat::Twelve => local.format("%I"),
+            ClockFormat::TwentyFour | ClockFormat::Locale => local.format("%H"),
+        };
+        let colon = if self.blink_colon && local.second() % 2 == 1 {
+            ' '
+        } else {
+            ':'
+        };
+        let text = format!("{}{}{}", hours, colon, local.format("%M"));
+
+        let mut buffer = FrameBuffer::new();
+        let x = (128 - SEGMENTS.width(&text) as i32) / 2;
+        SEGMENTS.draw(&text, Point::new(x, 2), &mut buffer)?;
+        Ok(buffer)
+    }
+
     pub fn render(&self) -> Result<FrameBuffer> {
-        let local: DateTime<Local> = Local::now();
+        let local: DateTime<Local> = self.clock.local();
+        if self.style == ClockStyle::Segments {
+            return self.render_segments(local);
+        }
+
         let format_string = match self.clock_format {
             ClockFormat::Twelve => "%I:%M:%S %p",
             ClockFormat::TwentyFour => "%H:%M:%S",
@@ -84,7 +156,7 @@ impl Clock {
 }
 
 impl ContentProvider for Clock {
-    type ContentStream<'a> = impl Stream<Item = Result<FrameBuffer>> + 'a;
+    type ContentStream<'a> = impl Stream<Item = Result<Content>> + 'a;
 
     // This needs to be enabled until full GAT support is here
     #[allow(clippy::needless_lifetimes)]
@@ -94,7 +166,7 @@ impl ContentProvider for Clock {
         Ok(try_stream! {
             loop {
                 if let Ok(image) = self.render() {
-                    yield image;
+                    yield Content::Frame(image);
                 }
                 interval.tick().await;
             }
@@ -105,3 +177,25 @@ impl ContentProvider for Clock {
         "clock"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{clock::MockClock, golden};
+    use chrono::{NaiveDate, TimeZone};
+
+    #[test]
+    fn renders_the_time() {
+        let wall = NaiveDate::from_ymd_opt(2023, 4, 5)
+            .and_then(|date| date.and_hms_opt(12, 34, 56))
+            .unwrap();
+        let clock = Clock {
+            clock_format: ClockFormat::TwentyFour,
+            style: ClockStyle::Text,
+            blink_colon: false,
+            clock: MockClock::new(Local.from_local_datetime(&wall).unwrap()).shared(),
+        };
+
+        golden::assert_renders_to(&clock.render().unwrap(), "clock.png");
+    }
+}
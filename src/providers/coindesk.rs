@@ -1,6 +1,12 @@
-use crate::render::{
-    display::ContentProvider,
-    scheduler::{ContentWrapper, CONTENT_PROVIDERS},
+use crate::{
+    net,
+    net::RateLimiter,
+    render::{
+        clock::SharedClock,
+        display::{Content, ContentProvider},
+        scheduler::{ContentWrapper, CONTENT_PROVIDERS},
+        stale::{StaleMarker, Staleness},
+    },
 };
 use anyhow::{anyhow, Result};
 use apex_hardware::FrameBuffer;
@@ -19,7 +25,7 @@ use futures::Stream;
 use lazy_static::lazy_static;
 use linkme::distributed_slice;
 use log::info;
-use reqwest::{header, Client, ClientBuilder};
+use reqwest::header;
 use serde::{Deserialize, Serialize};
 use std::{convert::TryFrom, time::Duration};
 use tinybmp::Bmp;
@@ -33,7 +39,8 @@ lazy_static! {
 }
 
 #[distributed_slice(CONTENT_PROVIDERS)]
-pub static PROVIDER_INIT: fn(&Config) -> Result<Box<dyn ContentWrapper>> = register_callback;
+pub static PROVIDER_INIT: fn(&Config, &SharedClock) -> Result<Box<dyn ContentWrapper>> =
+    register_callback;
 
 #[derive(Debug, Copy, Clone)]
 pub enum Target {
@@ -72,18 +79,27 @@ impl Target {
 }
 
 #[allow(clippy::unnecessary_wraps)]
-fn register_callback(config: &Config) -> Result<Box<dyn ContentWrapper>> {
+fn register_callback(config: &Config, clock: &SharedClock) -> Result<Box<dyn ContentWrapper>> {
     info!("Registering Coindesk display source.");
     let currency = config
-        .get_str("crypto.currency")
+        .get_str("coindesk.currency")
         .unwrap_or_else(|_| String::from("USD"));
     let currency = Target::try_from(currency).unwrap_or_default();
-    Ok(Box::new(Coindesk::new(currency)?))
+    let stale_after = config
+        .get_int("coindesk.stale_after")
+        .map_or(DEFAULT_STALE_AFTER, |seconds| seconds as u64);
+    Ok(Box::new(Coindesk::new(
+        currency,
+        Duration::from_secs(stale_after),
+        clock.clone(),
+    )))
 }
 
 const COINDESK_URL: &str = "https://api.coindesk.com/v1/bpi/currentprice.json";
 
-static APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"),);
+/// How many seconds may pass without a successful fetch before the price is
+/// marked as stale
+const DEFAULT_STALE_AFTER: u64 = 5 * 60;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Currency {
@@ -142,49 +158,41 @@ impl Status {
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 struct Coindesk {
-    client: Client,
+    net: RateLimiter,
     target: Target,
+    stale_after: Duration,
+    clock: SharedClock,
 }
 
 impl Coindesk {
-    pub fn new(target: Target) -> Result<Self> {
-        let mut headers = header::HeaderMap::new();
-        headers.insert(
-            header::CONTENT_TYPE,
-            header::HeaderValue::from_static("application/json"),
-        );
-        Ok(Coindesk {
-            client: ClientBuilder::new()
-                .user_agent(APP_USER_AGENT)
-                .default_headers(headers)
-                .build()?,
+    pub fn new(target: Target, stale_after: Duration, clock: SharedClock) -> Self {
+        Coindesk {
+            net: RateLimiter::new("Coindesk", clock.clone()),
             target,
-        })
+            stale_after,
+            clock,
+        }
     }
 
-    pub async fn fetch(&self) -> Result<Status> {
-        let status = self
-            .client
+    pub async fn fetch(&mut self) -> Result<Status> {
+        let request = net::client()
             .get(COINDESK_URL)
-            .send()
-            .await?
-            .json::<Status>()
-            .await?;
+            .header(header::CONTENT_TYPE, "application/json");
+        let status = self.net.send(request).await?.json::<Status>().await?;
 
         Ok(status)
     }
 }
 
 impl ContentProvider for Coindesk {
-    type ContentStream<'a> = impl Stream<Item = Result<FrameBuffer>> + 'a;
+    type ContentStream<'a> = impl Stream<Item = Result<Content>> + 'a;
 
     #[allow(clippy::needless_lifetimes)]
     fn stream<'this>(&'this mut self) -> Result<Self::ContentStream<'this>> {
         // Coindesk updates its data every minute so we only need to fetch every minute
-        let mut refetch = time::interval(Duration::from_secs(60));
-        refetch.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut refetch = net::interval(Duration::from_secs(60), &self.clock);
 
         // The scheduler expect a new image every so often so if no image is delivered
         // it'll just display a black image until the refetch timer ran.
@@ -194,21 +202,29 @@ impl ContentProvider for Coindesk {
         // We need some sort of synchronization between the task that displays the data
         // and the task that fetches it
         let status = RwLock::new(FrameBuffer::new());
+        let mut staleness = Staleness::new(self.clock.clone());
 
         Ok(try_stream! {
             loop {
-                tokio::select! {
-                    _ = render.tick() => {
-                        let buffer = status.read().await;
-                        yield *buffer;
-                    },
-                    _ = refetch.tick() => {
-                        let data = self.fetch().await.and_then(|d| d.render(self.target));
-                        let mut buffer = status.write().await;
-                        if let Ok(data) = data {
-                            *buffer = data;
-                        }
+                // `?` doesn't work within `select!` so it only picks what to do
+                let refetched = tokio::select! {
+                    _ = render.tick() => false,
+                    _ = refetch.tick() => true,
+                };
+
+                if refetched {
+                    let data = self.fetch().await.and_then(|d| d.render(self.target));
+                    let mut buffer = status.write().await;
+                    if let Ok(data) = data {
+                        *buffer = data;
+                        staleness.success();
+                    }
+                } else {
+                    let mut buffer = *status.read().await;
+                    if staleness.is_stale(self.stale_after) {
+                        StaleMarker::new().draw(&mut buffer)?;
                     }
+                    yield Content::Frame(buffer);
                 }
             }
         })
@@ -0,0 +1,247 @@
+use crate::{
+    render::{
+        clock::SharedClock,
+        display::{Content, ContentProvider},
+        scheduler::{ContentWrapper, CONTENT_PROVIDERS, TICK_LENGTH},
+    },
+    stats,
+    stats::{Collector, Cpu, Memory, Network},
+};
+use anyhow::{anyhow, Result};
+use apex_hardware::FrameBuffer;
+use async_stream::try_stream;
+use config::{Config, Value};
+use embedded_graphics::{
+    geometry::{Point, Size},
+    mono_font::{ascii, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    prelude::Primitive,
+    primitives::{PrimitiveStyle, Rectangle},
+    text::{renderer::TextRenderer, Baseline, Text},
+    Drawable,
+};
+use futures::Stream;
+use linkme::distributed_slice;
+use log::{info, warn};
+use std::convert::TryFrom;
+use sysinfo::{System, SystemExt};
+use tokio::{
+    time,
+    time::{Duration, MissedTickBehavior},
+};
+
+#[doc(hidden)]
+#[distributed_slice(CONTENT_PROVIDERS)]
+pub static PROVIDER_INIT: fn(&Config, &SharedClock) -> Result<Box<dyn ContentWrapper>> =
+    register_callback;
+
+/// How often the statistics are collected
+const REFRESH_INTERVAL: u64 = 1;
+/// The layout that's used unless configured otherwise
+const DEFAULT_TILES: [Tile; 4] = [Tile::Cpu, Tile::Memory, Tile::Network, Tile::Clock];
+const DEFAULT_COLUMNS: usize = 2;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+/// The things that can be shown on the dashboard
+enum Tile {
+    Cpu,
+    Memory,
+    Network,
+    Clock,
+}
+
+impl TryFrom<Value> for Tile {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let value = value.into_str()?;
+        match value.to_lowercase().as_str() {
+            "cpu" => Ok(Tile::Cpu),
+            "ram" | "memory" => Ok(Tile::Memory),
+            "net" | "network" => Ok(Tile::Network),
+            "clock" => Ok(Tile::Clock),
+            _ => Err(anyhow!("Unknown dashboard tile: {}", value)),
+        }
+    }
+}
+
+#[doc(hidden)]
+#[allow(clippy::unnecessary_wraps)]
+fn register_callback(config: &Config, clock: &SharedClock) -> Result<Box<dyn ContentWrapper>> {
+    info!("Registering dashboard display source.");
+
+    let tiles = match config.get_array("dashboard.tiles") {
+        Ok(tiles) => tiles
+            .into_iter()
+            .filter_map(|tile| {
+                Tile::try_from(tile)
+                    .map_err(|e| warn!("Skipping dashboard tile: {}", e))
+                    .ok()
+            })
+            .collect(),
+        Err(_) => DEFAULT_TILES.to_vec(),
+    };
+
+    let columns = config
+        .get_int("dashboard.columns")
+        .map_or(DEFAULT_COLUMNS, |columns| columns.max(1) as usize);
+
+    Ok(Box::new(Dashboard {
+        tiles,
+        columns,
+        system: System::new(),
+        cpu: Cpu::default(),
+        memory: Memory::default(),
+        network: Network::new(clock.clone()),
+        clock: clock.clone(),
+    }))
+}
+
+/// Shows several system statistics at once, tiled in a grid.
+#[derive(Debug)]
+struct Dashboard {
+    tiles: Vec<Tile>,
+    columns: usize,
+    system: System,
+    cpu: Cpu,
+    memory: Memory,
+    network: Network,
+    clock: SharedClock,
+}
+
+impl Dashboard {
+    /// Refreshes only the collectors that are actually displayed.
+    fn refresh(&mut self) {
+        if self.tiles.contains(&Tile::Cpu) {
+            self.cpu.refresh(&mut self.system);
+        }
+        if self.tiles.contains(&Tile::Memory) {
+            self.memory.refresh(&mut self.system);
+        }
+        if self.tiles.contains(&Tile::Network) {
+            self.network.refresh(&mut self.system);
+        }
+    }
+
+    /// Draws a tiny label with a value next to it and a bar below.
+    fn draw_gauge(
+        buffer: &mut FrameBuffer,
+        area: Rectangle,
+        label: &str,
+        percent: f32,
+    ) -> Result<()> {
+        let style = MonoTextStyle::new(&ascii::FONT_4X6, BinaryColor::On);
+        let origin = area.top_left + Point::new(2, 2);
+        Text::with_baseline(label, origin, style, Baseline::Top).draw(buffer)?;
+
+        let value = format!("{:.0}%", percent);
+        let metrics = style.measure_string(&value, Point::zero(), Baseline::Top);
+        let right = area.top_left.x + area.size.width as i32 - 2;
+        Text::with_baseline(
+            &value,
+            Point::new(right - metrics.bounding_box.size.width as i32, origin.y),
+            style,
+            Baseline::Top,
+        )
+        .draw(buffer)?;
+
+        let bar = Rectangle::new(
+            origin + Point::new(0, 8),
+            Size::new(area.size.width.saturating_sub(4), 5),
+        );
+        bar.into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+            .draw(buffer)?;
+
+        let filled = (bar.size.width as f32 * percent.clamp(0.0, 100.0) / 100.0) as u32;
+        Rectangle::new(bar.top_left, Size::new(filled, bar.size.height))
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+            .draw(buffer)?;
+
+        Ok(())
+    }
+
+    fn draw_tile(&self, buffer: &mut FrameBuffer, area: Rectangle, tile: Tile) -> Result<()> {
+        let small = MonoTextStyle::new(&ascii::FONT_4X6, BinaryColor::On);
+        let origin = area.top_left + Point::new(2, 2);
+
+        match tile {
+            Tile::Cpu => Self::draw_gauge(buffer, area, "CPU", self.cpu.usage)?,
+            Tile::Memory => Self::draw_gauge(buffer, area, "RAM", self.memory.usage())?,
+            Tile::Network => {
+                Text::with_baseline("NET", origin, small, Baseline::Top).draw(buffer)?;
+                let down = format!("D {}/s", stats::format_bytes(self.network.received));
+                let up = format!("U {}/s", stats::format_bytes(self.network.transmitted));
+                Text::with_baseline(&down, origin + Point::new(0, 6), small, Baseline::Top)
+                    .draw(buffer)?;
+                Text::with_baseline(&up, origin + Point::new(0, 12), small, Baseline::Top)
+                    .draw(buffer)?;
+            }
+            Tile::Clock => {
+                let text = self.clock.local().format("%H:%M").to_string();
+                let style = MonoTextStyle::new(&ascii::FONT_8X13_BOLD, BinaryColor::On);
+                let metrics = style.measure_string(&text, Point::zero(), Baseline::Top);
+                let size = metrics.bounding_box.size;
+                let position =
+                    area.center() - Point::new(size.width as i32 / 2, size.height as i32 / 2);
+                Text::with_baseline(&text, position, style, Baseline::Top).draw(buffer)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn render(&self) -> Result<FrameBuffer> {
+        let mut buffer = FrameBuffer::new();
+
+        let rows = self.tiles.len().div_ceil(self.columns);
+        let size = Size::new(128 / self.columns as u32, 40 / rows.max(1) as u32);
+
+        for (i, tile) in self.tiles.iter().enumerate() {
+            let (column, row) = (i % self.columns, i / self.columns);
+            let area = Rectangle::new(
+                Point::new(
+                    column as i32 * size.width as i32,
+                    row as i32 * size.height as i32,
+                ),
+                size,
+            );
+            self.draw_tile(&mut buffer, area, *tile)?;
+        }
+
+        Ok(buffer)
+    }
+}
+
+impl ContentProvider for Dashboard {
+    type ContentStream<'a> = impl Stream<Item = Result<Content>> + 'a;
+
+    // This needs to be enabled until full GAT support is here
+    #[allow(clippy::needless_lifetimes)]
+    fn stream<'this>(&'this mut self) -> Result<Self::ContentStream<'this>> {
+        if self.tiles.is_empty() {
+            return Err(anyhow!("No dashboard tiles configured!"));
+        }
+
+        let mut render = time::interval(Duration::from_millis(TICK_LENGTH as u64));
+        render.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut refresh = time::interval(Duration::from_secs(REFRESH_INTERVAL));
+        refresh.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        Ok(try_stream! {
+            loop {
+                tokio::select! {
+                    _ = render.tick() => {
+                        yield Content::Frame(self.render()?);
+                    },
+                    _ = refresh.tick() => {
+                        self.refresh();
+                    }
+                }
+            }
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "dashboard"
+    }
+}
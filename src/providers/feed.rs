@@ -0,0 +1,243 @@
+use crate::{
+    net,
+    net::RateLimiter,
+    providers::ticker::Mode,
+    render::{
+        clock::SharedClock,
+        display::{Content, ContentProvider},
+        scheduler::{ContentWrapper, CONTENT_PROVIDERS, TICK_LENGTH},
+        stale::{StaleMarker, Staleness},
+        text::StatefulScrollable,
+    },
+};
+use anyhow::{anyhow, Result};
+use apex_hardware::FrameBuffer;
+use async_stream::try_stream;
+use config::Config;
+use embedded_graphics::{mono_font::ascii, Drawable};
+use futures::Stream;
+use linkme::distributed_slice;
+use log::{info, warn};
+use std::{cmp::Reverse, convert::TryFrom};
+use tokio::{
+    time,
+    time::{Duration, Instant, MissedTickBehavior},
+};
+
+#[doc(hidden)]
+#[distributed_slice(CONTENT_PROVIDERS)]
+pub static PROVIDER_INIT: fn(&Config, &SharedClock) -> Result<Box<dyn ContentWrapper>> =
+    register_callback;
+
+/// How many headlines are shown unless configured otherwise
+const DEFAULT_COUNT: usize = 5;
+/// How often the feed is fetched unless configured otherwise, in seconds
+const DEFAULT_REFRESH: u64 = 15 * 60;
+/// How many seconds each headline is shown for when rotating
+const DEFAULT_DURATION: u64 = 8;
+const DEFAULT_SEPARATOR: &str = " +++ ";
+/// How long to wait before trying again after the feed couldn't be fetched,
+/// in seconds
+const RETRY_INTERVAL: u64 = 60;
+
+#[doc(hidden)]
+#[allow(clippy::unnecessary_wraps)]
+fn register_callback(config: &Config, clock: &SharedClock) -> Result<Box<dyn ContentWrapper>> {
+    info!("Registering feed display source.");
+
+    let mode = config
+        .get_str("feed.mode")
+        .map_or(Ok(Mode::Marquee), Mode::try_from)
+        .unwrap_or_else(|e| {
+            warn!("{}", e);
+            Mode::Marquee
+        });
+    let count = config
+        .get_int("feed.count")
+        .map_or(DEFAULT_COUNT, |count| count.max(1) as usize);
+    let refresh = config
+        .get_int("feed.refresh")
+        .map_or(DEFAULT_REFRESH, |seconds| seconds.max(60) as u64);
+    let duration = config
+        .get_int("feed.duration")
+        .map_or(DEFAULT_DURATION, |seconds| seconds.max(1) as u64);
+    let separator = config
+        .get_str("feed.separator")
+        .unwrap_or_else(|_| String::from(DEFAULT_SEPARATOR));
+
+    Ok(Box::new(FeedSource {
+        url: config.get_str("feed.url").ok(),
+        count,
+        refresh: Duration::from_secs(refresh),
+        mode,
+        duration: Duration::from_secs(duration),
+        separator,
+        show_name: config.get_bool("feed.show_name").unwrap_or(false),
+        net: RateLimiter::new("Feed", clock.clone()),
+        clock: clock.clone(),
+    }))
+}
+
+/// What's left of a feed once it's fetched.
+#[derive(Debug, Clone, Default)]
+struct Headlines {
+    /// The title of the feed itself, e.g. the name of the blog
+    name: Option<String>,
+    /// The newest ones come first
+    titles: Vec<String>,
+}
+
+/// Titles may span several lines which doesn't work on a single row.
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn parse(bytes: &[u8], count: usize) -> Result<Headlines> {
+    let mut feed = feed_rs::parser::parse(bytes)?;
+
+    // Most feeds are sorted already, entries without a date keep their place
+    // among each other
+    feed.entries
+        .sort_by_key(|entry| Reverse(entry.published.or(entry.updated)));
+    let titles = feed
+        .entries
+        .iter()
+        .filter_map(|entry| entry.title.as_ref())
+        .map(|title| single_line(&title.content))
+        .filter(|title| !title.is_empty())
+        .take(count)
+        .collect::<Vec<_>>();
+
+    if titles.is_empty() {
+        return Err(anyhow!("The feed doesn't have any headlines"));
+    }
+
+    Ok(Headlines {
+        name: feed.title.map(|title| single_line(&title.content)),
+        titles,
+    })
+}
+
+/// Scrolls the latest headlines of an RSS or Atom feed past. The headlines
+/// from the last successful fetch are kept around when the feed can't be
+/// reached, they're marked as stale once the fetch that was due and the retry
+/// after it failed.
+#[derive(Debug)]
+struct FeedSource {
+    url: Option<String>,
+    /// How many headlines are shown
+    count: usize,
+    /// How often the feed is fetched
+    refresh: Duration,
+    mode: Mode,
+    /// How long each headline is shown for in [`Mode::Rotate`]
+    duration: Duration,
+    /// What goes between the headlines in [`Mode::Marquee`]
+    separator: String,
+    /// Whether to show the name of the feed above the headlines
+    show_name: bool,
+    net: RateLimiter,
+    clock: SharedClock,
+}
+
+impl FeedSource {
+    async fn fetch(&mut self, url: &str) -> Result<Headlines> {
+        let response = self.net.send(net::client().get(url)).await?;
+        let bytes = response.bytes().await?;
+        parse(&bytes, self.count)
+    }
+
+    fn text(&self, headlines: &Headlines, index: usize) -> String {
+        match self.mode {
+            Mode::Rotate => headlines.titles[index % headlines.titles.len()].clone(),
+            Mode::Marquee => headlines.titles.join(&self.separator),
+        }
+    }
+}
+
+impl ContentProvider for FeedSource {
+    type ContentStream<'a> = impl Stream<Item = Result<Content>> + 'a;
+
+    // This needs to be enabled until full GAT support is here
+    #[allow(clippy::needless_lifetimes)]
+    fn stream<'this>(&'this mut self) -> Result<Self::ContentStream<'this>> {
+        let url = self
+            .url
+            .clone()
+            .ok_or_else(|| anyhow!("No feed configured!"))?;
+
+        let mut interval = time::interval(Duration::from_millis(TICK_LENGTH as u64));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        // The headline moves down to make room for the name
+        let mut name = StatefulScrollable::wide_row(&ascii::FONT_6X10, 3)?;
+        let y = if self.show_name { 20 } else { (40 - 13) / 2 };
+        let mut headline = StatefulScrollable::wide_row(&ascii::FONT_8X13_BOLD, y)?;
+
+        let stale_after = self.refresh + Duration::from_secs(RETRY_INTERVAL);
+
+        Ok(try_stream! {
+            let mut refetch = net::interval(self.refresh, &self.clock);
+            let mut headlines: Option<Headlines> = None;
+            let mut staleness = Staleness::new(self.clock.clone());
+            let mut fetch_at: Option<Instant> = None;
+
+            let mut index = 0;
+            let mut switch_at = self.clock.now() + self.duration;
+
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {},
+                    _ = refetch.tick() => fetch_at = Some(self.clock.now()),
+                }
+
+                let now = self.clock.now();
+                if fetch_at.is_some_and(|at| now >= at) {
+                    match self.fetch(&url).await {
+                        Ok(update) => {
+                            if staleness.age().is_some() && staleness.is_stale(stale_after) {
+                                info!("Fetched {} again", url);
+                            }
+                            staleness.success();
+                            fetch_at = None;
+                            headlines = Some(update);
+                        }
+                        Err(e) => {
+                            warn!("Couldn't fetch {}: {}", url, e);
+                            fetch_at = Some(now + Duration::from_secs(RETRY_INTERVAL));
+                        }
+                    }
+                }
+
+                let Some(headlines) = &headlines else {
+                    yield Content::Frame(FrameBuffer::new());
+                    continue;
+                };
+
+                if self.mode == Mode::Rotate && now >= switch_at {
+                    index = (index + 1) % headlines.titles.len();
+                    switch_at = now + self.duration;
+                    // The next headline starts scrolling from the beginning
+                    headline.text.scroll = 0;
+                }
+
+                let mut buffer = FrameBuffer::new();
+                if let (true, Some(text)) = (self.show_name, &headlines.name) {
+                    name.update_or_scroll(text)?;
+                    name.text.draw(&mut buffer)?;
+                }
+                headline.update_or_scroll(&self.text(headlines, index))?;
+                headline.text.draw(&mut buffer)?;
+                if staleness.is_stale(stale_after) {
+                    StaleMarker::new().draw(&mut buffer)?;
+                }
+
+                yield Content::Frame(buffer);
+            }
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "feed"
+    }
+}
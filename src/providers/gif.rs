@@ -0,0 +1,229 @@
+use crate::render::{
+    clock::SharedClock,
+    display::{Content, ContentProvider},
+    dither,
+    scheduler::{ContentWrapper, CONTENT_PROVIDERS, TICK_LENGTH},
+};
+use anyhow::{anyhow, Result};
+use apex_hardware::FrameBuffer;
+use async_stream::try_stream;
+use config::Config;
+use futures::Stream;
+use image::{
+    codecs::gif::GifDecoder, imageops, imageops::FilterType, AnimationDecoder, GrayImage, Luma,
+    RgbaImage,
+};
+use linkme::distributed_slice;
+use log::info;
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+use tokio::{
+    time,
+    time::{Duration, MissedTickBehavior},
+};
+
+#[doc(hidden)]
+#[distributed_slice(CONTENT_PROVIDERS)]
+pub static PROVIDER_INIT: fn(&Config, &SharedClock) -> Result<Box<dyn ContentWrapper>> =
+    register_callback;
+
+const WIDTH: u32 = 128;
+const HEIGHT: u32 = 40;
+/// Browsers play frames without a proper delay at this speed, in milliseconds
+const DEFAULT_DELAY: u64 = 100;
+
+#[doc(hidden)]
+#[allow(clippy::unnecessary_wraps)]
+fn register_callback(config: &Config, clock: &SharedClock) -> Result<Box<dyn ContentWrapper>> {
+    info!("Registering GIF display source.");
+
+    let path = config.get_str("gif.path").ok().map(PathBuf::from);
+
+    Ok(Box::new(Gif {
+        path,
+        clock: clock.clone(),
+    }))
+}
+
+/// How often an animation is played.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Repeat {
+    Infinite,
+    /// Played once and then repeated this many times
+    Finite(u16),
+}
+
+/// Finds the loop count in the NETSCAPE2.0 application extension. GIFs
+/// without one are only played once.
+fn repeat(data: &[u8]) -> Repeat {
+    const EXTENSION: &[u8] = b"NETSCAPE2.0";
+
+    let count = data
+        .windows(EXTENSION.len())
+        .position(|window| window == EXTENSION)
+        .and_then(|start| data.get(start + EXTENSION.len()..start + EXTENSION.len() + 4))
+        .filter(|block| block[0] == 3 && block[1] == 1)
+        .map(|block| u16::from_le_bytes([block[2], block[3]]));
+
+    match count {
+        Some(0) => Repeat::Infinite,
+        Some(count) => Repeat::Finite(count),
+        None => Repeat::Finite(0),
+    }
+}
+
+/// Scales a frame down to fit onto the display, smaller frames are left
+/// alone. Transparent pixels end up black.
+fn prepare(frame: &RgbaImage) -> FrameBuffer {
+    let gray = GrayImage::from_fn(frame.width(), frame.height(), |x, y| {
+        let [r, g, b, a] = frame.get_pixel(x, y).0;
+        let luma = (u32::from(r) * 299 + u32::from(g) * 587 + u32::from(b) * 114) / 1000;
+        Luma([(luma * u32::from(a) / 255) as u8])
+    });
+
+    let scale = f64::min(
+        1.0,
+        f64::min(
+            f64::from(WIDTH) / f64::from(gray.width()),
+            f64::from(HEIGHT) / f64::from(gray.height()),
+        ),
+    );
+    let (width, height) = (
+        ((f64::from(gray.width()) * scale) as u32).max(1),
+        ((f64::from(gray.height()) * scale) as u32).max(1),
+    );
+    let gray = if (width, height) == gray.dimensions() {
+        gray
+    } else {
+        imageops::resize(&gray, width, height, FilterType::Triangle)
+    };
+
+    // Centered on the display
+    let (left, top) = ((WIDTH - width) / 2, (HEIGHT - height) / 2);
+    let mut buffer = FrameBuffer::new();
+    for (i, on) in dither::dither(&gray).into_iter().enumerate() {
+        let (x, y) = (left + i as u32 % width, top + i as u32 / width);
+        buffer.framebuffer.set((x + y * WIDTH + 8) as usize, on);
+    }
+    buffer
+}
+
+/// Decodes and dithers every frame, this only happens once so every loop is
+/// just a matter of copying the frames to the display.
+fn load(path: &Path) -> Result<(Vec<(FrameBuffer, Duration)>, Repeat)> {
+    let data = std::fs::read(path)?;
+    let repeat = repeat(&data);
+
+    let decoder = GifDecoder::new(Cursor::new(&data))?;
+    let frames = decoder
+        .into_frames()
+        .map(|frame| {
+            let frame = frame?;
+            let (numerator, denominator) = frame.delay().numer_denom_ms();
+            let delay = u64::from(numerator) / u64::from(denominator.max(1));
+            // Most viewers treat tiny delays as unset
+            let delay = if delay < 20 { DEFAULT_DELAY } else { delay };
+            Ok((prepare(frame.buffer()), Duration::from_millis(delay)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if frames.is_empty() {
+        return Err(anyhow!("{} doesn't contain any frames", path.display()));
+    }
+
+    Ok((frames, repeat))
+}
+
+/// Plays an animated GIF in a loop.
+#[derive(Debug, Clone)]
+struct Gif {
+    path: Option<PathBuf>,
+    clock: SharedClock,
+}
+
+impl ContentProvider for Gif {
+    type ContentStream<'a> = impl Stream<Item = Result<Content>> + 'a;
+
+    // This needs to be enabled until full GAT support is here
+    #[allow(clippy::needless_lifetimes)]
+    fn stream<'this>(&'this mut self) -> Result<Self::ContentStream<'this>> {
+        let path = self
+            .path
+            .as_ref()
+            .ok_or_else(|| anyhow!("No GIF configured!"))?;
+        let (frames, repeat) = load(path)?;
+        info!("Loaded {} frames from {}", frames.len(), path.display());
+
+        let mut interval = time::interval(Duration::from_millis(TICK_LENGTH as u64));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        Ok(try_stream! {
+            let mut plays = 0;
+            let mut index = 0;
+            let mut next = self.clock.now();
+
+            loop {
+                interval.tick().await;
+
+                let finished = matches!(repeat, Repeat::Finite(count) if plays > count);
+                if finished || self.clock.now() < next {
+                    yield Content::Unchanged;
+                    continue;
+                }
+
+                let (frame, delay) = frames[index];
+                yield Content::Frame(frame);
+                next = self.clock.now() + delay;
+
+                index += 1;
+                if index == frames.len() {
+                    index = 0;
+                    plays += 1;
+                }
+            }
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "gif"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The application extension as written by most encoders, `count` is
+    /// little endian.
+    fn netscape(count: u16) -> Vec<u8> {
+        let mut data = b"GIF89a\x21\xff\x0bNETSCAPE2.0\x03\x01".to_vec();
+        data.extend_from_slice(&count.to_le_bytes());
+        data.push(0);
+        data
+    }
+
+    #[test]
+    fn zero_loops_forever() {
+        assert_eq!(repeat(&netscape(0)), Repeat::Infinite);
+    }
+
+    #[test]
+    fn reads_the_loop_count() {
+        assert_eq!(repeat(&netscape(3)), Repeat::Finite(3));
+        assert_eq!(repeat(&netscape(0x0102)), Repeat::Finite(0x0102));
+    }
+
+    #[test]
+    fn plays_once_without_the_extension() {
+        assert_eq!(repeat(b"GIF89a\x00\x3b"), Repeat::Finite(0));
+        // Cut off right after the identifier
+        assert_eq!(repeat(b"GIF89a\x21\xff\x0bNETSCAPE2.0"), Repeat::Finite(0));
+        // Some other sub-block
+        assert_eq!(
+            repeat(b"GIF89a\x21\xff\x0bNETSCAPE2.0\x05\x02\x00\x00"),
+            Repeat::Finite(0)
+        );
+    }
+}
@@ -0,0 +1,306 @@
+use crate::render::{
+    clock::SharedClock,
+    display::{Content, ContentProvider},
+    glyph::Glyph,
+    scheduler::{ContentWrapper, CONTENT_PROVIDERS, TICK_LENGTH},
+};
+use anyhow::{anyhow, Result};
+use apex_hardware::FrameBuffer;
+use async_stream::try_stream;
+use config::Config;
+use embedded_graphics::{
+    geometry::Point,
+    mono_font::{ascii, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    text::{renderer::TextRenderer, Baseline, Text},
+    Drawable,
+};
+use futures::Stream;
+use git2::{BranchType, ErrorCode, Repository, StatusOptions};
+use linkme::distributed_slice;
+use log::{info, warn};
+use notify::{RecursiveMode, Watcher};
+use std::{
+    ffi::OsStr,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+use tokio::{
+    sync::Notify,
+    task, time,
+    time::{Duration, MissedTickBehavior},
+};
+
+#[doc(hidden)]
+#[distributed_slice(CONTENT_PROVIDERS)]
+pub static PROVIDER_INIT: fn(&Config, &SharedClock) -> Result<Box<dyn ContentWrapper>> =
+    register_callback;
+
+/// How often the repository is read even if no change was noticed, in seconds
+const DEFAULT_REFRESH: u64 = 30;
+/// Changes usually come in bursts, e.g. a checkout touching hundreds of files,
+/// so we wait this many milliseconds for things to settle before reading
+const DEBOUNCE: u64 = 500;
+
+const AHEAD_GLYPH: Glyph = Glyph::new(
+    &[
+        0b00100, //
+        0b01110, //
+        0b10101, //
+        0b00100, //
+        0b00100, //
+        0b00100, //
+        0b00100, //
+    ],
+    5,
+);
+const BEHIND_GLYPH: Glyph = Glyph::new(
+    &[
+        0b00100, //
+        0b00100, //
+        0b00100, //
+        0b00100, //
+        0b10101, //
+        0b01110, //
+        0b00100, //
+    ],
+    5,
+);
+const DIRTY_GLYPH: Glyph = Glyph::new(
+    &[
+        0b00000, //
+        0b01110, //
+        0b11111, //
+        0b11111, //
+        0b11111, //
+        0b01110, //
+        0b00000, //
+    ],
+    5,
+);
+
+#[doc(hidden)]
+#[allow(clippy::unnecessary_wraps)]
+fn register_callback(config: &Config, _clock: &SharedClock) -> Result<Box<dyn ContentWrapper>> {
+    info!("Registering Git display source.");
+
+    let path = config.get_str("git.path").ok().map(PathBuf::from);
+    let refresh = config
+        .get_int("git.refresh")
+        .map_or(DEFAULT_REFRESH, |refresh| refresh.max(1) as u64);
+
+    Ok(Box::new(Git { path, refresh }))
+}
+
+/// What we know about the repository.
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum State {
+    Repository {
+        /// The name of the branch or the abbreviated commit for a detached HEAD
+        head: String,
+        /// How many commits the branch is ahead and behind its upstream, if it
+        /// has one
+        upstream: Option<(usize, usize)>,
+        /// How many files were changed, staged or not, including untracked
+        /// ones
+        dirty: usize,
+    },
+    /// The path isn't a repository or it couldn't be read
+    Unavailable(String),
+}
+
+fn read_state(path: &Path) -> State {
+    let repository = match Repository::open(path) {
+        Ok(repository) => repository,
+        Err(e) if e.code() == ErrorCode::NotFound => {
+            return State::Unavailable(String::from("Not a repository"))
+        }
+        Err(e) => return State::Unavailable(e.message().to_string()),
+    };
+
+    read_repository(&repository).unwrap_or_else(|e| State::Unavailable(e.message().to_string()))
+}
+
+fn read_repository(repository: &Repository) -> Result<State, git2::Error> {
+    let (head, upstream) = match repository.head() {
+        Ok(head) if head.is_branch() => {
+            let name = head.shorthand().unwrap_or("?").to_string();
+            let branch = repository.find_branch(&name, BranchType::Local)?;
+            let upstream = match (branch.upstream(), head.target()) {
+                (Ok(upstream), Some(local)) => match upstream.get().target() {
+                    Some(remote) => Some(repository.graph_ahead_behind(local, remote)?),
+                    None => None,
+                },
+                _ => None,
+            };
+            (name, upstream)
+        }
+        Ok(head) => {
+            let commit = head.peel_to_commit()?;
+            let id = commit.as_object().short_id()?;
+            (format!("@{}", id.as_str().unwrap_or("?")), None)
+        }
+        // A fresh repository doesn't have any commits yet, HEAD still names the branch though
+        Err(e) if e.code() == ErrorCode::UnbornBranch => {
+            let head = repository.find_reference("HEAD")?;
+            let name = head
+                .symbolic_target()
+                .and_then(|target| target.strip_prefix("refs/heads/"))
+                .unwrap_or("?")
+                .to_string();
+            (name, None)
+        }
+        Err(e) => return Err(e),
+    };
+
+    // Unchanged and ignored files aren't listed at all
+    let mut options = StatusOptions::new();
+    options.include_untracked(true).include_ignored(false);
+    let dirty = repository.statuses(Some(&mut options))?.len();
+
+    Ok(State::Repository {
+        head,
+        upstream,
+        dirty,
+    })
+}
+
+/// Returns `false` for changes that don't affect what's displayed. Git writes
+/// plenty of files on its own, reading the status may even update the index,
+/// so only the work tree, `HEAD` and the refs count.
+fn is_relevant(repository: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(repository) else {
+        return true;
+    };
+
+    let mut components = relative.components();
+    if components.next() != Some(Component::Normal(OsStr::new(".git"))) {
+        return true;
+    }
+
+    match components.next() {
+        Some(Component::Normal(name)) => name == "HEAD" || name == "refs" || name == "packed-refs",
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Git {
+    path: Option<PathBuf>,
+    refresh: u64,
+}
+
+impl Git {
+    fn render(state: &State) -> Result<FrameBuffer> {
+        let mut buffer = FrameBuffer::new();
+        let large = MonoTextStyle::new(&ascii::FONT_8X13_BOLD, BinaryColor::On);
+        let small = MonoTextStyle::new(&ascii::FONT_6X10, BinaryColor::On);
+
+        let (head, upstream, dirty) = match state {
+            State::Repository {
+                head,
+                upstream,
+                dirty,
+            } => (head, upstream, dirty),
+            State::Unavailable(reason) => {
+                Text::with_baseline("No repository", Point::new(3, 8), large, Baseline::Top)
+                    .draw(&mut buffer)?;
+                let reason = reason.chars().take(20).collect::<String>();
+                Text::with_baseline(&reason, Point::new(3, 24), small, Baseline::Top)
+                    .draw(&mut buffer)?;
+                return Ok(buffer);
+            }
+        };
+
+        // Long branch names are cut off at the edge of the display
+        let characters = (128 - 3 - 3) / ascii::FONT_8X13_BOLD.character_size.width as usize;
+        let head = head.chars().take(characters).collect::<String>();
+        Text::with_baseline(&head, Point::new(3, 6), large, Baseline::Top).draw(&mut buffer)?;
+
+        let mut counters = Vec::new();
+        if let Some((ahead, behind)) = upstream {
+            counters.push((AHEAD_GLYPH, *ahead));
+            counters.push((BEHIND_GLYPH, *behind));
+        }
+        counters.push((DIRTY_GLYPH, *dirty));
+
+        let mut x = 3;
+        for (glyph, count) in counters {
+            glyph.at(Point::new(x, 25)).draw(&mut buffer)?;
+            x += glyph.size().width as i32 + 1;
+
+            let text = count.to_string();
+            let metrics = small.measure_string(&text, Point::zero(), Baseline::Top);
+            Text::with_baseline(&text, Point::new(x, 24), small, Baseline::Top)
+                .draw(&mut buffer)?;
+            x += metrics.bounding_box.size.width as i32 + 6;
+        }
+
+        Ok(buffer)
+    }
+}
+
+impl ContentProvider for Git {
+    type ContentStream<'a> = impl Stream<Item = Result<Content>> + 'a;
+
+    // This needs to be enabled until full GAT support is here
+    #[allow(clippy::needless_lifetimes)]
+    fn stream<'this>(&'this mut self) -> Result<Self::ContentStream<'this>> {
+        let path = self
+            .path
+            .clone()
+            .ok_or_else(|| anyhow!("No repository configured!"))?;
+
+        let changed = Arc::new(Notify::new());
+        let notify = changed.clone();
+        let root = path.clone();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                if let Ok(event) = event {
+                    if event.paths.iter().any(|path| is_relevant(&root, path)) {
+                        notify.notify_one();
+                    }
+                }
+            })?;
+        // Without the watcher we still notice changes, just later
+        if let Err(e) = watcher.watch(&path, RecursiveMode::Recursive) {
+            warn!("Can't watch {} for changes: {}", path.display(), e);
+        }
+
+        let mut render = time::interval(Duration::from_millis(TICK_LENGTH as u64));
+        render.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut refresh = time::interval(Duration::from_secs(self.refresh));
+        refresh.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        Ok(try_stream! {
+            // The watcher stops as soon as it's dropped
+            let _watcher = watcher;
+            let mut state = None;
+            let mut image = None;
+
+            loop {
+                tokio::select! {
+                    _ = render.tick() => {
+                        yield image.take().map_or(Content::Unchanged, Content::Frame);
+                        continue;
+                    },
+                    _ = refresh.tick() => {},
+                    () = changed.notified() => {
+                        time::sleep(Duration::from_millis(DEBOUNCE)).await;
+                    },
+                }
+
+                let path = path.clone();
+                let new = task::spawn_blocking(move || read_state(&path)).await?;
+                if state.as_ref() != Some(&new) {
+                    image = Some(Self::render(&new)?);
+                    state = Some(new);
+                }
+            }
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "git"
+    }
+}
@@ -0,0 +1,376 @@
+use crate::{
+    backend,
+    backend::{Backend, Player, Session},
+    net,
+    net::RateLimiter,
+    render::{
+        clock::SharedClock,
+        display::{Content, ContentProvider},
+        scheduler::{ContentWrapper, CONTENT_PROVIDERS, TICK_LENGTH},
+        text::{ScrollableBuilder, StatefulScrollable},
+        util::format_count,
+    },
+    secrets,
+};
+use anyhow::{anyhow, Result};
+use apex_hardware::FrameBuffer;
+use apex_music::{Metadata, PlayerEvent};
+use async_stream::try_stream;
+use config::Config;
+use embedded_graphics::{
+    geometry::{Point, Size},
+    mono_font::{ascii, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    prelude::Primitive,
+    primitives::{Line, PrimitiveStyle},
+    text::{Baseline, Text},
+    Drawable,
+};
+use futures::{pin_mut, Stream, StreamExt};
+use linkme::distributed_slice;
+use log::{info, warn};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::{collections::HashMap, convert::TryInto, sync::Arc};
+use tokio::{
+    time,
+    time::{Duration, MissedTickBehavior},
+};
+
+#[doc(hidden)]
+#[distributed_slice(CONTENT_PROVIDERS)]
+pub static PROVIDER_INIT: fn(&Config, &SharedClock) -> Result<Box<dyn ContentWrapper>> =
+    register_callback;
+
+const LASTFM_URL: &str = "https://ws.audioscrobbler.com/2.0/";
+/// How often a lookup that failed is tried again, in seconds
+const RETRY_INTERVAL: u64 = 60;
+/// Plenty for a listening session, the cache is simply cleared when it's full
+const CACHE_SIZE: usize = 256;
+/// Last.fm's error code for unknown tracks
+const TRACK_NOT_FOUND: u32 = 6;
+
+#[doc(hidden)]
+#[allow(clippy::unnecessary_wraps)]
+fn register_callback(config: &Config, clock: &SharedClock) -> Result<Box<dyn ContentWrapper>> {
+    info!("Registering Last.fm display source.");
+
+    let api_key = secrets::get(config, "lastfm.api_key").unwrap_or_else(|e| {
+        warn!("{}", e);
+        None
+    });
+    let username = config.get_str("lastfm.username").ok();
+
+    // The play counts belong to the same track the music source is showing
+    let name = config.get_str("mpris2.preferred_player").ok();
+    let priority = backend::configured_priority(config);
+
+    Ok(Box::new(LastFm {
+        api_key,
+        username,
+        name: name.map(Arc::new),
+        backends: backend::configured(config),
+        priority,
+        cache: HashMap::new(),
+        net: RateLimiter::new("Last.fm", clock.clone()),
+    }))
+}
+
+/// A number that Last.fm sends as a string.
+fn count<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.and_then(|value| value.parse().ok()))
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+struct TrackInfo {
+    #[serde(default, deserialize_with = "count")]
+    playcount: Option<u64>,
+    #[serde(default, deserialize_with = "count")]
+    userplaycount: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum Response {
+    Track { track: TrackInfo },
+    Error { error: u32, message: String },
+}
+
+/// What Last.fm knows about a track.
+#[derive(Debug, Clone, Copy)]
+enum Counts {
+    Found(TrackInfo),
+    NotFound,
+}
+
+/// The track whose play counts are shown.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+struct Track {
+    title: String,
+    /// Last.fm only knows about the first artist
+    artist: String,
+}
+
+impl Track {
+    fn describe(&self) -> String {
+        format!("{} - {}", self.title, self.artist)
+    }
+}
+
+async fn current_track(player: &Session<'_>) -> Result<Option<Track>> {
+    let metadata = player.progress().await?.metadata;
+    let title = metadata.title().unwrap_or_default();
+    let artist = metadata
+        .artists()
+        .ok()
+        .and_then(|artists| artists.into_iter().next())
+        .unwrap_or_default();
+
+    // There's nothing to look up without both
+    if title.is_empty() || artist.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(Track { title, artist }))
+}
+
+/// Draws the track at the top and its play counts below it.
+#[derive(Debug)]
+struct LastFmRenderer {
+    track: StatefulScrollable,
+}
+
+impl LastFmRenderer {
+    fn new() -> Result<Self> {
+        Ok(Self {
+            track: ScrollableBuilder::new()
+                .with_custom_spacing(10)
+                .with_position(Point::new(3, 1))
+                .with_projection(Size::new(128 - 2 * 3, 10))
+                .try_into()?,
+        })
+    }
+
+    fn message(text: &str) -> Result<FrameBuffer> {
+        let mut buffer = FrameBuffer::new();
+        let style = MonoTextStyle::new(&ascii::FONT_6X10, BinaryColor::On);
+        Text::with_baseline(text, Point::new(3, 15), style, Baseline::Top).draw(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Draws a label with a count below it, or a dash if there's none.
+    fn draw_count(buffer: &mut FrameBuffer, x: i32, label: &str, count: Option<u64>) -> Result<()> {
+        let small = MonoTextStyle::new(&ascii::FONT_6X10, BinaryColor::On);
+        let large = MonoTextStyle::new(&ascii::FONT_7X13_BOLD, BinaryColor::On);
+
+        Text::with_baseline(label, Point::new(x, 14), small, Baseline::Top).draw(buffer)?;
+        match count {
+            Some(count) => {
+                Text::with_baseline(
+                    &format_count(count),
+                    Point::new(x, 25),
+                    large,
+                    Baseline::Top,
+                )
+                .draw(buffer)?;
+            }
+            // The fonts don't come with an em dash
+            None => {
+                Line::new(Point::new(x, 31), Point::new(x + 10, 31))
+                    .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 2))
+                    .draw(buffer)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn render(
+        &mut self,
+        track: &Track,
+        counts: Option<Counts>,
+        personal: bool,
+    ) -> Result<FrameBuffer> {
+        let mut buffer = FrameBuffer::new();
+
+        let text = track.describe();
+        if !self.track.update(&text)? {
+            self.track.text.scroll();
+        }
+        self.track.text.draw(&mut buffer)?;
+
+        // Still waiting for Last.fm, or it couldn't be reached
+        let Some(counts) = counts else {
+            let style = MonoTextStyle::new(&ascii::FONT_6X10, BinaryColor::On);
+            Text::with_baseline("Asking Last.fm...", Point::new(3, 20), style, Baseline::Top)
+                .draw(&mut buffer)?;
+            return Ok(buffer);
+        };
+
+        let info = match counts {
+            Counts::Found(info) => info,
+            Counts::NotFound => TrackInfo::default(),
+        };
+        Self::draw_count(&mut buffer, 3, "Scrobbles", info.playcount)?;
+        if personal {
+            Self::draw_count(&mut buffer, 128 / 2 + 16, "You", info.userplaycount)?;
+        }
+
+        Ok(buffer)
+    }
+}
+
+/// Shows how often the current track was played according to Last.fm.
+#[derive(Debug)]
+struct LastFm {
+    api_key: Option<String>,
+    /// The personal play count is only shown with a username
+    username: Option<String>,
+    name: Option<Arc<String>>,
+    backends: Vec<Backend>,
+    /// The players to prefer in order, the preferred one comes first
+    priority: Vec<String>,
+    /// Every track is only looked up once
+    cache: HashMap<Track, Counts>,
+    net: RateLimiter,
+}
+
+impl LastFm {
+    async fn fetch(&mut self, api_key: &str, track: &Track) -> Result<Counts> {
+        let mut query = vec![
+            ("method", "track.getInfo"),
+            ("api_key", api_key),
+            ("artist", track.artist.as_str()),
+            ("track", track.title.as_str()),
+            ("autocorrect", "1"),
+            ("format", "json"),
+        ];
+        if let Some(username) = &self.username {
+            query.push(("username", username.as_str()));
+        }
+
+        let request = net::client().get(LASTFM_URL).query(&query);
+        let response = match self.net.send(request).await {
+            Ok(response) => response.json::<Response>().await?,
+            // Unknown tracks may come with a 404
+            Err(e)
+                if e.downcast_ref::<reqwest::Error>()
+                    .and_then(reqwest::Error::status)
+                    == Some(StatusCode::NOT_FOUND) =>
+            {
+                return Ok(Counts::NotFound)
+            }
+            Err(e) => return Err(e),
+        };
+
+        match response {
+            Response::Track { track } => Ok(Counts::Found(track)),
+            Response::Error { error, .. } if error == TRACK_NOT_FOUND => Ok(Counts::NotFound),
+            Response::Error { message, .. } => Err(anyhow!("Last.fm: {}", message)),
+        }
+    }
+
+    /// Looks the track up unless it's cached, failed lookups aren't cached so
+    /// they're tried again later.
+    async fn lookup(&mut self, api_key: &str, track: &Track) -> Option<Counts> {
+        if let Some(counts) = self.cache.get(track) {
+            return Some(*counts);
+        }
+
+        match self.fetch(api_key, track).await {
+            Ok(counts) => {
+                if self.cache.len() >= CACHE_SIZE {
+                    self.cache.clear();
+                }
+                self.cache.insert(track.clone(), counts);
+                Some(counts)
+            }
+            Err(e) => {
+                warn!("Couldn't look up {:?} on Last.fm: {}", track.describe(), e);
+                None
+            }
+        }
+    }
+}
+
+impl ContentProvider for LastFm {
+    type ContentStream<'a> = impl Stream<Item = Result<Content>> + 'a;
+
+    // This needs to be enabled until full GAT support is here
+    #[allow(clippy::needless_lifetimes)]
+    fn stream<'this>(&'this mut self) -> Result<Self::ContentStream<'this>> {
+        let api_key = self
+            .api_key
+            .clone()
+            .ok_or_else(|| anyhow!("No Last.fm API key configured!"))?;
+        let mut renderer = LastFmRenderer::new()?;
+        let personal = self.username.is_some();
+
+        Ok(try_stream! {
+            let backend = Player::auto(&self.backends)
+                .await
+                .with_priority(self.priority.clone());
+
+            'outer: loop {
+                yield Content::Frame(LastFmRenderer::message("No player found")?);
+                let player = backend.wait_for_player(self.name.clone()).await?;
+
+                let tracker = backend.stream().await?;
+                pin_mut!(tracker);
+
+                let Ok(mut track) = current_track(&player).await else {
+                    continue 'outer;
+                };
+                let mut counts = match &track {
+                    Some(track) => self.lookup(&api_key, track).await,
+                    None => None,
+                };
+
+                let mut render = time::interval(Duration::from_millis(TICK_LENGTH as u64));
+                render.set_missed_tick_behavior(MissedTickBehavior::Skip);
+                let mut retry = time::interval(Duration::from_secs(RETRY_INTERVAL));
+                retry.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+                loop {
+                    tokio::select! {
+                        event = tracker.next() => match event {
+                            None => continue 'outer,
+                            // Only a new track needs new counts
+                            Some(PlayerEvent::PositionChanged | PlayerEvent::Seeked) => {},
+                            Some(_) => {
+                                let Ok(new) = current_track(&player).await else {
+                                    continue 'outer;
+                                };
+                                if new != track {
+                                    counts = match &new {
+                                        Some(new) => self.lookup(&api_key, new).await,
+                                        None => None,
+                                    };
+                                    track = new;
+                                }
+                            },
+                        },
+                        _ = retry.tick(), if counts.is_none() => {
+                            if let Some(track) = &track {
+                                counts = self.lookup(&api_key, track).await;
+                            }
+                        },
+                        _ = render.tick() => {}
+                    }
+
+                    let image = match &track {
+                        Some(track) => renderer.render(track, counts, personal)?,
+                        None => LastFmRenderer::message("Nothing to look up")?,
+                    };
+                    yield Content::Frame(image);
+                }
+            }
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "lastfm"
+    }
+}
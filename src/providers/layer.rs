@@ -0,0 +1,105 @@
+use crate::render::{
+    clock::SharedClock,
+    display::{Content, ContentProvider},
+    scheduler::{ContentWrapper, CONTENT_PROVIDERS},
+};
+use anyhow::Result;
+use apex_hardware::FrameBuffer;
+use async_stream::try_stream;
+use config::Config;
+use embedded_graphics::{
+    geometry::Point,
+    mono_font::{ascii, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    text::{renderer::TextRenderer, Baseline, Text},
+    Drawable,
+};
+use futures::Stream;
+use lazy_static::lazy_static;
+use linkme::distributed_slice;
+use log::info;
+use tokio::{
+    sync::watch,
+    time,
+    time::{Duration, MissedTickBehavior},
+};
+
+lazy_static! {
+    // The keyboards don't report their active profile or layer over HID so external
+    // tooling (e.g. a macro engine) has to push it to us via the control socket.
+    static ref LAYER: (watch::Sender<Option<String>>, watch::Receiver<Option<String>>) =
+        watch::channel(None);
+}
+
+/// Sets the name of the active keyboard layer, `None` clears it.
+pub fn set_layer(name: Option<String>) {
+    // We hold on to a receiver ourselves so this can't fail
+    let _ = LAYER.0.send(name);
+}
+
+#[doc(hidden)]
+#[distributed_slice(CONTENT_PROVIDERS)]
+pub static PROVIDER_INIT: fn(&Config, &SharedClock) -> Result<Box<dyn ContentWrapper>> =
+    register_callback;
+
+#[doc(hidden)]
+#[allow(clippy::unnecessary_wraps)]
+fn register_callback(_config: &Config, _clock: &SharedClock) -> Result<Box<dyn ContentWrapper>> {
+    info!("Registering keyboard layer display source.");
+    Ok(Box::new(Layer {}))
+}
+
+pub struct Layer;
+
+impl Layer {
+    pub fn render(name: Option<&str>) -> Result<FrameBuffer> {
+        let mut buffer = FrameBuffer::new();
+
+        let label = MonoTextStyle::new(&ascii::FONT_6X10, BinaryColor::On);
+        Text::with_baseline("Layer", Point::new(3, 3), label, Baseline::Top).draw(&mut buffer)?;
+
+        let text = name.unwrap_or("Unknown");
+        let style = MonoTextStyle::new(&ascii::FONT_8X13_BOLD, BinaryColor::On);
+        let metrics = style.measure_string(text, Point::zero(), Baseline::Top);
+        let width: i32 = (metrics.bounding_box.size.width / 2) as i32;
+
+        Text::with_baseline(text, Point::new(128 / 2 - width, 20), style, Baseline::Top)
+            .draw(&mut buffer)?;
+
+        Ok(buffer)
+    }
+}
+
+impl ContentProvider for Layer {
+    type ContentStream<'a> = impl Stream<Item = Result<Content>> + 'a;
+
+    // This needs to be enabled until full GAT support is here
+    #[allow(clippy::needless_lifetimes)]
+    fn stream<'this>(&'this mut self) -> Result<Self::ContentStream<'this>> {
+        let mut interval = time::interval(Duration::from_millis(50));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut layer = LAYER.1.clone();
+
+        Ok(try_stream! {
+            // The layer rarely changes so the frame is only sent again when it does
+            let mut image = Some(Self::render(layer.borrow().as_deref())?);
+            loop {
+                // `?` doesn't work within `select!` so it only picks what to do
+                let changed = tokio::select! {
+                    _ = interval.tick() => false,
+                    Ok(()) = layer.changed() => true,
+                };
+
+                if changed {
+                    image = Some(Self::render(layer.borrow().as_deref())?);
+                } else {
+                    yield image.take().map_or(Content::Unchanged, Content::Frame);
+                }
+            }
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "layer"
+    }
+}
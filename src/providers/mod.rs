@@ -1,5 +1,31 @@
+#[cfg(unix)]
+pub(crate) mod badges;
 pub(crate) mod clock;
 #[cfg(feature = "crypto")]
 pub(crate) mod coindesk;
+#[cfg(feature = "stats")]
+pub(crate) mod dashboard;
+#[cfg(feature = "feeds")]
+pub(crate) mod feed;
+#[cfg(feature = "gif")]
+pub(crate) mod gif;
+#[cfg(feature = "git")]
+pub(crate) mod git;
+#[cfg(all(feature = "http", any(feature = "dbus-support", target_os = "windows")))]
+pub(crate) mod lastfm;
+#[cfg(unix)]
+pub(crate) mod layer;
 #[cfg(any(feature = "dbus-support", target_os = "windows"))]
 pub(crate) mod music;
+#[cfg(unix)]
+pub(crate) mod pomodoro;
+pub(crate) mod reminders;
+#[cfg(feature = "http")]
+pub(crate) mod remote;
+#[cfg(feature = "http")]
+pub(crate) mod streams;
+pub(crate) mod ticker;
+#[cfg(any(feature = "dbus-support", target_os = "windows"))]
+pub(crate) mod upnext;
+#[cfg(feature = "worldclock")]
+pub(crate) mod worldclock;
@@ -1,35 +1,48 @@
-use crate::render::display::ContentProvider;
-#[cfg(not(target_os = "windows"))]
-use anyhow::anyhow;
-use anyhow::Result;
+use crate::{
+    backend,
+    backend::{Backend, Player},
+    render::{
+        clock::{SharedClock, SystemClock},
+        display::{Content, ContentProvider},
+    },
+};
+use anyhow::{anyhow, Result};
 use async_stream::try_stream;
-#[cfg(not(target_os = "windows"))]
-use embedded_graphics::prelude::Primitive;
-#[cfg(not(target_os = "windows"))]
-use embedded_graphics::primitives::{Line, PrimitiveStyle};
 use embedded_graphics::{
-    geometry::Size, image::Image, pixelcolor::BinaryColor, prelude::Point, Drawable,
+    geometry::{OriginDimensions, Size},
+    image::Image,
+    pixelcolor::BinaryColor,
+    prelude::{Point, Primitive},
+    primitives::{Line, PointsIter, PrimitiveStyle, Rectangle},
+    Drawable,
 };
 use futures_core::stream::Stream;
 use linkme::distributed_slice;
 
-use log::info;
+use log::{info, warn};
 use tinybmp::Bmp;
 use tokio::time;
 
+#[cfg(feature = "art")]
+use crate::render::art::{self, AlbumArtWidget};
 use crate::render::{
+    glyph::Glyph,
+    iconpack, icons,
     scheduler::{ContentWrapper, CONTENT_PROVIDERS},
-    text::{ScrollableBuilder, StatefulScrollable},
+    text::{ScrollMode, ScrollableBuilder, StatefulScrollable},
 };
-use apex_music::{AsyncPlayer, Metadata, Progress};
+use apex_music::{Metadata, PlayerEvent, Progress};
 use config::Config;
 use embedded_graphics::{
     mono_font::{ascii, MonoTextStyle},
-    text::{Baseline, Text},
+    text::{renderer::TextRenderer, Baseline, Text},
 };
 use futures::StreamExt;
-use std::{convert::TryInto, sync::Arc};
-use tokio::time::{Duration, MissedTickBehavior};
+use std::{
+    convert::{TryFrom, TryInto},
+    sync::Arc,
+};
+use tokio::time::{Duration, Instant, MissedTickBehavior};
 
 use apex_hardware::FrameBuffer;
 use apex_music::PlaybackStatus;
@@ -48,20 +61,12 @@ lazy_static! {
     static ref NOTE_BMP: Bmp<'static, BinaryColor> =
         Bmp::<BinaryColor>::from_slice(NOTE_ICON).expect("Failed to parse BMP for note icon!");
 }
-#[cfg(target_os = "windows")]
-lazy_static! {
-// Windows doesn't expose the current progress within the song so we don't draw
-// it here TODO: Spice this up?
-static ref PLAYER_TEMPLATE: FrameBuffer = FrameBuffer::new();
-}
-
-#[cfg(not(target_os = "windows"))]
 lazy_static! {
 static ref PLAYER_TEMPLATE: FrameBuffer = {
     let mut base = FrameBuffer::new();
     let style = PrimitiveStyle::with_stroke(BinaryColor::On, 1);
 
-    let points = vec![
+    let points = [
         (Point::new(0, 39), Point::new(127, 39)),
         (Point::new(0, 39), Point::new(0, 39 - 5)),
         (Point::new(127, 39), Point::new(127, 39 - 5)),
@@ -80,21 +85,27 @@ static ref PLAYER_TEMPLATE: FrameBuffer = {
     base
 };
 }
+/// Draws the large icon in the top left corner, the icon pack may replace it.
+fn draw_large_icon(base: &mut FrameBuffer, key: &str, bundled: &Bmp<'static, BinaryColor>) {
+    let position = Point::new(5, 5);
+    match iconpack::registry().get(key, Size::new(24, 24)) {
+        Some(icon) => icon.draw_at(position, base),
+        None => Image::new(bundled, position).draw(base),
+    }
+    .expect("Failed to prepare template for music player");
+}
+
 lazy_static! {
     static ref PLAY_TEMPLATE: FrameBuffer = {
         let mut base = *PLAYER_TEMPLATE;
-        Image::new(&*NOTE_BMP, Point::new(5, 5))
-            .draw(&mut base)
-            .expect("Failed to prepare 'play' template for music player");
+        draw_large_icon(&mut base, "note", &NOTE_BMP);
         base
     };
 }
 lazy_static! {
     static ref PAUSE_TEMPLATE: FrameBuffer = {
         let mut base = *PLAYER_TEMPLATE;
-        Image::new(&*PAUSE_BMP, Point::new(5, 5))
-            .draw(&mut base)
-            .expect("Failed to prepare 'pause' template for music player");
+        draw_large_icon(&mut base, "pause", &PAUSE_BMP);
         base
     };
 }
@@ -114,16 +125,106 @@ lazy_static! {
     };
 }
 
+const PLAYING_GLYPH: Glyph = Glyph::new(
+    &[
+        0b10000, //
+        0b11100, //
+        0b11111, //
+        0b11100, //
+        0b10000, //
+    ],
+    5,
+);
+const PAUSED_GLYPH: Glyph = Glyph::new(
+    &[
+        0b11011, //
+        0b11011, //
+        0b11011, //
+        0b11011, //
+        0b11011, //
+    ],
+    5,
+);
+const STOPPED_GLYPH: Glyph = Glyph::new(
+    &[
+        0b11111, //
+        0b11111, //
+        0b11111, //
+        0b11111, //
+        0b11111, //
+    ],
+    5,
+);
+
+/// The heights the equalizer bars go through, every bar has its own sequence
+/// so they don't move in lockstep
+const EQUALIZER_HEIGHTS: [[u32; 8]; 3] = [
+    [2, 4, 5, 3, 1, 3, 4, 2],
+    [5, 3, 1, 2, 4, 5, 2, 3],
+    [3, 1, 2, 4, 5, 2, 1, 4],
+];
+/// Where the equalizer goes unless configured otherwise, right below the icon
+const DEFAULT_EQUALIZER_POSITION: Point = Point::new(5, 29);
+
+/// A tiny three bar equalizer that bounces while the music is playing. It
+/// doesn't know anything about the actual audio, it's just there to show that
+/// something is playing.
+#[derive(Debug, Copy, Clone)]
+pub struct Equalizer {
+    position: Point,
+}
+
+impl Equalizer {
+    const BAR_WIDTH: u32 = 2;
+    const HEIGHT: u32 = 5;
+
+    pub fn new(position: Point) -> Self {
+        Self { position }
+    }
+
+    fn draw(self, display: &mut FrameBuffer, status: PlaybackStatus, frame: u32) -> Result<()> {
+        let width = Self::BAR_WIDTH * 3 + 2;
+        Rectangle::new(self.position, Size::new(width, Self::HEIGHT))
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::Off))
+            .draw(display)?;
+
+        for (i, heights) in EQUALIZER_HEIGHTS.iter().enumerate() {
+            // Anything but playing keeps the bars flat
+            let height = match status {
+                PlaybackStatus::Playing => heights[frame as usize % heights.len()],
+                PlaybackStatus::Paused | PlaybackStatus::Stopped => 1,
+            };
+            let x = self.position.x + (i as u32 * (Self::BAR_WIDTH + 1)) as i32;
+            let y = self.position.y + (Self::HEIGHT - height) as i32;
+            Rectangle::new(Point::new(x, y), Size::new(Self::BAR_WIDTH, height))
+                .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                .draw(display)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// How many updates the checkerboard of a paused track takes to shift by one
+/// pixel, the player is polled every 100ms
+const DIM_SHIFT_INTERVAL: u32 = 10;
+
 static UNKNOWN_TITLE: &str = "Unknown title";
 static UNKNOWN_ARTIST: &str = "Unknown artist";
 
 const RECONNECT_DELAY: u64 = 5;
+/// How long a track stays on the display before the next one may replace it
+/// unless configured otherwise, in milliseconds
+const DEFAULT_MIN_DISPLAY_TIME: u64 = 1000;
+/// How often the display is redrawn from the last known state, in milliseconds
+const RENDER_INTERVAL: u64 = 100;
 
 #[distributed_slice(CONTENT_PROVIDERS)]
-static PROVIDER_INIT: fn(&Config) -> Result<Box<dyn ContentWrapper>> = register_callback;
+static PROVIDER_INIT: fn(&Config, &SharedClock) -> Result<Box<dyn ContentWrapper>> =
+    register_callback;
 
 #[allow(clippy::unnecessary_wraps)]
-fn register_callback(config: &Config) -> Result<Box<dyn ContentWrapper>> {
+fn register_callback(config: &Config, clock: &SharedClock) -> Result<Box<dyn ContentWrapper>> {
     info!("Registering MPRIS2 display source.");
 
     let player = match config.get_str("mpris2.preferred_player") {
@@ -131,13 +232,219 @@ fn register_callback(config: &Config) -> Result<Box<dyn ContentWrapper>> {
         Err(_) => MediaPlayerBuilder::new(),
     };
 
+    let player = player
+        .with_clock(clock.clone())
+        .with_source_indicator(config.get_bool("mpris2.show_source").unwrap_or(false))
+        .with_status_icon(config.get_bool("mpris2.status_icon").unwrap_or(false))
+        .with_dimming(config.get_bool("mpris2.dim_paused").unwrap_or(false))
+        .with_min_display_time(Duration::from_millis(
+            config
+                .get_int("mpris2.min_display_time")
+                .map_or(DEFAULT_MIN_DISPLAY_TIME, |millis| millis.max(0) as u64),
+        ));
+
+    let player = player.with_backends(backend::configured(config));
+
+    let player = match config.get_str("mpris2.scroll_mode").as_deref() {
+        Ok("fixed-speed") => player.with_scroll_mode(ScrollMode::FixedSpeed(
+            config
+                .get_int("mpris2.scroll_speed")
+                .map_or(DEFAULT_SCROLL_SPEED, |speed| speed.max(1) as u32),
+        )),
+        Ok("fixed-duration") => player.with_scroll_mode(ScrollMode::FixedDuration(
+            config
+                .get_int("mpris2.scroll_duration")
+                .map_or(DEFAULT_SCROLL_DURATION, |seconds| seconds.max(1) as u32),
+        )),
+        Ok(mode) => {
+            warn!("Unknown scroll mode: {}", mode);
+            player
+        }
+        Err(_) => player,
+    };
+
+    let player = player.with_player_priority(backend::configured_priority(config));
+
+    let mut artist_format = ArtistFormat::default();
+    if let Ok(separator) = config.get_str("mpris2.artist_separator") {
+        artist_format.separator = separator;
+    }
+    if let Ok(max) = config.get_int("mpris2.max_artists") {
+        artist_format.max = Some(max.max(1) as usize);
+    }
+    let player = player.with_artist_format(artist_format);
+
+    let player = if config.get_bool("mpris2.equalizer").unwrap_or(false) {
+        let position = if let Ok(position) = config.get_array("mpris2.equalizer_position") {
+            let coordinates = position
+                .into_iter()
+                .map(config::Value::into_int)
+                .collect::<Result<Vec<_>, _>>();
+            if let Ok([x, y]) = coordinates.as_deref() {
+                Point::new(*x as i32, *y as i32)
+            } else {
+                warn!("The equalizer position has to be two numbers, e.g. [5, 29]");
+                DEFAULT_EQUALIZER_POSITION
+            }
+        } else {
+            DEFAULT_EQUALIZER_POSITION
+        };
+        player.with_equalizer(Equalizer::new(position))
+    } else {
+        player
+    };
+
+    let player = match config
+        .get_str("mpris2.time_display")
+        .map(TimeDisplay::try_from)
+    {
+        Ok(Ok(time_display)) => player.with_time_display(time_display),
+        Ok(Err(e)) => {
+            warn!("{}", e);
+            player
+        }
+        Err(_) => player,
+    };
+
+    let player = {
+        let album_art = config.get_bool("mpris2.album_art").unwrap_or(false);
+        if album_art && cfg!(not(feature = "art")) {
+            warn!("Showing album art requires the `art` feature!");
+        }
+        player.with_album_art(album_art)
+    };
+
+    #[cfg(target_os = "linux")]
+    let player = {
+        let read_file_tags = config.get_bool("mpris2.read_file_tags").unwrap_or(false);
+        if read_file_tags && cfg!(not(feature = "tags")) {
+            warn!("Reading tags from files requires the `tags` feature!");
+        }
+        player.with_file_tags(read_file_tags)
+    };
+
     Ok(Box::new(player))
 }
 
+#[derive(Debug, Copy, Clone)]
+/// Represents the options a user can choose for the time label next to the
+/// progress bar
+pub enum TimeDisplay {
+    /// Shows how much of the track has been played, e.g. `1:02`
+    Elapsed,
+    /// Shows how much of the track is left, e.g. `-2:22`
+    Remaining,
+    /// Shows both of the above, e.g. `1:02 -2:22`
+    Both,
+}
+
+impl TryFrom<String> for TimeDisplay {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "elapsed" => Ok(TimeDisplay::Elapsed),
+            "remaining" => Ok(TimeDisplay::Remaining),
+            "both" => Ok(TimeDisplay::Both),
+            _ => Err(anyhow!("Unknown time display: {}!", value)),
+        }
+    }
+}
+
+/// How the artists of a track are joined into a single line
+#[derive(Debug, Clone)]
+pub struct ArtistFormat {
+    /// Goes between two artists
+    separator: String,
+    /// How many artists are listed before the rest is summed up as `feat. +N`
+    max: Option<usize>,
+}
+
+impl Default for ArtistFormat {
+    fn default() -> Self {
+        Self {
+            separator: String::from(", "),
+            max: None,
+        }
+    }
+}
+
+impl ArtistFormat {
+    /// Joins the artists, e.g. `Foo, Bar feat. +2` when only two of four
+    /// artists may be listed.
+    pub fn format(&self, artists: &[String]) -> String {
+        let max = self.max.unwrap_or(usize::MAX).max(1);
+        let joined = artists[..artists.len().min(max)].join(&self.separator);
+
+        // `max` is at least one so a single artist is always listed in full
+        match artists.len().saturating_sub(max) {
+            0 => joined,
+            hidden => format!("{} feat. +{}", joined, hidden),
+        }
+    }
+}
+
+/// Formats a duration given in microseconds as `m:ss` or `h:mm:ss`.
+fn format_time(micros: u64) -> String {
+    let seconds = micros / 1_000_000;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+impl TimeDisplay {
+    /// Formats the label for the given position and length, both in
+    /// microseconds.
+    pub fn format(self, position: u64, length: u64) -> String {
+        let elapsed = format_time(position);
+
+        // There's nothing to count down from if the player doesn't know the length
+        if length == 0 {
+            return elapsed;
+        }
+
+        let remaining = format!("-{}", format_time(length.saturating_sub(position)));
+
+        match self {
+            TimeDisplay::Elapsed => elapsed,
+            TimeDisplay::Remaining => remaining,
+            TimeDisplay::Both => format!("{} {}", elapsed, remaining),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct MediaPlayerBuilder {
     /// If a preference for the player is wanted specify this field
     name: Option<Arc<String>>,
+    /// The backends to try in order, the first one that works is used
+    backends: Vec<Backend>,
+    /// The players to prefer in order when several are open, the preferred
+    /// player included. Only used by the Windows backend
+    player_priority: Vec<String>,
+    /// Which of the optional parts are drawn
+    layout: Layout,
+    /// How long a track is shown at least before the next one replaces it
+    min_display_time: Duration,
+    /// How fast the title and artist scroll, one pixel per update by default
+    scroll_mode: Option<ScrollMode>,
+    /// Which time label to draw next to the progress bar, if any
+    time_display: Option<TimeDisplay>,
+    /// How multiple artists are displayed
+    artist_format: ArtistFormat,
+    /// Where to draw the equalizer, if at all
+    equalizer: Option<Equalizer>,
+    /// Whether to replace the play/pause icon with the album art
+    album_art: bool,
+    /// Whether to fill in missing metadata from the tags of local files
+    #[cfg(target_os = "linux")]
+    read_file_tags: bool,
+    /// The clock of the scheduler, the system clock unless one is given
+    clock: Option<SharedClock>,
 }
 
 // Ok so the plan for the MPRIS2 module is to wait for two DBUS events
@@ -149,31 +456,375 @@ pub struct MediaPlayerBuilder {
 // queue. Upon receiving the event our code should pull the metadata from the
 // player.
 
+/// Turns the raw identifier of a player into something presentable, e.g.
+/// `org.mpris.MediaPlayer2.firefox.instance1234` becomes `firefox` and
+/// `SpotifyAB.SpotifyMusic_zpdnekdrzrea0!Spotify` becomes `Spotify`.
+fn friendly_name(name: &str) -> &str {
+    let name = name.strip_prefix("org.mpris.MediaPlayer2.").unwrap_or(name);
+    // Browsers and some other players append a suffix for each running instance
+    let name = name.split(".instance").next().unwrap_or(name);
+    // Windows uses the application user model id which may contain a package name
+    let name = name.rsplit('!').next().unwrap_or(name);
+    name.strip_suffix(".exe").unwrap_or(name)
+}
+
+/// Smooths out the position reported by the player.
+///
+/// Most players only update their position every now and then which makes the
+/// progress bar jump around. In between those updates the position is advanced
+/// by the time that has passed while the track is playing. Whenever the
+/// player reports a new position we snap to it right away, that way seeking is
+/// reflected immediately instead of being animated.
+#[derive(Debug, Copy, Clone, Default)]
+struct PositionTracker {
+    /// The last position the player reported, in microseconds
+    reported: i64,
+    /// When `reported` was received, unset until the first update
+    updated: Option<Instant>,
+}
+
+impl PositionTracker {
+    /// Takes the position that was just reported by the player at `now` and
+    /// returns the one that should be displayed.
+    fn update(&mut self, reported: i64, status: PlaybackStatus, length: u64, now: Instant) -> i64 {
+        let playing = matches!(status, PlaybackStatus::Playing);
+        match self.updated {
+            // Nothing new from the player so we fill the gap ourselves. Players that don't
+            // know the length of the track most likely don't know the position either.
+            Some(updated) if playing && length != 0 && reported == self.reported => {
+                let elapsed = now.duration_since(updated).as_micros() as i64;
+                (self.reported + elapsed).min(length as i64)
+            }
+            // A real update or the track isn't playing, both mean we show what the
+            // player tells us
+            _ => {
+                self.reported = reported;
+                self.updated = Some(now);
+                reported
+            }
+        }
+    }
+}
+
+/// Pixels per second in the `fixed-speed` scroll mode unless configured
+/// otherwise, the same pace as without a scroll mode
+const DEFAULT_SCROLL_SPEED: u32 = 10;
+/// Seconds per cycle in the `fixed-duration` scroll mode unless configured
+/// otherwise
+const DEFAULT_SCROLL_DURATION: u32 = 10;
+
+/// How long a track has to look like a live stream before it's shown as one.
+/// Players tend to report a length of zero for a moment when a track starts.
+const LIVE_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Recognizes live streams and podcasts that don't have a proper length,
+/// they either report none at all, a position past the end or a length that
+/// keeps on growing while they're playing.
+#[derive(Debug, Clone, Default)]
+struct LiveDetector {
+    /// The title and length of the track we saw last, to notice changes
+    track: Option<(String, u64)>,
+    /// How often the length grew during the current track
+    grown: u8,
+    /// Since when the current track looks like a live stream
+    since: Option<Instant>,
+}
+
+impl LiveDetector {
+    /// Returns whether the track should be displayed as a live stream.
+    /// `position` and `length` are in microseconds, as reported by the
+    /// player at `now`.
+    fn update(&mut self, title: &str, position: i64, length: u64, now: Instant) -> bool {
+        match &mut self.track {
+            Some((previous, previous_length)) if previous == title => {
+                // The jump from zero is simply the player figuring out the length
+                if *previous_length != 0 && length > *previous_length {
+                    self.grown = self.grown.saturating_add(1);
+                }
+                *previous_length = length;
+            }
+            track => {
+                *track = Some((title.to_string(), length));
+                self.grown = 0;
+                self.since = None;
+            }
+        }
+
+        // A single correction of the length isn't unheard of, growing twice is
+        let suspicious = length == 0 || position > length as i64 || self.grown >= 2;
+        if !suspicious {
+            self.since = None;
+            return false;
+        }
+
+        now.saturating_duration_since(*self.since.get_or_insert(now)) >= LIVE_DEBOUNCE
+    }
+}
+
+/// The title and artists, which tell tracks apart
+type Track = (String, Vec<String>);
+
+/// Keeps quickly skipping through a playlist from flashing every track by. A
+/// track that replaces one that was shown for less than the minimum display
+/// time has to keep playing for that long before it's shown, until then we're
+/// skipping. This is separate from the events of the player, those arrive in
+/// bursts for every single track.
+#[derive(Debug, Clone)]
+struct SkipCoalescer {
+    min_display: Duration,
+    /// The track on the display and since when it's there
+    shown: Option<(Track, Instant)>,
+    /// The track we're going to show once it stuck around for long enough
+    pending: Option<(Track, Instant)>,
+}
+
+impl SkipCoalescer {
+    fn new(min_display: Duration) -> Self {
+        Self {
+            min_display,
+            shown: None,
+            pending: None,
+        }
+    }
+
+    /// Returns whether we're in the middle of skipping, with `track` playing
+    /// at `now`.
+    fn update(&mut self, track: Track, now: Instant) -> bool {
+        if let Some((shown, since)) = &self.shown {
+            if *shown == track {
+                self.pending = None;
+                return false;
+            }
+
+            let pending = self
+                .pending
+                .as_ref()
+                .filter(|(pending, _)| *pending == track)
+                .map(|(_, since)| *since);
+            let settled = match pending {
+                Some(since) => now - since >= self.min_display,
+                // The first skip after a track had its time on the display goes through
+                // right away
+                None => self.pending.is_none() && now - *since >= self.min_display,
+            };
+            if !settled {
+                self.pending = Some((track, pending.unwrap_or(now)));
+                return true;
+            }
+        }
+
+        self.shown = Some((track, now));
+        self.pending = None;
+        false
+    }
+}
+
+/// The optional parts of the music source that are drawn.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Layout {
+    /// Whether to show which player the music is coming from
+    pub show_source: bool,
+    /// Whether to draw a play/pause/stop glyph next to the progress bar
+    pub status_icon: bool,
+    /// Whether to dim the texts while the track isn't playing
+    pub dim_paused: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct MediaPlayerRenderer {
     artist: StatefulScrollable,
     title: StatefulScrollable,
+    layout: Layout,
+    source: Option<String>,
+    time_display: Option<TimeDisplay>,
+    artist_format: ArtistFormat,
+    equalizer: Option<Equalizer>,
+    position: PositionTracker,
+    live: LiveDetector,
+    /// Counts the updates to animate the dimming
+    frame: u32,
+    #[cfg(feature = "art")]
+    art: Option<AlbumArtWidget>,
+    clock: SharedClock,
+}
+
+/// Dims a region by turning off every other pixel in a checkerboard pattern.
+/// Shifting `phase` moves the pattern by a pixel which keeps it subtly alive.
+fn dim(display: &mut FrameBuffer, region: Rectangle, phase: u32) {
+    for Point { x, y } in region.points() {
+        if let (x @ 0..=127, y @ 0..=39) = (x, y) {
+            if (x as u32 + y as u32 + phase) % 2 == 0 {
+                display.framebuffer.set((x + y * 128 + 8) as usize, false);
+            }
+        }
+    }
 }
 
 impl MediaPlayerRenderer {
-    fn new() -> Result<Self> {
-        let artist = ScrollableBuilder::new()
+    fn new(
+        layout: Layout,
+        time_display: Option<TimeDisplay>,
+        artist_format: ArtistFormat,
+        equalizer: Option<Equalizer>,
+        scroll_mode: Option<ScrollMode>,
+        clock: SharedClock,
+    ) -> Result<Self> {
+        let mut artist = ScrollableBuilder::new()
             .with_text(UNKNOWN_ARTIST)
             .with_custom_spacing(10)
             .with_position(Point::new(5 + 3 + 24, 3 + 10))
             .with_projection(Size::new(16 * 6, 10));
-        let title = ScrollableBuilder::new()
+        let mut title = ScrollableBuilder::new()
             .with_text(UNKNOWN_TITLE)
             .with_custom_spacing(10)
             .with_position(Point::new(5 + 3 + 24, 3))
             .with_projection(Size::new(16 * 6, 10));
+        if let Some(mode) = scroll_mode {
+            artist = artist.with_scroll_mode(mode, clock.clone());
+            title = title.with_scroll_mode(mode, clock.clone());
+        }
 
         Ok(Self {
             artist: artist.try_into()?,
             title: title.try_into()?,
+            layout,
+            source: None,
+            time_display,
+            artist_format,
+            equalizer,
+            position: PositionTracker::default(),
+            live: LiveDetector::default(),
+            frame: 0,
+            #[cfg(feature = "art")]
+            art: None,
+            clock,
         })
     }
 
+    /// Draws the album art in place of the play/pause icon once it's loaded.
+    #[cfg(feature = "art")]
+    pub fn set_artwork(&mut self, art: AlbumArtWidget) {
+        self.art = Some(art);
+    }
+
+    /// Draws the playback status in front of the progress bar and returns the x
+    /// coordinate where the bar starts.
+    fn draw_status(&self, display: &mut FrameBuffer, status: PlaybackStatus) -> Result<i32> {
+        if !self.layout.status_icon {
+            return Ok(0);
+        }
+
+        let (key, glyph) = match status {
+            PlaybackStatus::Playing => ("playing", PLAYING_GLYPH),
+            PlaybackStatus::Paused => ("paused", PAUSED_GLYPH),
+            PlaybackStatus::Stopped => ("stopped", STOPPED_GLYPH),
+        };
+        let left = glyph.size().width as i32 + 3;
+
+        // Move the left edge of the bar's border out of the way
+        Rectangle::new(Point::new(0, 39 - 5), Size::new(left as u32, 6))
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::Off))
+            .draw(display)?;
+        Line::new(Point::new(left, 39), Point::new(left, 39 - 5))
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+            .draw(display)?;
+
+        let position = Point::new(1, 39 - 5);
+        match iconpack::registry().get(key, glyph.size()) {
+            Some(icon) => icon.draw_at(position, display)?,
+            None => glyph.at(position).draw(display)?,
+        }
+
+        Ok(left)
+    }
+
+    /// Sets the name of the player that is currently being displayed.
+    pub fn set_source(&mut self, name: &str) {
+        self.source = Some(friendly_name(name).to_string());
+    }
+
+    /// Draws the time label right above the progress bar and returns the x
+    /// coordinate of its left edge so other elements can avoid it. Live
+    /// streams are always labelled as such, with the elapsed time if a time
+    /// label is wanted.
+    fn draw_time(
+        &self,
+        display: &mut FrameBuffer,
+        position: i64,
+        length: u64,
+        live: bool,
+    ) -> Result<i32> {
+        let position = position.max(0) as u64;
+
+        let text = match self.time_display {
+            Some(_) if live => format!("LIVE {}", format_time(position)),
+            None if live => String::from("LIVE"),
+            // Some backends don't report any timeline information at all
+            Some(time_display) if position != 0 || length != 0 => {
+                time_display.format(position, length)
+            }
+            _ => return Ok(128),
+        };
+        let style = MonoTextStyle::new(&ascii::FONT_4X6, BinaryColor::On);
+        let metrics = style.measure_string(&text, Point::zero(), Baseline::Top);
+        let left = 128 - 3 - metrics.bounding_box.size.width as i32;
+
+        Text::with_baseline(
+            &text,
+            Point::new(left, 3 + 10 + 10 + 4),
+            style,
+            Baseline::Top,
+        )
+        .draw(display)?;
+
+        Ok(left)
+    }
+
+    fn draw_source(&self, display: &mut FrameBuffer, right: i32) -> Result<()> {
+        let source = match &self.source {
+            Some(source) if self.layout.show_source => source,
+            _ => return Ok(()),
+        };
+
+        let mut offset = Point::new(5 + 3 + 24, 3 + 10 + 10);
+
+        // Known players get a tiny icon in front of their name, the icon pack may
+        // have icons for any player
+        let position = offset + Point::new(0, 1);
+        if let Some(icon) = iconpack::registry().get(&source.to_lowercase(), Size::new(8, 8)) {
+            icon.draw_at(position, display)?;
+            offset += Point::new(icon.size().width as i32 + 3, 0);
+        } else if let Some(icon) = icons::player_icon(source) {
+            Image::new(icon, position).draw(display)?;
+            offset += Point::new(icon.size().width as i32 + 3, 0);
+        }
+
+        // Cut the name off before it runs into the time label
+        let style = MonoTextStyle::new(&ascii::FONT_6X10, BinaryColor::On);
+        let characters =
+            (right - offset.x - 3).max(0) / ascii::FONT_6X10.character_size.width as i32;
+        let source = source.chars().take(characters as usize).collect::<String>();
+        Text::with_baseline(&source, offset, style, Baseline::Top).draw(display)?;
+
+        Ok(())
+    }
+
+    /// Shown instead of the track while skipping through tracks quickly, it
+    /// stays the same no matter how many tracks go by.
+    pub fn skipping(&self) -> Result<FrameBuffer> {
+        let mut display = *PLAY_TEMPLATE;
+        let style = MonoTextStyle::new(&ascii::FONT_6X10, BinaryColor::On);
+        Text::with_baseline(
+            "Skipping...",
+            Point::new(5 + 3 + 24, 3),
+            style,
+            Baseline::Top,
+        )
+        .draw(&mut display)?;
+        self.draw_source(&mut display, 128)?;
+        Ok(display)
+    }
+
     pub fn update<T: Metadata>(&mut self, progress: &Progress<T>) -> Result<FrameBuffer> {
         let mut display = match progress.status {
             PlaybackStatus::Playing => *PLAY_TEMPLATE,
@@ -182,38 +833,71 @@ impl MediaPlayerRenderer {
 
         let metadata = &progress.metadata;
 
-        #[cfg(not(target_os = "windows"))]
-        {
-            let length = metadata.length().unwrap_or(0) as f64;
+        #[cfg(feature = "art")]
+        if let (Some(art), Ok(url)) = (&self.art, metadata.art_url()) {
+            art.draw_for(&url, &mut display)?;
+        }
 
-            let current = progress.position as f64;
+        let title = metadata.title()?;
+        let length = metadata.length().unwrap_or(0);
+        let now = self.clock.now();
+        let live = self.live.update(&title, progress.position, length, now);
+        let position = self
+            .position
+            .update(progress.position, progress.status, length, now);
+        let left = self.draw_status(&mut display, progress.status)?;
+
+        // There's no end to show progress towards, a dashed bar makes that obvious
+        if live {
+            let style = PrimitiveStyle::with_stroke(BinaryColor::On, 3);
+            for x in (left + 3..128 - 3).step_by(4) {
+                Line::new(Point::new(x, 35), Point::new((x + 1).min(128 - 4), 35))
+                    .into_styled(style)
+                    .draw(&mut display)?;
+            }
+        } else {
+            let length = length as f64;
+
+            let current = position as f64;
 
             let completion = (current / length).clamp(0_f64, 1_f64);
 
-            let pixels = (128_f64 - 2_f64 * 3_f64) * completion;
+            let pixels = (128_f64 - f64::from(left) - 2_f64 * 3_f64) * completion;
             let style = PrimitiveStyle::with_stroke(BinaryColor::On, 3);
-            Line::new(Point::new(3, 35), Point::new(pixels as i32 + 3, 35))
-                .into_styled(style)
-                .draw(&mut display)?;
+            Line::new(
+                Point::new(left + 3, 35),
+                Point::new(left + pixels as i32 + 3, 35),
+            )
+            .into_styled(style)
+            .draw(&mut display)?;
         }
 
-        let artists = metadata.artists()?;
-        let title = metadata.title()?;
+        let artists = self.artist_format.format(&metadata.artists()?);
 
+        // Changed texts start over from the beginning
         if let Ok(false) = self.artist.update(&artists) {
-            if artists.len() > 16 {
-                self.artist.text.scroll();
-            }
+            self.artist.text.scroll();
         }
 
         if let Ok(false) = self.title.update(&title) {
-            if title.len() > 16 {
-                self.title.text.scroll();
-            }
+            self.title.text.scroll();
         }
 
         self.title.text.draw(&mut display)?;
         self.artist.text.draw(&mut display)?;
+        let right = self.draw_time(&mut display, position, length, live)?;
+        self.draw_source(&mut display, right)?;
+
+        if let Some(equalizer) = self.equalizer {
+            equalizer.draw(&mut display, progress.status, self.frame)?;
+        }
+
+        self.frame = self.frame.wrapping_add(1);
+        if self.layout.dim_paused && !matches!(progress.status, PlaybackStatus::Playing) {
+            // Everything but the icon and the progress bar
+            let region = Rectangle::new(Point::new(5 + 3 + 24, 0), Size::new(128, 39 - 5));
+            dim(&mut display, region, self.frame / DIM_SHIFT_INTERVAL);
+        }
 
         Ok(display)
     }
@@ -225,13 +909,79 @@ impl MediaPlayerBuilder {
         self
     }
 
+    pub fn with_time_display(mut self, time_display: TimeDisplay) -> Self {
+        self.time_display = Some(time_display);
+        self
+    }
+
+    pub fn with_source_indicator(mut self, show_source: bool) -> Self {
+        self.layout.show_source = show_source;
+        self
+    }
+
+    pub fn with_status_icon(mut self, status_icon: bool) -> Self {
+        self.layout.status_icon = status_icon;
+        self
+    }
+
+    pub fn with_dimming(mut self, dim_paused: bool) -> Self {
+        self.layout.dim_paused = dim_paused;
+        self
+    }
+
+    pub fn with_min_display_time(mut self, min_display_time: Duration) -> Self {
+        self.min_display_time = min_display_time;
+        self
+    }
+
+    pub fn with_scroll_mode(mut self, scroll_mode: ScrollMode) -> Self {
+        self.scroll_mode = Some(scroll_mode);
+        self
+    }
+
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    pub fn with_backends(mut self, backends: Vec<Backend>) -> Self {
+        self.backends = backends;
+        self
+    }
+
+    pub fn with_player_priority(mut self, player_priority: Vec<String>) -> Self {
+        self.player_priority = player_priority;
+        self
+    }
+
+    pub fn with_equalizer(mut self, equalizer: Equalizer) -> Self {
+        self.equalizer = Some(equalizer);
+        self
+    }
+
+    pub fn with_artist_format(mut self, artist_format: ArtistFormat) -> Self {
+        self.artist_format = artist_format;
+        self
+    }
+
+    pub fn with_album_art(mut self, album_art: bool) -> Self {
+        self.album_art = album_art;
+        self
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn with_file_tags(mut self, read_file_tags: bool) -> Self {
+        self.read_file_tags = read_file_tags;
+        self
+    }
+
     pub fn new() -> Self {
         Self::default()
     }
 }
 
 impl ContentProvider for MediaPlayerBuilder {
-    type ContentStream<'a> = impl Stream<Item = Result<FrameBuffer>> + 'a;
+    type ContentStream<'a> = impl Stream<Item = Result<Content>> + 'a;
 
     // This needs to be enabled until full GAT support is here
     #[allow(clippy::needless_lifetimes)]
@@ -241,13 +991,34 @@ impl ContentProvider for MediaPlayerBuilder {
             self.name
         );
 
-        let mut renderer = MediaPlayerRenderer::new()?;
+        let clock = self.clock.clone().unwrap_or_else(SystemClock::shared);
+        let mut renderer = MediaPlayerRenderer::new(
+            self.layout,
+            self.time_display,
+            self.artist_format.clone(),
+            self.equalizer,
+            self.scroll_mode,
+            clock.clone(),
+        )?;
+
+        #[cfg(feature = "art")]
+        let mut artwork = self.album_art.then(|| {
+            let (loader, widget) = art::channel(Point::new(5, 5));
+            renderer.set_artwork(widget);
+            loader
+        });
 
         Ok(try_stream! {
-            #[cfg(target_os = "windows")]
-            let mpris = apex_windows::Player::new()?;
+            let mpris = Player::auto(&self.backends)
+                .await
+                .with_priority(self.player_priority.clone());
             #[cfg(target_os = "linux")]
-            let mpris = apex_mpris2::MPRIS2::new().await?;
+            let mpris = mpris.with_file_tags(self.read_file_tags);
+            // The media keys go to whichever session is shown here
+            #[cfg(target_os = "windows")]
+            if let Some(session) = mpris.selected_session() {
+                crate::mediakeys::follow(session);
+            }
             pin_mut!(mpris);
 
             let mut interval = time::interval(Duration::from_secs(RECONNECT_DELAY));
@@ -257,27 +1028,72 @@ impl ContentProvider for MediaPlayerBuilder {
                     "Trying to connect to DBUS with player preference: {:?}",
                     self.name
                 );
-                yield *IDLE_TEMPLATE;
-                #[cfg(target_os = "windows")]
-                let player = &mpris;
-                #[cfg(target_os = "linux")]
+                yield Content::Frame(*IDLE_TEMPLATE);
                 let player = mpris.wait_for_player(self.name.clone()).await?;
 
-                info!("Connected to music player: {:?}", player.name().await);
+                let mut name = player.name().await;
+                info!("Connected to music player: {:?}", name);
+                renderer.set_source(&name);
 
 
                 let tracker = mpris.stream().await?;
                 pin_mut!(tracker);
 
-                while let Some(_) = tracker.next().await {
-                    // TODO: We could probably save *some* resources here by making use of the event
-                    // that's being called but I don't see enough of a reason to do so at the moment
-                    if let Ok(progress) = player.progress().await {
-                        if let Ok(image) = renderer.update(&progress) {
-                            yield image;
+                let Ok(mut progress) = player.progress().await else {
+                    continue 'outer;
+                };
+                let mut skips = SkipCoalescer::new(self.min_display_time);
+
+                // The player is only asked again when it tells us something changed, in
+                // between we keep scrolling and moving the progress bar on our own
+                let mut render = time::interval(Duration::from_millis(RENDER_INTERVAL));
+                render.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+                loop {
+                    tokio::select! {
+                        event = tracker.next() => {
+                            let Some(event) = event else {
+                                continue 'outer;
+                            };
+                            // The backend moved on to another player, e.g. because ours was closed
+                            if event == PlayerEvent::PlayerChanged {
+                                let new = player.name().await;
+                                if new != name {
+                                    info!("Switched to music player: {:?}", new);
+                                    renderer.set_source(&new);
+                                    name = new;
+                                }
+                            }
+                            // If the player vanished the query fails and we look for a new one
+                            match player.progress().await {
+                                Ok(update) => progress = update,
+                                Err(_) => continue 'outer,
+                            }
+                        },
+                        _ = render.tick() => {}
+                    }
+
+                    let metadata = &progress.metadata;
+                    let track = (
+                        metadata.title().unwrap_or_default(),
+                        metadata.artists().unwrap_or_default(),
+                    );
+                    if skips.update(track, clock.now()) {
+                        if let Ok(image) = renderer.skipping() {
+                            yield Content::Frame(image);
                         }
-                    } else {
-                        continue 'outer;
+                        continue;
+                    }
+
+                    // The artwork is loaded in the background, nothing happens unless the
+                    // track changed. Tracks that are skipped never get that far
+                    #[cfg(feature = "art")]
+                    if let Some(loader) = &mut artwork {
+                        loader.load(metadata.art_url().ok());
+                    }
+
+                    if let Ok(image) = renderer.update(&progress) {
+                        yield Content::Frame(image);
                     }
                 }
             }
@@ -288,3 +1104,194 @@ impl ContentProvider for MediaPlayerBuilder {
         "mpris2"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{clock::MockClock, golden};
+    use chrono::Local;
+
+    struct Song {
+        title: &'static str,
+        artists: Vec<String>,
+        /// In microseconds
+        length: u64,
+    }
+
+    impl Metadata for Song {
+        fn title(&self) -> Result<String> {
+            Ok(self.title.to_string())
+        }
+
+        fn artists(&self) -> Result<Vec<String>> {
+            Ok(self.artists.clone())
+        }
+
+        fn length(&self) -> Result<u64> {
+            Ok(self.length)
+        }
+
+        fn art_url(&self) -> Result<String> {
+            Err(anyhow!("No album art"))
+        }
+    }
+
+    #[test]
+    fn renders_the_track_that_is_playing() {
+        let mut renderer = MediaPlayerRenderer::new(
+            Layout {
+                status_icon: true,
+                ..Layout::default()
+            },
+            Some(TimeDisplay::Both),
+            ArtistFormat::default(),
+            Some(Equalizer::new(DEFAULT_EQUALIZER_POSITION)),
+            None,
+            MockClock::new(Local::now()).shared(),
+        )
+        .unwrap();
+        let progress = Progress {
+            metadata: Song {
+                title: "Blue",
+                artists: vec![String::from("Joni Mitchell")],
+                length: 180_000_000,
+            },
+            position: 61_000_000,
+            status: PlaybackStatus::Playing,
+        };
+
+        golden::assert_renders_to(&renderer.update(&progress).unwrap(), "now_playing.png");
+    }
+
+    #[test]
+    fn interpolates_the_position_while_playing() {
+        let start = Instant::now();
+        let mut tracker = PositionTracker::default();
+        let length = 180_000_000;
+
+        assert_eq!(
+            tracker.update(1_000_000, PlaybackStatus::Playing, length, start),
+            1_000_000
+        );
+        // The player keeps reporting the same position
+        let later = start + Duration::from_millis(500);
+        assert_eq!(
+            tracker.update(1_000_000, PlaybackStatus::Playing, length, later),
+            1_500_000
+        );
+        // But never past the end
+        let much_later = start + Duration::from_secs(600);
+        assert_eq!(
+            tracker.update(1_000_000, PlaybackStatus::Playing, length, much_later),
+            length as i64
+        );
+    }
+
+    #[test]
+    fn snaps_to_the_position_the_player_reports() {
+        let start = Instant::now();
+        let mut tracker = PositionTracker::default();
+        let length = 180_000_000;
+        tracker.update(1_000_000, PlaybackStatus::Playing, length, start);
+
+        // Seeking
+        let later = start + Duration::from_millis(500);
+        assert_eq!(
+            tracker.update(90_000_000, PlaybackStatus::Playing, length, later),
+            90_000_000
+        );
+        // Paused tracks don't move
+        let paused = later + Duration::from_secs(1);
+        assert_eq!(
+            tracker.update(90_000_000, PlaybackStatus::Paused, length, paused),
+            90_000_000
+        );
+        // Neither do tracks without a length
+        let mut tracker = PositionTracker::default();
+        tracker.update(0, PlaybackStatus::Playing, 0, start);
+        assert_eq!(tracker.update(0, PlaybackStatus::Playing, 0, later), 0);
+    }
+
+    #[test]
+    fn tracks_without_a_length_are_live_after_a_moment() {
+        let start = Instant::now();
+        let mut detector = LiveDetector::default();
+
+        assert!(!detector.update("Radio", 0, 0, start));
+        assert!(!detector.update("Radio", 0, 0, start + LIVE_DEBOUNCE / 2));
+        assert!(detector.update("Radio", 0, 0, start + LIVE_DEBOUNCE));
+        // A proper length ends that right away
+        let later = start + LIVE_DEBOUNCE * 2;
+        assert!(!detector.update("Radio", 0, 180_000_000, later));
+    }
+
+    #[test]
+    fn positions_past_the_end_are_live() {
+        let start = Instant::now();
+        let mut detector = LiveDetector::default();
+
+        assert!(!detector.update("Podcast", 200_000_000, 180_000_000, start));
+        assert!(detector.update("Podcast", 201_000_000, 180_000_000, start + LIVE_DEBOUNCE));
+    }
+
+    #[test]
+    fn lengths_that_keep_growing_are_live() {
+        let start = Instant::now();
+        let mut detector = LiveDetector::default();
+
+        // Figuring out the length and correcting it once is fine
+        assert!(!detector.update("Stream", 0, 0, start));
+        detector.update("Stream", 0, 10_000_000, start);
+        assert!(!detector.update("Stream", 0, 11_000_000, start + LIVE_DEBOUNCE));
+        // Growing again isn't
+        detector.update("Stream", 0, 12_000_000, start + LIVE_DEBOUNCE);
+        assert!(detector.update("Stream", 0, 12_000_000, start + LIVE_DEBOUNCE * 2));
+        // The next track starts over
+        assert!(!detector.update("Song", 0, 180_000_000, start + LIVE_DEBOUNCE * 3));
+    }
+
+    fn track(title: &str) -> Track {
+        (title.to_string(), vec![String::from("Artist")])
+    }
+
+    #[test]
+    fn shows_tracks_that_stay_long_enough() {
+        let start = Instant::now();
+        let min_display = Duration::from_secs(1);
+        let mut coalescer = SkipCoalescer::new(min_display);
+
+        assert!(!coalescer.update(track("One"), start));
+        assert!(!coalescer.update(track("One"), start + min_display / 2));
+        // One had its time on the display
+        assert!(!coalescer.update(track("Two"), start + min_display));
+    }
+
+    #[test]
+    fn coalesces_quick_skips() {
+        let start = Instant::now();
+        let min_display = Duration::from_secs(1);
+        let mut coalescer = SkipCoalescer::new(min_display);
+        coalescer.update(track("One"), start);
+
+        let step = min_display / 4;
+        assert!(coalescer.update(track("Two"), start + step));
+        assert!(coalescer.update(track("Three"), start + step * 2));
+        assert!(coalescer.update(track("Four"), start + step * 3));
+        // Four keeps playing until it's settled
+        assert!(coalescer.update(track("Four"), start + step * 3 + min_display / 2));
+        assert!(!coalescer.update(track("Four"), start + step * 3 + min_display));
+        // And it's on the display now
+        assert!(!coalescer.update(track("Four"), start + step * 4 + min_display));
+    }
+
+    #[test]
+    fn going_back_to_the_shown_track_stops_skipping() {
+        let start = Instant::now();
+        let min_display = Duration::from_secs(1);
+        let mut coalescer = SkipCoalescer::new(min_display);
+        coalescer.update(track("One"), start);
+
+        assert!(coalescer.update(track("Two"), start + min_display / 4));
+        assert!(!coalescer.update(track("One"), start + min_display / 2));
+    }
+}
@@ -0,0 +1,290 @@
+use crate::{
+    instance,
+    render::{
+        clock::SharedClock,
+        display::{Content, ContentProvider},
+        scheduler::{ContentWrapper, CONTENT_PROVIDERS, TICK_LENGTH},
+    },
+};
+use anyhow::{anyhow, Result};
+use apex_hardware::FrameBuffer;
+use async_stream::try_stream;
+use chrono::NaiveDate;
+use config::Config;
+use embedded_graphics::{
+    geometry::Point,
+    mono_font::{ascii, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    prelude::Primitive,
+    primitives::{Circle, PrimitiveStyle},
+    text::{renderer::TextRenderer, Baseline, Text},
+    Drawable,
+};
+use futures::Stream;
+use lazy_static::lazy_static;
+use linkme::distributed_slice;
+use log::{info, warn};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tokio::{
+    sync::watch,
+    time,
+    time::{Duration, MissedTickBehavior},
+};
+
+lazy_static! {
+    // How many focus sessions were completed since we started. The source isn't running
+    // while something else is on the display so it catches up with the difference.
+    static ref COMPLETED: (watch::Sender<u64>, watch::Receiver<u64>) = watch::channel(0);
+}
+
+/// Counts another completed focus session.
+pub fn session_completed() {
+    COMPLETED.0.send_modify(|count| *count += 1);
+}
+
+#[doc(hidden)]
+#[distributed_slice(CONTENT_PROVIDERS)]
+pub static PROVIDER_INIT: fn(&Config, &SharedClock) -> Result<Box<dyn ContentWrapper>> =
+    register_callback;
+
+/// How many sessions make a good day unless configured otherwise
+const DEFAULT_GOAL: u32 = 8;
+/// More dots don't fit into a single row
+const MAX_GOAL: u32 = 12;
+
+#[doc(hidden)]
+#[allow(clippy::unnecessary_wraps)]
+fn register_callback(config: &Config, clock: &SharedClock) -> Result<Box<dyn ContentWrapper>> {
+    info!("Registering Pomodoro display source.");
+
+    let path = config
+        .get_str("pomodoro.path")
+        .map_or_else(|_| instance::data_dir().join("pomodoro.txt"), PathBuf::from);
+    let goal = config
+        .get_int("pomodoro.goal")
+        .map_or(DEFAULT_GOAL, |goal| goal.max(1) as u32);
+    if goal > MAX_GOAL {
+        warn!("Only {} sessions fit onto the display", MAX_GOAL);
+    }
+
+    Ok(Box::new(Pomodoro {
+        path,
+        goal: goal.min(MAX_GOAL),
+        clock: clock.clone(),
+    }))
+}
+
+/// The sessions of a single day, stored as `<date> <count>`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct Tally {
+    day: NaiveDate,
+    count: u32,
+}
+
+impl Tally {
+    fn empty(day: NaiveDate) -> Self {
+        Self { day, count: 0 }
+    }
+
+    /// Reads the tally from disk, a tally from a day other than `today`
+    /// counts as nothing.
+    fn load(path: &Path, today: NaiveDate) -> Self {
+        let tally = fs::read_to_string(path).ok().and_then(|contents| {
+            let (day, count) = contents.trim().split_once(' ')?;
+            Some(Self {
+                day: day.parse().ok()?,
+                count: count.parse().ok()?,
+            })
+        });
+
+        match tally {
+            Some(tally) if tally.day == today => tally,
+            _ => Self::empty(today),
+        }
+    }
+
+    fn save(self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, format!("{} {}\n", self.day, self.count))?;
+        Ok(())
+    }
+}
+
+/// Counts the focus sessions completed today.
+#[derive(Debug, Clone)]
+struct Pomodoro {
+    path: PathBuf,
+    /// How many dots are drawn
+    goal: u32,
+    clock: SharedClock,
+}
+
+impl Pomodoro {
+    fn today(&self) -> NaiveDate {
+        self.clock.local().naive_local().date()
+    }
+
+    fn render(&self, count: u32) -> Result<FrameBuffer> {
+        let mut buffer = FrameBuffer::new();
+        let large = MonoTextStyle::new(&ascii::FONT_10X20, BinaryColor::On);
+        let small = MonoTextStyle::new(&ascii::FONT_6X10, BinaryColor::On);
+
+        let text = count.to_string();
+        let metrics = large.measure_string(&text, Point::zero(), Baseline::Top);
+        Text::with_baseline(&text, Point::new(3, 2), large, Baseline::Top).draw(&mut buffer)?;
+        let label = if count == 1 { "session" } else { "sessions" };
+        Text::with_baseline(
+            label,
+            Point::new(3 + metrics.bounding_box.size.width as i32 + 4, 10),
+            small,
+            Baseline::Top,
+        )
+        .draw(&mut buffer)?;
+
+        // One dot per session up to the goal, anything past it doesn't show
+        let (diameter, gap) = (7, 3);
+        let width = self.goal * diameter + (self.goal - 1) * gap;
+        let left = (128 - width as i32) / 2;
+        for i in 0..self.goal {
+            let circle = Circle::new(
+                Point::new(left + (i * (diameter + gap)) as i32, 28),
+                diameter,
+            );
+            let style = if i < count {
+                PrimitiveStyle::with_fill(BinaryColor::On)
+            } else {
+                PrimitiveStyle::with_stroke(BinaryColor::On, 1)
+            };
+            circle.into_styled(style).draw(&mut buffer)?;
+        }
+
+        Ok(buffer)
+    }
+
+    fn save(&self, tally: Tally) {
+        if let Err(e) = tally.save(&self.path) {
+            warn!("Couldn't save the Pomodoro sessions: {}", e);
+        }
+    }
+}
+
+impl ContentProvider for Pomodoro {
+    type ContentStream<'a> = impl Stream<Item = Result<Content>> + 'a;
+
+    // This needs to be enabled until full GAT support is here
+    #[allow(clippy::needless_lifetimes)]
+    fn stream<'this>(&'this mut self) -> Result<Self::ContentStream<'this>> {
+        if self.path.is_dir() {
+            return Err(anyhow!("{} is a directory!", self.path.display()));
+        }
+
+        let mut interval = time::interval(Duration::from_millis(TICK_LENGTH as u64));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut completed = COMPLETED.1.clone();
+
+        Ok(try_stream! {
+            let mut tally = Tally::load(&self.path, self.today());
+            // Sessions that were completed before we first ran still count
+            let mut seen = 0;
+            yield Content::Frame(self.render(tally.count)?);
+
+            loop {
+                let total = *completed.borrow_and_update();
+                let before = tally;
+
+                // The count starts over at midnight
+                let today = self.today();
+                if tally.day != today {
+                    tally = Tally::empty(today);
+                }
+                if total != seen {
+                    tally.count += (total - seen) as u32;
+                    seen = total;
+                }
+
+                if tally == before {
+                    yield Content::Unchanged;
+                } else {
+                    self.save(tally);
+                    yield Content::Frame(self.render(tally.count)?);
+                }
+
+                tokio::select! {
+                    _ = interval.tick() => {},
+                    Ok(()) = completed.changed() => {},
+                }
+            }
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "pomodoro"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    /// A file of its own for every test, they run in parallel.
+    fn path(name: &str) -> PathBuf {
+        env::temp_dir()
+            .join(format!("apex-tux-{}", std::process::id()))
+            .join(name)
+    }
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2023, 4, day).unwrap()
+    }
+
+    #[test]
+    fn loads_what_was_saved_today() {
+        let path = path("today");
+        let tally = Tally {
+            day: day(5),
+            count: 3,
+        };
+        tally.save(&path).unwrap();
+
+        assert_eq!(Tally::load(&path, day(5)), tally);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn starts_over_on_the_next_day() {
+        let path = path("yesterday");
+        Tally {
+            day: day(4),
+            count: 3,
+        }
+        .save(&path)
+        .unwrap();
+
+        assert_eq!(Tally::load(&path, day(5)), Tally::empty(day(5)));
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn ignores_missing_and_broken_files() {
+        let path = path("broken");
+        assert_eq!(Tally::load(&path, day(5)), Tally::empty(day(5)));
+
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        for contents in ["", "2023-04-05", "2023-04-05 many", "yesterday 3"] {
+            fs::write(&path, contents).unwrap();
+            assert_eq!(
+                Tally::load(&path, day(5)),
+                Tally::empty(day(5)),
+                "{:?}",
+                contents
+            );
+        }
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,376 @@
+use crate::render::{
+    attention::AttentionHandle,
+    clock::SharedClock,
+    display::{Content, ContentProvider},
+    scheduler::{ContentWrapper, CONTENT_PROVIDERS, TICK_LENGTH},
+    text::{ScrollableBuilder, StatefulScrollable},
+};
+use anyhow::{anyhow, Result};
+use apex_hardware::FrameBuffer;
+use async_stream::try_stream;
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, Local, NaiveTime, TimeZone, Weekday};
+use config::{Config, Value};
+use embedded_graphics::{
+    geometry::{Point, Size},
+    mono_font::{ascii, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    text::{renderer::TextRenderer, Baseline, Text},
+    Drawable,
+};
+use futures::Stream;
+use linkme::distributed_slice;
+use log::{info, warn};
+use std::convert::{TryFrom, TryInto};
+use tokio::{
+    sync::watch,
+    task::JoinHandle,
+    time,
+    time::{Duration, MissedTickBehavior},
+};
+
+#[doc(hidden)]
+#[distributed_slice(CONTENT_PROVIDERS)]
+pub static PROVIDER_INIT: fn(&Config, &SharedClock) -> Result<Box<dyn ContentWrapper>> =
+    register_callback;
+
+/// How long a reminder is shown once it's due unless configured otherwise
+const DEFAULT_FLASH_DURATION: u64 = 30;
+/// How often the background task checks whether a reminder is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone)]
+/// Represents how often a reminder goes off
+enum Repeat {
+    /// Every day at the same time
+    Daily,
+    /// Only on the given days of the week
+    Weekly(Vec<Weekday>),
+}
+
+#[derive(Debug, Clone)]
+struct Reminder {
+    label: String,
+    time: NaiveTime,
+    repeat: Repeat,
+}
+
+impl TryFrom<Value> for Reminder {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let mut table = value.into_table()?;
+
+        let label = table
+            .remove("label")
+            .ok_or_else(|| anyhow!("Reminder is missing a label!"))?
+            .into_str()?;
+
+        let time = table
+            .remove("time")
+            .ok_or_else(|| anyhow!("Reminder {:?} is missing a time!", label))?
+            .into_str()?;
+        let time = NaiveTime::parse_from_str(&time, "%H:%M")
+            .map_err(|e| anyhow!("Reminder {:?} has an invalid time: {}", label, e))?;
+
+        let repeat = table.remove("repeat").map(Value::into_str).transpose()?;
+        let repeat = match repeat.as_deref() {
+            None | Some("daily") => Repeat::Daily,
+            Some("weekly") => {
+                let days = table
+                    .remove("days")
+                    .ok_or_else(|| anyhow!("Weekly reminder {:?} is missing its days!", label))?
+                    .into_array()?
+                    .into_iter()
+                    .map(|day| {
+                        day.into_str()?
+                            .parse::<Weekday>()
+                            .map_err(|_| anyhow!("Reminder {:?} has an invalid day!", label))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Repeat::Weekly(days)
+            }
+            Some(repeat) => {
+                return Err(anyhow!(
+                    "Reminder {:?} has an unknown repetition: {}",
+                    label,
+                    repeat
+                ))
+            }
+        };
+
+        Ok(Reminder {
+            label,
+            time,
+            repeat,
+        })
+    }
+}
+
+impl Reminder {
+    /// Returns the first time this reminder goes off strictly after `after`.
+    fn next_occurrence(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let today = after.naive_local().date();
+
+        // A weekly reminder goes off at least once within the next eight days
+        (0..=7)
+            .map(|offset| today + ChronoDuration::days(offset))
+            .filter(|date| match &self.repeat {
+                Repeat::Daily => true,
+                Repeat::Weekly(days) => days.contains(&date.weekday()),
+            })
+            .filter_map(|date| {
+                Local
+                    .from_local_datetime(&date.and_time(self.time))
+                    .earliest()
+            })
+            .find(|occurrence| *occurrence > after)
+    }
+}
+
+#[doc(hidden)]
+#[allow(clippy::unnecessary_wraps)]
+fn register_callback(config: &Config, clock: &SharedClock) -> Result<Box<dyn ContentWrapper>> {
+    info!("Registering reminders display source.");
+
+    let reminders = config
+        .get_array("reminders.items")
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|value| {
+            Reminder::try_from(value)
+                .map_err(|e| warn!("Skipping reminder: {}", e))
+                .ok()
+        })
+        .collect::<Vec<_>>();
+
+    let flash_duration = config
+        .get_int("reminders.flash_duration")
+        .map_or(DEFAULT_FLASH_DURATION, |seconds| seconds as u64);
+
+    Ok(Box::new(Reminders {
+        reminders,
+        flash_duration: ChronoDuration::seconds(flash_duration as i64),
+        clock: clock.clone(),
+        attention: None,
+        watcher: None,
+    }))
+}
+
+/// The label of the reminder that is due and until when it's shown.
+type Due = Option<(String, DateTime<Local>)>;
+
+struct Reminders {
+    reminders: Vec<Reminder>,
+    flash_duration: ChronoDuration,
+    clock: SharedClock,
+    attention: Option<AttentionHandle>,
+    /// Tells when reminders are due, while the source is on the display or not
+    watcher: Option<JoinHandle<()>>,
+}
+
+impl Drop for Reminders {
+    fn drop(&mut self) {
+        if let Some(watcher) = self.watcher.take() {
+            watcher.abort();
+        }
+    }
+}
+
+/// Formats the time until a reminder goes off, e.g. `in 2h 05m` or `in 4:05`.
+fn format_countdown(remaining: ChronoDuration) -> String {
+    let seconds = remaining.num_seconds().max(0);
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+    if hours > 0 {
+        format!("in {}h {:02}m", hours, minutes)
+    } else {
+        format!("in {}:{:02}", minutes, seconds)
+    }
+}
+
+/// Returns the reminder that goes off next and when it does so.
+fn upcoming(reminders: &[Reminder], now: DateTime<Local>) -> Option<(&Reminder, DateTime<Local>)> {
+    reminders
+        .iter()
+        .filter_map(|reminder| Some((reminder, reminder.next_occurrence(now)?)))
+        .min_by_key(|(_, occurrence)| *occurrence)
+}
+
+/// Publishes the reminder that is due and asks for attention when one goes
+/// off. The checks run in a task of their own so they keep going while some
+/// other source is on the display.
+async fn watch_reminders(
+    reminders: Vec<Reminder>,
+    flash_duration: ChronoDuration,
+    clock: SharedClock,
+    attention: Option<AttentionHandle>,
+    due: watch::Sender<Due>,
+) {
+    let mut interval = time::interval(CHECK_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut last_check = clock.local();
+
+    loop {
+        interval.tick().await;
+        let now = clock.local();
+
+        let newly_due = upcoming(&reminders, last_check)
+            .filter(|(_, occurrence)| *occurrence <= now)
+            .map(|(reminder, occurrence)| (reminder.label.clone(), occurrence + flash_duration));
+        last_check = now;
+        if let Some((label, until)) = newly_due {
+            info!("Reminder {:?} is due", label);
+            due.send_replace(Some((label, until)));
+            if let Some(attention) = &attention {
+                attention.trigger_attention();
+            }
+        }
+
+        let over = matches!(&*due.borrow(), Some((_, until)) if *until <= now);
+        if over {
+            due.send_replace(None);
+            if let Some(attention) = &attention {
+                attention.dismiss_attention();
+            }
+        }
+    }
+}
+
+impl Reminders {
+    fn render(label: &StatefulScrollable, status: &str) -> Result<FrameBuffer> {
+        let mut buffer = FrameBuffer::new();
+        label.text.draw(&mut buffer)?;
+
+        let style = MonoTextStyle::new(&ascii::FONT_8X13_BOLD, BinaryColor::On);
+        let metrics = style.measure_string(status, Point::zero(), Baseline::Top);
+        let width: i32 = (metrics.bounding_box.size.width / 2) as i32;
+        Text::with_baseline(
+            status,
+            Point::new(128 / 2 - width, 20),
+            style,
+            Baseline::Top,
+        )
+        .draw(&mut buffer)?;
+
+        Ok(buffer)
+    }
+}
+
+impl ContentProvider for Reminders {
+    type ContentStream<'a> = impl Stream<Item = Result<Content>> + 'a;
+
+    // This needs to be enabled until full GAT support is here
+    #[allow(clippy::needless_lifetimes)]
+    fn stream<'this>(&'this mut self) -> Result<Self::ContentStream<'this>> {
+        if self.reminders.is_empty() {
+            return Err(anyhow!("No reminders configured!"));
+        }
+
+        let mut interval = time::interval(Duration::from_millis(TICK_LENGTH as u64));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        let mut label: StatefulScrollable = ScrollableBuilder::new()
+            .with_custom_spacing(10)
+            .with_position(Point::new(3, 3))
+            .with_projection(Size::new(128 - 2 * 3, 10))
+            .try_into()?;
+
+        let (sender, due) = watch::channel(None);
+        self.watcher = Some(tokio::spawn(watch_reminders(
+            self.reminders.clone(),
+            self.flash_duration,
+            self.clock.clone(),
+            self.attention.clone(),
+            sender,
+        )));
+
+        Ok(try_stream! {
+            loop {
+                let now = self.clock.local();
+
+                let current = due.borrow().clone();
+                let image = if let Some((text, _)) = &current {
+                    if !label.update(text)? {
+                        label.text.scroll();
+                    }
+                    Self::render(&label, "Now!")?
+                } else if let Some((reminder, occurrence)) = upcoming(&self.reminders, now) {
+                    if !label.update(&reminder.label)? {
+                        label.text.scroll();
+                    }
+                    Self::render(&label, &format_countdown(occurrence - now))?
+                } else {
+                    FrameBuffer::new()
+                };
+
+                yield Content::Frame(image);
+
+                interval.tick().await;
+            }
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "reminders"
+    }
+
+    fn set_attention(&mut self, attention: AttentionHandle) {
+        self.attention = Some(attention);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{attention::Request, clock::MockClock};
+    use chrono::NaiveDate;
+    use tokio::sync::mpsc;
+
+    /// Moves the time forward and gives the watcher a chance to catch up.
+    async fn advance(clock: &MockClock, duration: Duration) {
+        clock.advance(duration).await;
+        for _ in 0..4 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn asks_for_attention_without_being_on_the_display() {
+        let wall = NaiveDate::from_ymd_opt(2023, 4, 5)
+            .and_then(|date| date.and_hms_opt(8, 59, 58))
+            .unwrap();
+        let clock = MockClock::new(Local.from_local_datetime(&wall).unwrap());
+        let reminder = Reminder {
+            label: String::from("Stand up"),
+            time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            repeat: Repeat::Daily,
+        };
+        let (sender, mut requests) = mpsc::unbounded_channel();
+        let (due_sender, due) = watch::channel(None);
+        // Nothing polls the stream of the source, only the watcher runs
+        tokio::spawn(watch_reminders(
+            vec![reminder],
+            ChronoDuration::seconds(10),
+            clock.shared(),
+            Some(AttentionHandle::new("reminders", sender)),
+            due_sender,
+        ));
+
+        advance(&clock, Duration::from_secs(1)).await;
+        assert!(requests.try_recv().is_err());
+        assert!(due.borrow().is_none());
+
+        advance(&clock, Duration::from_secs(1)).await;
+        assert_eq!(requests.try_recv(), Ok(Request::Trigger("reminders")));
+        assert_eq!(
+            due.borrow().as_ref().map(|(label, _)| label.clone()),
+            Some(String::from("Stand up"))
+        );
+
+        for _ in 0..10 {
+            advance(&clock, Duration::from_secs(1)).await;
+        }
+        assert_eq!(requests.try_recv(), Ok(Request::Dismiss("reminders")));
+        assert!(due.borrow().is_none());
+    }
+}
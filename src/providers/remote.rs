@@ -0,0 +1,192 @@
+use crate::{
+    api::NowPlaying,
+    net,
+    render::{
+        clock::SharedClock,
+        display::{Content, ContentProvider},
+        scheduler::{ContentWrapper, CONTENT_PROVIDERS, TICK_LENGTH},
+        text::StatefulScrollable,
+    },
+};
+use anyhow::{anyhow, Result};
+use apex_hardware::FrameBuffer;
+use async_stream::try_stream;
+use config::Config;
+use embedded_graphics::{
+    geometry::{Point, Size},
+    pixelcolor::BinaryColor,
+    prelude::Primitive,
+    primitives::{PrimitiveStyle, Rectangle},
+    Drawable,
+};
+use futures::Stream;
+use linkme::distributed_slice;
+use log::{info, warn};
+use tokio::{
+    time,
+    time::{Duration, Instant, MissedTickBehavior},
+};
+
+#[doc(hidden)]
+#[distributed_slice(CONTENT_PROVIDERS)]
+pub static PROVIDER_INIT: fn(&Config, &SharedClock) -> Result<Box<dyn ContentWrapper>> =
+    register_callback;
+
+/// How often the remote is asked what's playing unless configured otherwise,
+/// in milliseconds
+const DEFAULT_REFRESH: u64 = 1000;
+/// How long to wait before trying again after the remote couldn't be reached,
+/// in seconds
+const RECONNECT_DELAY: u64 = 5;
+/// Slow remotes shouldn't freeze the display for long, in milliseconds
+const TIMEOUT: u64 = 1000;
+
+#[doc(hidden)]
+#[allow(clippy::unnecessary_wraps)]
+fn register_callback(config: &Config, clock: &SharedClock) -> Result<Box<dyn ContentWrapper>> {
+    info!("Registering remote now playing display source.");
+
+    let url = config.get_str("remote.url").ok();
+    let refresh = config
+        .get_int("remote.refresh")
+        .map_or(DEFAULT_REFRESH, |refresh| refresh.max(100) as u64);
+
+    Ok(Box::new(RemoteNowPlayingSource {
+        url,
+        refresh,
+        clock: clock.clone(),
+    }))
+}
+
+async fn fetch(url: &str) -> Result<Option<NowPlaying>> {
+    let response = net::client()
+        .get(url)
+        .timeout(Duration::from_millis(TIMEOUT))
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(response.json().await?)
+}
+
+/// Draws the title and artists of the remote track above a progress bar.
+#[derive(Debug)]
+struct RemoteRenderer {
+    title: StatefulScrollable,
+    artists: StatefulScrollable,
+}
+
+impl RemoteRenderer {
+    fn new() -> Result<Self> {
+        Ok(Self {
+            title: StatefulScrollable::row(4)?,
+            artists: StatefulScrollable::row(17)?,
+        })
+    }
+
+    /// `position` is where we think the remote is by now, the remote is only
+    /// asked every so often.
+    fn render(&mut self, playing: &NowPlaying, position: u64) -> Result<FrameBuffer> {
+        let mut buffer = FrameBuffer::new();
+
+        self.title.update_or_scroll(&playing.title)?;
+        self.title.text.draw(&mut buffer)?;
+
+        let artists = if playing.artists.is_empty() {
+            playing.player.clone()
+        } else {
+            playing.artists.join(", ")
+        };
+        self.artists.update_or_scroll(&artists)?;
+        self.artists.text.draw(&mut buffer)?;
+
+        Rectangle::new(Point::new(3, 32), Size::new(128 - 2 * 3, 5))
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+            .draw(&mut buffer)?;
+        if playing.length != 0 {
+            let width = (128 - 2 * 3) * position.min(playing.length) / playing.length;
+            Rectangle::new(Point::new(3, 32), Size::new(width as u32, 5))
+                .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                .draw(&mut buffer)?;
+        }
+
+        Ok(buffer)
+    }
+}
+
+/// Mirrors what another instance is playing, e.g. the one on the PC in the
+/// other room. The source is hidden while the remote can't be reached or
+/// isn't playing anything.
+#[derive(Debug, Clone)]
+struct RemoteNowPlayingSource {
+    /// Where `GET /nowplaying` of the remote is found
+    url: Option<String>,
+    /// How often the remote is asked, in milliseconds
+    refresh: u64,
+    clock: SharedClock,
+}
+
+impl ContentProvider for RemoteNowPlayingSource {
+    type ContentStream<'a> = impl Stream<Item = Result<Content>> + 'a;
+
+    // This needs to be enabled until full GAT support is here
+    #[allow(clippy::needless_lifetimes)]
+    fn stream<'this>(&'this mut self) -> Result<Self::ContentStream<'this>> {
+        let url = self
+            .url
+            .clone()
+            .ok_or_else(|| anyhow!("No remote configured!"))?;
+        let refresh = Duration::from_millis(self.refresh);
+        let mut renderer = RemoteRenderer::new()?;
+
+        let mut interval = time::interval(Duration::from_millis(TICK_LENGTH as u64));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        Ok(try_stream! {
+            let mut state: Option<(NowPlaying, Instant)> = None;
+            let mut online = true;
+            let mut fetch_at = self.clock.now();
+
+            loop {
+                interval.tick().await;
+
+                let now = self.clock.now();
+                if now >= fetch_at {
+                    match fetch(&url).await {
+                        Ok(update) => {
+                            if !online {
+                                info!("Reconnected to {}", url);
+                                online = true;
+                            }
+                            state = update.map(|playing| (playing, now));
+                            fetch_at = now + refresh;
+                        }
+                        Err(e) => {
+                            if online {
+                                warn!("Lost the connection to {}: {}", url, e);
+                                online = false;
+                            }
+                            state = None;
+                            fetch_at = now + Duration::from_secs(RECONNECT_DELAY);
+                        }
+                    }
+                }
+
+                match &state {
+                    Some((playing, fetched)) => {
+                        let position = if playing.playing {
+                            playing.position + self.clock.elapsed(*fetched).as_millis() as u64
+                        } else {
+                            playing.position
+                        };
+                        yield Content::Frame(renderer.render(playing, position)?);
+                    }
+                    None => yield Content::Hidden,
+                }
+            }
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "remote"
+    }
+}
@@ -0,0 +1,658 @@
+use crate::{
+    net,
+    net::RateLimiter,
+    render::{
+        clock::SharedClock,
+        display::{Content, ContentProvider},
+        scheduler::{ContentWrapper, CONTENT_PROVIDERS, TICK_LENGTH},
+        stale::{StaleMarker, Staleness},
+        text::StatefulScrollable,
+        util::format_count,
+    },
+    secrets,
+};
+use anyhow::{anyhow, Result};
+use apex_hardware::FrameBuffer;
+use async_stream::try_stream;
+use config::Config;
+use embedded_graphics::{
+    geometry::{Point, Size},
+    mono_font::{ascii, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    prelude::Primitive,
+    primitives::{Circle, PrimitiveStyle, Rectangle},
+    text::{renderer::TextRenderer, Baseline, Text},
+    Drawable,
+};
+use futures::Stream;
+use linkme::distributed_slice;
+use log::{info, warn};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use tokio::{
+    time,
+    time::{Duration, Instant, MissedTickBehavior},
+};
+
+#[doc(hidden)]
+#[distributed_slice(CONTENT_PROVIDERS)]
+pub static PROVIDER_INIT: fn(&Config, &SharedClock) -> Result<Box<dyn ContentWrapper>> =
+    register_callback;
+
+const TWITCH_TOKEN_URL: &str = "https://id.twitch.tv/oauth2/token";
+const TWITCH_STREAMS_URL: &str = "https://api.twitch.tv/helix/streams";
+const YOUTUBE_SEARCH_URL: &str = "https://www.googleapis.com/youtube/v3/search";
+const YOUTUBE_VIDEOS_URL: &str = "https://www.googleapis.com/youtube/v3/videos";
+/// Twitch takes at most this many channels per request
+const MAX_TWITCH_CHANNELS: usize = 100;
+/// How often Twitch is asked unless configured otherwise, in seconds
+const DEFAULT_REFRESH: u64 = 120;
+/// How often YouTube is asked unless configured otherwise, in seconds. With
+/// more than a single channel it's asked less often to stay within the quota
+const DEFAULT_YOUTUBE_REFRESH: u64 = 30 * 60;
+/// How many of the 10,000 units of the daily YouTube quota the checks may
+/// use, the rest leaves room for retries after failed requests
+const YOUTUBE_DAILY_BUDGET: u64 = 9_000;
+/// Every channel costs one search of 100 units per check, looking up the
+/// viewers of whatever is live costs another unit
+const YOUTUBE_SEARCH_COST: u64 = 100;
+const YOUTUBE_VIDEOS_COST: u64 = 1;
+/// How many seconds each stream is shown for when several are live
+const DEFAULT_DURATION: u64 = 5;
+/// How long to wait before trying again after a request failed, in seconds.
+/// This doubles with every failure in a row up to the refresh interval
+const RETRY_INTERVAL: u64 = 60;
+
+#[doc(hidden)]
+#[allow(clippy::unnecessary_wraps)]
+fn register_callback(config: &Config, clock: &SharedClock) -> Result<Box<dyn ContentWrapper>> {
+    info!("Registering stream status display source.");
+
+    let secret = |key: &str| {
+        secrets::get(config, key).unwrap_or_else(|e| {
+            warn!("{}", e);
+            None
+        })
+    };
+    let channels = |key: &str| {
+        config
+            .get_array(key)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|channel| channel.into_str().ok())
+            .collect::<Vec<_>>()
+    };
+
+    let mut twitch_channels = channels("streams.twitch");
+    if twitch_channels.len() > MAX_TWITCH_CHANNELS {
+        warn!(
+            "Only the first {} Twitch channels are checked",
+            MAX_TWITCH_CHANNELS
+        );
+        twitch_channels.truncate(MAX_TWITCH_CHANNELS);
+    }
+    let twitch = match (
+        twitch_channels.is_empty(),
+        secret("streams.twitch_client_id"),
+        secret("streams.twitch_client_secret"),
+    ) {
+        (true, ..) => None,
+        (false, Some(client_id), Some(client_secret)) => Some(Twitch {
+            client_id,
+            client_secret,
+            channels: twitch_channels,
+            token: None,
+            net: RateLimiter::new("Twitch", clock.clone()),
+            clock: clock.clone(),
+        }),
+        _ => {
+            warn!("Twitch needs a client id and a client secret");
+            None
+        }
+    };
+
+    let youtube_channels = channels("streams.youtube");
+    let youtube_count = youtube_channels.len();
+    let youtube_min_refresh = youtube_min_refresh(youtube_count);
+    let youtube = match (
+        youtube_channels.is_empty(),
+        secret("streams.youtube_api_key"),
+    ) {
+        (true, _) => None,
+        (false, Some(api_key)) => Some(YouTube {
+            api_key,
+            channels: youtube_channels,
+            net: RateLimiter::new("YouTube", clock.clone()),
+        }),
+        (false, None) => {
+            warn!("YouTube needs an API key");
+            None
+        }
+    };
+
+    let refresh = config
+        .get_int("streams.refresh")
+        .map_or(DEFAULT_REFRESH, |seconds| seconds.max(30) as u64);
+    let youtube_refresh = match config.get_int("streams.youtube_refresh") {
+        Ok(seconds) if (seconds.max(0) as u64) < youtube_min_refresh => {
+            warn!(
+                "YouTube can only be asked every {} seconds with {} channels before running out \
+                 of quota",
+                youtube_min_refresh, youtube_count
+            );
+            youtube_min_refresh
+        }
+        Ok(seconds) => seconds as u64,
+        Err(_) => DEFAULT_YOUTUBE_REFRESH.max(youtube_min_refresh),
+    };
+    let duration = config
+        .get_int("streams.duration")
+        .map_or(DEFAULT_DURATION, |seconds| seconds.max(1) as u64);
+
+    Ok(Box::new(StreamStatusSource {
+        twitch,
+        youtube,
+        refresh: Duration::from_secs(refresh),
+        youtube_refresh: Duration::from_secs(youtube_refresh),
+        duration: Duration::from_secs(duration),
+        clock: clock.clone(),
+    }))
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Platform {
+    Twitch,
+    YouTube,
+}
+
+impl Platform {
+    fn name(self) -> &'static str {
+        match self {
+            Platform::Twitch => "Twitch",
+            Platform::YouTube => "YouTube",
+        }
+    }
+}
+
+/// A channel that's live right now.
+#[derive(Debug, Clone)]
+struct Live {
+    platform: Platform,
+    channel: String,
+    title: String,
+    /// YouTube leaves this out if the channel hides it
+    viewers: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TwitchToken {
+    access_token: String,
+    /// In seconds
+    expires_in: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TwitchStreams {
+    data: Vec<TwitchStream>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TwitchStream {
+    user_name: String,
+    title: String,
+    viewer_count: u64,
+}
+
+/// Asks Twitch with an app access token, which is fetched with the client
+/// credentials and reused until it expires.
+#[derive(Debug)]
+struct Twitch {
+    client_id: String,
+    client_secret: String,
+    /// The logins of the channels, not their display names
+    channels: Vec<String>,
+    token: Option<(String, Instant)>,
+    net: RateLimiter,
+    clock: SharedClock,
+}
+
+impl Twitch {
+    async fn token(&mut self) -> Result<String> {
+        if let Some((token, expires)) = &self.token {
+            if self.clock.now() < *expires {
+                return Ok(token.clone());
+            }
+        }
+
+        let request = net::client().post(TWITCH_TOKEN_URL).query(&[
+            ("client_id", self.client_id.as_str()),
+            ("client_secret", self.client_secret.as_str()),
+            ("grant_type", "client_credentials"),
+        ]);
+        let token = self.net.send(request).await?.json::<TwitchToken>().await?;
+
+        // Asking for a new one a little early keeps requests from failing halfway
+        let expires = self.clock.now() + Duration::from_secs(token.expires_in * 9 / 10);
+        self.token = Some((token.access_token.clone(), expires));
+        Ok(token.access_token)
+    }
+
+    async fn fetch(&mut self) -> Result<Vec<Live>> {
+        let token = self.token().await?;
+        let query = self
+            .channels
+            .iter()
+            .map(|channel| ("user_login", channel.as_str()))
+            .collect::<Vec<_>>();
+
+        let request = net::client()
+            .get(TWITCH_STREAMS_URL)
+            .query(&query)
+            .header("Client-Id", &self.client_id)
+            .bearer_auth(token);
+        let streams = match self.net.send(request).await {
+            Ok(response) => response.json::<TwitchStreams>().await?,
+            Err(e) => {
+                // The token was revoked, the next attempt gets a new one
+                if e.downcast_ref::<reqwest::Error>()
+                    .and_then(reqwest::Error::status)
+                    == Some(StatusCode::UNAUTHORIZED)
+                {
+                    self.token = None;
+                }
+                return Err(e);
+            }
+        };
+
+        Ok(streams
+            .data
+            .into_iter()
+            .map(|stream| Live {
+                platform: Platform::Twitch,
+                channel: stream.user_name,
+                title: stream.title,
+                viewers: Some(stream.viewer_count),
+            })
+            .collect())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct YouTubeSearch {
+    items: Vec<YouTubeSearchResult>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct YouTubeSearchResult {
+    id: YouTubeVideoId,
+    snippet: YouTubeSnippet,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct YouTubeVideoId {
+    video_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct YouTubeSnippet {
+    channel_title: String,
+    title: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct YouTubeVideos {
+    items: Vec<YouTubeVideo>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct YouTubeVideo {
+    id: String,
+    live_streaming_details: Option<YouTubeLiveDetails>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct YouTubeLiveDetails {
+    /// YouTube sends this as a string
+    concurrent_viewers: Option<String>,
+}
+
+/// The shortest refresh interval in seconds that keeps the checks for
+/// `channels` within the daily budget, e.g. about 16 minutes for a single
+/// channel and 48 for three.
+fn youtube_min_refresh(channels: usize) -> u64 {
+    let cost = channels as u64 * YOUTUBE_SEARCH_COST + YOUTUBE_VIDEOS_COST;
+    (24 * 60 * 60 * cost).div_ceil(YOUTUBE_DAILY_BUDGET)
+}
+
+/// Asks YouTube with an API key. Searching is expensive so this happens far
+/// less often than for Twitch.
+#[derive(Debug)]
+struct YouTube {
+    api_key: String,
+    /// The ids of the channels, e.g. `UC...`
+    channels: Vec<String>,
+    net: RateLimiter,
+}
+
+impl YouTube {
+    async fn fetch(&mut self) -> Result<Vec<Live>> {
+        let mut found = Vec::new();
+        for channel in &self.channels {
+            let request = net::client().get(YOUTUBE_SEARCH_URL).query(&[
+                ("part", "snippet"),
+                ("channelId", channel.as_str()),
+                ("eventType", "live"),
+                ("type", "video"),
+                ("key", self.api_key.as_str()),
+            ]);
+            let search = self
+                .net
+                .send(request)
+                .await?
+                .json::<YouTubeSearch>()
+                .await?;
+            found.extend(search.items);
+        }
+        if found.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // The viewers come from the videos themselves, which costs next to nothing
+        let ids = found
+            .iter()
+            .map(|result| result.id.video_id.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+        let request = net::client().get(YOUTUBE_VIDEOS_URL).query(&[
+            ("part", "liveStreamingDetails"),
+            ("id", ids.as_str()),
+            ("key", self.api_key.as_str()),
+        ]);
+        let videos = self
+            .net
+            .send(request)
+            .await?
+            .json::<YouTubeVideos>()
+            .await?;
+
+        Ok(found
+            .into_iter()
+            .map(|result| {
+                let viewers = videos
+                    .items
+                    .iter()
+                    .find(|video| video.id == result.id.video_id)
+                    .and_then(|video| video.live_streaming_details.as_ref())
+                    .and_then(|details| details.concurrent_viewers.as_ref())
+                    .and_then(|viewers| viewers.parse().ok());
+                Live {
+                    platform: Platform::YouTube,
+                    channel: result.snippet.channel_title,
+                    title: result.snippet.title,
+                    viewers,
+                }
+            })
+            .collect())
+    }
+}
+
+/// When a platform is asked next. Failed requests are retried sooner than
+/// the refresh interval but back off the more often they fail.
+#[derive(Debug, Copy, Clone)]
+struct Schedule {
+    refresh: Duration,
+    next: Instant,
+    failures: u32,
+}
+
+impl Schedule {
+    /// The first request is due right away, at `now`.
+    fn new(refresh: Duration, now: Instant) -> Self {
+        Self {
+            refresh,
+            next: now,
+            failures: 0,
+        }
+    }
+
+    fn due(&self, now: Instant) -> bool {
+        now >= self.next
+    }
+
+    fn succeeded(&mut self, now: Instant) {
+        self.failures = 0;
+        self.next = now + self.refresh;
+    }
+
+    fn failed(&mut self, now: Instant) {
+        let retry = Duration::from_secs(RETRY_INTERVAL) * 2_u32.pow(self.failures.min(8));
+        self.failures += 1;
+        self.next = now + retry.min(self.refresh);
+    }
+}
+
+/// What a platform said the last time it was asked. The streams are kept
+/// when it can't be reached, they're marked as stale once the request that
+/// was due and the retry after it failed.
+#[derive(Debug, Clone)]
+struct Cached {
+    live: Vec<Live>,
+    staleness: Staleness,
+    stale_after: Duration,
+}
+
+impl Cached {
+    fn new(refresh: Duration, clock: SharedClock) -> Self {
+        Self {
+            live: Vec::new(),
+            staleness: Staleness::new(clock),
+            stale_after: refresh + Duration::from_secs(RETRY_INTERVAL),
+        }
+    }
+
+    fn is_stale(&self) -> bool {
+        self.staleness.is_stale(self.stale_after)
+    }
+
+    fn update(&mut self, platform: Platform, result: Result<Vec<Live>>) -> bool {
+        match result {
+            Ok(live) => {
+                if self.staleness.age().is_some() && self.is_stale() {
+                    info!("Reached {} again", platform.name());
+                }
+                self.live = live;
+                self.staleness.success();
+                true
+            }
+            Err(e) => {
+                warn!("Couldn't ask {} who's live: {}", platform.name(), e);
+                false
+            }
+        }
+    }
+}
+
+/// Draws the live badge and the viewers above the channel and the title of
+/// the stream.
+#[derive(Debug)]
+struct StreamRenderer {
+    channel: StatefulScrollable,
+    title: StatefulScrollable,
+}
+
+impl StreamRenderer {
+    fn new() -> Result<Self> {
+        Ok(Self {
+            channel: StatefulScrollable::wide_row(&ascii::FONT_8X13_BOLD, 14)?,
+            title: StatefulScrollable::wide_row(&ascii::FONT_6X10, 29)?,
+        })
+    }
+
+    fn render(&mut self, live: &Live, stale: bool) -> Result<FrameBuffer> {
+        let mut buffer = FrameBuffer::new();
+        let small = MonoTextStyle::new(&ascii::FONT_6X10, BinaryColor::On);
+
+        // The fonts don't have a dot so the badge is drawn by hand
+        Rectangle::new(Point::new(0, 0), Size::new(38, 12))
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+            .draw(&mut buffer)?;
+        Circle::new(Point::new(3, 3), 6)
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::Off))
+            .draw(&mut buffer)?;
+        Text::with_baseline(
+            "LIVE",
+            Point::new(12, 1),
+            MonoTextStyle::new(&ascii::FONT_6X10, BinaryColor::Off),
+            Baseline::Top,
+        )
+        .draw(&mut buffer)?;
+
+        let viewers = match live.viewers {
+            Some(1) => String::from("1 viewer"),
+            Some(viewers) => format!("{} viewers", format_count(viewers)),
+            None => String::from(live.platform.name()),
+        };
+        // Leaves room for the stale marker in the corner
+        let width = small
+            .measure_string(&viewers, Point::zero(), Baseline::Top)
+            .bounding_box
+            .size
+            .width as i32;
+        Text::with_baseline(
+            &viewers,
+            Point::new(128 - 7 - width, 1),
+            small,
+            Baseline::Top,
+        )
+        .draw(&mut buffer)?;
+        if stale {
+            StaleMarker::new().draw(&mut buffer)?;
+        }
+
+        self.channel.update_or_scroll(&live.channel)?;
+        self.channel.text.draw(&mut buffer)?;
+        self.title.update_or_scroll(&live.title)?;
+        self.title.text.draw(&mut buffer)?;
+
+        Ok(buffer)
+    }
+}
+
+/// Shows which of the configured Twitch and YouTube channels are live. The
+/// source is hidden while none of them are.
+#[derive(Debug)]
+struct StreamStatusSource {
+    twitch: Option<Twitch>,
+    youtube: Option<YouTube>,
+    /// How often Twitch is asked
+    refresh: Duration,
+    /// How often YouTube is asked
+    youtube_refresh: Duration,
+    /// How long each stream is shown for when several are live
+    duration: Duration,
+    clock: SharedClock,
+}
+
+impl ContentProvider for StreamStatusSource {
+    type ContentStream<'a> = impl Stream<Item = Result<Content>> + 'a;
+
+    // This needs to be enabled until full GAT support is here
+    #[allow(clippy::needless_lifetimes)]
+    fn stream<'this>(&'this mut self) -> Result<Self::ContentStream<'this>> {
+        if self.twitch.is_none() && self.youtube.is_none() {
+            return Err(anyhow!("No channels configured!"));
+        }
+        let mut renderer = StreamRenderer::new()?;
+
+        let mut interval = time::interval(Duration::from_millis(TICK_LENGTH as u64));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        Ok(try_stream! {
+            let mut twitch_schedule = Schedule::new(self.refresh, self.clock.now());
+            let mut youtube_schedule = Schedule::new(self.youtube_refresh, self.clock.now());
+            let mut twitch_cache = Cached::new(self.refresh, self.clock.clone());
+            let mut youtube_cache = Cached::new(self.youtube_refresh, self.clock.clone());
+
+            let mut index = 0;
+            let mut switch_at = self.clock.now() + self.duration;
+
+            loop {
+                interval.tick().await;
+
+                let now = self.clock.now();
+                if let (Some(twitch), true) = (&mut self.twitch, twitch_schedule.due(now)) {
+                    if twitch_cache.update(Platform::Twitch, twitch.fetch().await) {
+                        twitch_schedule.succeeded(now);
+                    } else {
+                        twitch_schedule.failed(now);
+                    }
+                }
+                if let (Some(youtube), true) = (&mut self.youtube, youtube_schedule.due(now)) {
+                    if youtube_cache.update(Platform::YouTube, youtube.fetch().await) {
+                        youtube_schedule.succeeded(now);
+                    } else {
+                        youtube_schedule.failed(now);
+                    }
+                }
+
+                let live = twitch_cache
+                    .live
+                    .iter()
+                    .chain(&youtube_cache.live)
+                    .collect::<Vec<_>>();
+                if live.is_empty() {
+                    yield Content::Hidden;
+                    continue;
+                }
+
+                if live.len() > 1 && now >= switch_at {
+                    index += 1;
+                    switch_at = now + self.duration;
+                    // The next stream starts scrolling from the beginning
+                    renderer.channel.text.scroll = 0;
+                    renderer.title.text.scroll = 0;
+                }
+                // Streams that ended since take the index with them
+                let live = live[index % live.len()];
+                let stale = match live.platform {
+                    Platform::Twitch => twitch_cache.is_stale(),
+                    Platform::YouTube => youtube_cache.is_stale(),
+                };
+
+                yield Content::Frame(renderer.render(live, stale)?);
+            }
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "streams"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn youtube_stays_within_the_daily_quota() {
+        for channels in 1..=10 {
+            let refresh = youtube_min_refresh(channels);
+            let checks = 24 * 60 * 60 / refresh;
+            let cost = checks * (channels as u64 * YOUTUBE_SEARCH_COST + YOUTUBE_VIDEOS_COST);
+            assert!(
+                cost <= YOUTUBE_DAILY_BUDGET,
+                "{} channels cost {}",
+                channels,
+                cost
+            );
+        }
+        // The default is fine for a single channel but not for three
+        assert!(youtube_min_refresh(1) < DEFAULT_YOUTUBE_REFRESH);
+        assert!(youtube_min_refresh(3) > DEFAULT_YOUTUBE_REFRESH);
+    }
+}
@@ -0,0 +1,323 @@
+#[cfg(feature = "stats")]
+use crate::stats::{Collector, Cpu, Memory};
+use crate::{
+    render::{
+        clock::SharedClock,
+        display::{Content, ContentProvider},
+        scheduler::{ContentWrapper, CONTENT_PROVIDERS, TICK_LENGTH},
+        text::{ScrollableBuilder, StatefulScrollable},
+    },
+    settings,
+};
+use anyhow::{anyhow, Result};
+use apex_hardware::FrameBuffer;
+use async_stream::try_stream;
+use config::Config;
+use embedded_graphics::{
+    geometry::{Point, Size},
+    mono_font::{ascii, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    text::{renderer::TextRenderer, Baseline, Text},
+    Drawable,
+};
+use futures::Stream;
+use linkme::distributed_slice;
+use log::{info, warn};
+use std::{convert::TryFrom, time::SystemTime};
+#[cfg(feature = "stats")]
+use sysinfo::System;
+use tokio::{
+    time,
+    time::{Duration, MissedTickBehavior},
+};
+
+#[doc(hidden)]
+#[distributed_slice(CONTENT_PROVIDERS)]
+pub static PROVIDER_INIT: fn(&Config, &SharedClock) -> Result<Box<dyn ContentWrapper>> =
+    register_callback;
+
+/// How many seconds a message is shown for unless configured otherwise
+const DEFAULT_DURATION: u64 = 5;
+const DEFAULT_SEPARATOR: &str = " | ";
+/// How often we look for changes to the settings, in seconds
+const RELOAD_INTERVAL: u64 = 2;
+/// How often the statistics for `{cpu}` and `{ram}` are refreshed, in seconds
+#[cfg(feature = "stats")]
+const STATS_INTERVAL: u64 = 1;
+
+/// How the messages are shown.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Mode {
+    /// One message after the other
+    Rotate,
+    /// All messages joined together and scrolled past
+    Marquee,
+}
+
+impl TryFrom<String> for Mode {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "rotate" => Ok(Mode::Rotate),
+            "marquee" => Ok(Mode::Marquee),
+            _ => Err(anyhow!("Unknown ticker mode: {}", value)),
+        }
+    }
+}
+
+fn read_messages(config: &Config) -> Vec<String> {
+    config
+        .get_array("ticker.messages")
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|message| {
+            message
+                .into_str()
+                .map_err(|e| warn!("Skipping ticker message: {}", e))
+                .ok()
+        })
+        .collect()
+}
+
+#[doc(hidden)]
+#[allow(clippy::unnecessary_wraps)]
+fn register_callback(config: &Config, clock: &SharedClock) -> Result<Box<dyn ContentWrapper>> {
+    info!("Registering ticker display source.");
+
+    let mode = config
+        .get_str("ticker.mode")
+        .map_or(Ok(Mode::Rotate), Mode::try_from)
+        .unwrap_or_else(|e| {
+            warn!("{}", e);
+            Mode::Rotate
+        });
+    let duration = config
+        .get_int("ticker.duration")
+        .map_or(DEFAULT_DURATION, |seconds| seconds.max(1) as u64);
+    let separator = config
+        .get_str("ticker.separator")
+        .unwrap_or_else(|_| String::from(DEFAULT_SEPARATOR));
+
+    Ok(Box::new(Ticker {
+        messages: read_messages(config),
+        mode,
+        duration: Duration::from_secs(duration),
+        separator,
+        tokens: Tokens::new(clock.clone()),
+        clock: clock.clone(),
+    }))
+}
+
+/// Resolves the `{...}` tokens in messages.
+#[derive(Debug)]
+struct Tokens {
+    #[cfg(feature = "stats")]
+    system: System,
+    #[cfg(feature = "stats")]
+    cpu: Cpu,
+    #[cfg(feature = "stats")]
+    memory: Memory,
+    /// Where `{time}` and `{date}` come from
+    clock: SharedClock,
+}
+
+impl Tokens {
+    fn new(clock: SharedClock) -> Self {
+        Self {
+            #[cfg(feature = "stats")]
+            system: System::default(),
+            #[cfg(feature = "stats")]
+            cpu: Cpu::default(),
+            #[cfg(feature = "stats")]
+            memory: Memory::default(),
+            clock,
+        }
+    }
+
+    /// Refreshes the statistics, but only the ones that are mentioned.
+    #[cfg(feature = "stats")]
+    fn refresh(&mut self, messages: &[String]) {
+        if messages.iter().any(|message| message.contains("{cpu}")) {
+            self.cpu.refresh(&mut self.system);
+        }
+        if messages.iter().any(|message| message.contains("{ram}")) {
+            self.memory.refresh(&mut self.system);
+        }
+    }
+
+    /// Replaces the tokens this build knows about, unknown tokens are left
+    /// alone so typos are easy to spot.
+    fn resolve(&self, message: &str) -> String {
+        if !message.contains('{') {
+            return message.to_string();
+        }
+
+        let now = self.clock.local();
+        let tokens = [
+            ("{time}", now.format("%H:%M").to_string()),
+            ("{date}", now.format("%Y-%m-%d").to_string()),
+        ];
+        #[cfg(feature = "stats")]
+        let tokens = tokens.into_iter().chain([
+            ("{cpu}", format!("{:.0}%", self.cpu.usage)),
+            ("{ram}", format!("{:.0}%", self.memory.usage())),
+        ]);
+
+        tokens
+            .into_iter()
+            .fold(message.to_string(), |message, (token, value)| {
+                message.replace(token, &value)
+            })
+    }
+}
+
+/// When the settings were changed last, if we can tell.
+fn settings_modified() -> Option<SystemTime> {
+    std::fs::metadata(settings::PATH)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Cycles through a list of custom messages.
+#[derive(Debug)]
+struct Ticker {
+    messages: Vec<String>,
+    mode: Mode,
+    /// How long each message is shown for in [`Mode::Rotate`]
+    duration: Duration,
+    /// What goes between the messages in [`Mode::Marquee`]
+    separator: String,
+    tokens: Tokens,
+    clock: SharedClock,
+}
+
+impl Ticker {
+    /// Reads the messages from the settings again. The old ones are kept if
+    /// the settings can't be read or don't contain any messages.
+    fn reload(&mut self) {
+        let config = match settings::load() {
+            Ok(config) => config,
+            Err(e) => {
+                warn!("Couldn't reload the ticker messages: {:#}", e);
+                return;
+            }
+        };
+
+        let messages = read_messages(&config);
+        if messages.is_empty() {
+            warn!("No ticker messages configured, keeping the old ones");
+        } else if messages != self.messages {
+            info!("Reloaded {} ticker messages", messages.len());
+            self.messages = messages;
+        }
+    }
+
+    fn text(&self, index: usize) -> String {
+        match self.mode {
+            Mode::Rotate => self.tokens.resolve(&self.messages[index]),
+            Mode::Marquee => self
+                .messages
+                .iter()
+                .map(|message| self.tokens.resolve(message))
+                .collect::<Vec<_>>()
+                .join(&self.separator),
+        }
+    }
+
+    /// Centers text that fits onto the display and scrolls everything else.
+    fn render(label: &mut StatefulScrollable, text: &str) -> Result<FrameBuffer> {
+        let mut buffer = FrameBuffer::new();
+        let style = MonoTextStyle::new(&ascii::FONT_8X13_BOLD, BinaryColor::On);
+        let metrics = style.measure_string(text, Point::zero(), Baseline::Top);
+        let width = metrics.bounding_box.size.width as i32;
+
+        if width <= 128 {
+            Text::with_baseline(
+                text,
+                Point::new((128 - width) / 2, (40 - 13) / 2),
+                style,
+                Baseline::Top,
+            )
+            .draw(&mut buffer)?;
+        } else {
+            // Tokens like `{time}` change the text while it's scrolling, this shouldn't
+            // make it jump back to the start
+            let scroll = label.text.scroll;
+            if label.update(text)? {
+                label.text.scroll = scroll;
+            }
+            label.text.scroll();
+            label.text.draw(&mut buffer)?;
+        }
+
+        Ok(buffer)
+    }
+}
+
+impl ContentProvider for Ticker {
+    type ContentStream<'a> = impl Stream<Item = Result<Content>> + 'a;
+
+    // This needs to be enabled until full GAT support is here
+    #[allow(clippy::needless_lifetimes)]
+    fn stream<'this>(&'this mut self) -> Result<Self::ContentStream<'this>> {
+        if self.messages.is_empty() {
+            return Err(anyhow!("No ticker messages configured!"));
+        }
+
+        let mut interval = time::interval(Duration::from_millis(TICK_LENGTH as u64));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        let mut label: StatefulScrollable = ScrollableBuilder::new()
+            .with_custom_font(&ascii::FONT_8X13_BOLD)
+            .with_custom_spacing(16)
+            .with_position(Point::new(0, (40 - 13) / 2))
+            .with_projection(Size::new(128, 13))
+            .try_into()?;
+
+        Ok(try_stream! {
+            let mut index = 0;
+            let mut switch_at = self.clock.now() + self.duration;
+            let mut modified = settings_modified();
+            let mut reload_at = self.clock.now();
+            #[cfg(feature = "stats")]
+            let mut refresh_at = self.clock.now();
+
+            loop {
+                let now = self.clock.now();
+
+                if now >= reload_at {
+                    reload_at = now + Duration::from_secs(RELOAD_INTERVAL);
+                    let new = settings_modified();
+                    if new != modified {
+                        modified = new;
+                        self.reload();
+                        index %= self.messages.len();
+                    }
+                }
+
+                #[cfg(feature = "stats")]
+                if now >= refresh_at {
+                    refresh_at = now + Duration::from_secs(STATS_INTERVAL);
+                    self.tokens.refresh(&self.messages);
+                }
+
+                if self.mode == Mode::Rotate && now >= switch_at {
+                    index = (index + 1) % self.messages.len();
+                    switch_at = now + self.duration;
+                    // The next message starts scrolling from the beginning
+                    label.text.scroll = 0;
+                }
+
+                let text = self.text(index);
+                yield Content::Frame(Self::render(&mut label, &text)?);
+
+                interval.tick().await;
+            }
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "ticker"
+    }
+}
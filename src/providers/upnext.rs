@@ -0,0 +1,215 @@
+use crate::{
+    backend,
+    backend::{Backend, Player, Session},
+    render::{
+        clock::SharedClock,
+        display::{Content, ContentProvider},
+        scheduler::{ContentWrapper, CONTENT_PROVIDERS, TICK_LENGTH},
+        text::StatefulScrollable,
+    },
+};
+use anyhow::Result;
+use apex_hardware::FrameBuffer;
+use apex_music::{Metadata, PlayerEvent};
+use async_stream::try_stream;
+use config::Config;
+use embedded_graphics::{
+    geometry::Point,
+    mono_font::{ascii, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    prelude::Primitive,
+    primitives::{Line, PrimitiveStyle},
+    text::{Baseline, Text},
+    Drawable,
+};
+use futures::{pin_mut, Stream, StreamExt};
+use linkme::distributed_slice;
+use log::{info, warn};
+use std::sync::Arc;
+use tokio::{
+    time,
+    time::{Duration, MissedTickBehavior},
+};
+
+#[doc(hidden)]
+#[distributed_slice(CONTENT_PROVIDERS)]
+pub static PROVIDER_INIT: fn(&Config, &SharedClock) -> Result<Box<dyn ContentWrapper>> =
+    register_callback;
+
+/// Only two tracks fit below the current one
+const MAX_TRACKS: usize = 2;
+
+#[doc(hidden)]
+#[allow(clippy::unnecessary_wraps)]
+fn register_callback(config: &Config, _clock: &SharedClock) -> Result<Box<dyn ContentWrapper>> {
+    info!("Registering up next display source.");
+
+    let count = config
+        .get_int("upnext.count")
+        .map_or(MAX_TRACKS, |count| count.max(1) as usize);
+    if count > MAX_TRACKS {
+        warn!(
+            "Only {} upcoming tracks fit onto the display, ignoring the rest",
+            MAX_TRACKS
+        );
+    }
+
+    // The queue belongs to the same player the music source is showing
+    let name = config.get_str("mpris2.preferred_player").ok();
+    let priority = backend::configured_priority(config);
+
+    Ok(Box::new(UpNext {
+        name: name.map(Arc::new),
+        backends: backend::configured(config),
+        priority,
+        count: count.min(MAX_TRACKS),
+    }))
+}
+
+/// What's known about the queue of the player.
+#[derive(Debug, Clone)]
+enum Queue {
+    /// The upcoming tracks, empty at the end of the queue
+    Tracks(Vec<String>),
+    /// The player doesn't tell
+    Unsupported,
+}
+
+/// Formats a track as `Title - Artist, Artist`.
+fn describe(metadata: &impl Metadata) -> String {
+    let title = metadata
+        .title()
+        .unwrap_or_else(|_| String::from("Unknown title"));
+    match metadata.artists() {
+        Ok(artists) if !artists.is_empty() => format!("{} - {}", title, artists.join(", ")),
+        _ => title,
+    }
+}
+
+/// Reads the current track and the queue, fails if the player went away.
+async fn fetch(player: &Session<'_>, count: usize) -> Result<(String, Queue)> {
+    let current = describe(&player.progress().await?.metadata);
+    let queue = match player.next_tracks(count).await {
+        Ok(tracks) => Queue::Tracks(tracks.iter().map(describe).collect()),
+        Err(_) => Queue::Unsupported,
+    };
+    Ok((current, queue))
+}
+
+/// Draws the current track at the top and the upcoming ones below it.
+#[derive(Debug)]
+struct UpNextRenderer {
+    current: StatefulScrollable,
+    next: Vec<StatefulScrollable>,
+}
+
+impl UpNextRenderer {
+    fn new() -> Result<Self> {
+        Ok(Self {
+            current: StatefulScrollable::row(1)?,
+            next: vec![StatefulScrollable::row(15)?, StatefulScrollable::row(27)?],
+        })
+    }
+
+    fn message(text: &str) -> Result<FrameBuffer> {
+        let mut buffer = FrameBuffer::new();
+        let style = MonoTextStyle::new(&ascii::FONT_6X10, BinaryColor::On);
+        Text::with_baseline(text, Point::new(3, 15), style, Baseline::Top).draw(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn render(&mut self, current: &str, queue: &Queue) -> Result<FrameBuffer> {
+        let mut buffer = FrameBuffer::new();
+
+        self.current.update_or_scroll(current)?;
+        self.current.text.draw(&mut buffer)?;
+        Line::new(Point::new(3, 12), Point::new(128 - 4, 12))
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+            .draw(&mut buffer)?;
+
+        let style = MonoTextStyle::new(&ascii::FONT_6X10, BinaryColor::On);
+        let tracks = match queue {
+            Queue::Tracks(tracks) if !tracks.is_empty() => tracks,
+            Queue::Tracks(_) => {
+                Text::with_baseline("Nothing up next", Point::new(3, 15), style, Baseline::Top)
+                    .draw(&mut buffer)?;
+                return Ok(buffer);
+            }
+            Queue::Unsupported => {
+                Text::with_baseline("No queue info", Point::new(3, 15), style, Baseline::Top)
+                    .draw(&mut buffer)?;
+                return Ok(buffer);
+            }
+        };
+
+        for (row, track) in self.next.iter_mut().zip(tracks) {
+            row.update_or_scroll(&format!("> {}", track))?;
+            row.text.draw(&mut buffer)?;
+        }
+
+        Ok(buffer)
+    }
+}
+
+/// Shows the tracks that are going to be played next.
+#[derive(Debug, Clone)]
+struct UpNext {
+    name: Option<Arc<String>>,
+    backends: Vec<Backend>,
+    /// The players to prefer in order, the preferred one comes first
+    priority: Vec<String>,
+    /// How many upcoming tracks are shown
+    count: usize,
+}
+
+impl ContentProvider for UpNext {
+    type ContentStream<'a> = impl Stream<Item = Result<Content>> + 'a;
+
+    // This needs to be enabled until full GAT support is here
+    #[allow(clippy::needless_lifetimes)]
+    fn stream<'this>(&'this mut self) -> Result<Self::ContentStream<'this>> {
+        let mut renderer = UpNextRenderer::new()?;
+
+        Ok(try_stream! {
+            let backend = Player::auto(&self.backends)
+                .await
+                .with_priority(self.priority.clone());
+
+            'outer: loop {
+                yield Content::Frame(UpNextRenderer::message("No player found")?);
+                let player = backend.wait_for_player(self.name.clone()).await?;
+
+                let tracker = backend.stream().await?;
+                pin_mut!(tracker);
+
+                let Ok((mut current, mut queue)) = fetch(&player, self.count).await else {
+                    continue 'outer;
+                };
+
+                let mut render = time::interval(Duration::from_millis(TICK_LENGTH as u64));
+                render.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+                loop {
+                    tokio::select! {
+                        event = tracker.next() => match event {
+                            None => continue 'outer,
+                            // The queue doesn't change just because the track moves on
+                            Some(PlayerEvent::PositionChanged | PlayerEvent::Seeked) => {},
+                            Some(_) => match fetch(&player, self.count).await {
+                                Ok(state) => (current, queue) = state,
+                                Err(_) => continue 'outer,
+                            },
+                        },
+                        _ = render.tick() => {}
+                    }
+
+                    yield Content::Frame(renderer.render(&current, &queue)?);
+                }
+            }
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "upnext"
+    }
+}
@@ -0,0 +1,188 @@
+use crate::render::{
+    clock::SharedClock,
+    display::{Content, ContentProvider},
+    scheduler::{ContentWrapper, CONTENT_PROVIDERS, TICK_LENGTH},
+};
+use anyhow::{anyhow, Result};
+use apex_hardware::FrameBuffer;
+use async_stream::try_stream;
+use chrono_tz::Tz;
+use config::{Config, Value};
+use embedded_graphics::{
+    geometry::Point,
+    mono_font::{ascii, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    text::{renderer::TextRenderer, Baseline, Text},
+    Drawable,
+};
+use futures::Stream;
+use linkme::distributed_slice;
+use log::{info, warn};
+use std::convert::TryFrom;
+use tokio::{
+    time,
+    time::{Duration, MissedTickBehavior},
+};
+
+#[doc(hidden)]
+#[distributed_slice(CONTENT_PROVIDERS)]
+pub static PROVIDER_INIT: fn(&Config, &SharedClock) -> Result<Box<dyn ContentWrapper>> =
+    register_callback;
+
+/// Only this many zones fit onto the display
+const MAX_ZONES: usize = 3;
+
+#[derive(Debug, Clone)]
+struct Zone {
+    label: String,
+    timezone: Tz,
+}
+
+impl TryFrom<Value> for Zone {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let mut table = value.into_table()?;
+
+        let timezone = table
+            .remove("timezone")
+            .ok_or_else(|| anyhow!("Zone is missing a timezone!"))?
+            .into_str()?;
+        let timezone = timezone
+            .parse::<Tz>()
+            .map_err(|e| anyhow!("Unknown timezone {:?}: {}", timezone, e))?;
+
+        // The city is a sensible default, e.g. `New York` for `America/New_York`
+        let label = match table.remove("label") {
+            Some(label) => label.into_str()?,
+            None => timezone
+                .name()
+                .rsplit('/')
+                .next()
+                .unwrap_or_default()
+                .replace('_', " "),
+        };
+
+        Ok(Zone { label, timezone })
+    }
+}
+
+#[doc(hidden)]
+#[allow(clippy::unnecessary_wraps)]
+fn register_callback(config: &Config, clock: &SharedClock) -> Result<Box<dyn ContentWrapper>> {
+    info!("Registering world clock display source.");
+
+    let mut zones = config
+        .get_array("worldclock.zones")
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|value| {
+            Zone::try_from(value)
+                .map_err(|e| warn!("Skipping world clock zone: {}", e))
+                .ok()
+        })
+        .collect::<Vec<_>>();
+
+    if zones.len() > MAX_ZONES {
+        warn!(
+            "Only {} zones fit onto the world clock, ignoring the rest",
+            MAX_ZONES
+        );
+        zones.truncate(MAX_ZONES);
+    }
+
+    let twelve_hour = config.get_bool("worldclock.twelve_hour").unwrap_or(false);
+
+    Ok(Box::new(WorldClock {
+        zones,
+        twelve_hour,
+        clock: clock.clone(),
+    }))
+}
+
+/// Shows the current time in several timezones stacked on top of each other.
+#[derive(Debug)]
+struct WorldClock {
+    zones: Vec<Zone>,
+    twelve_hour: bool,
+    clock: SharedClock,
+}
+
+impl WorldClock {
+    fn render(&self) -> Result<FrameBuffer> {
+        let mut buffer = FrameBuffer::new();
+        let now = self.clock.local();
+        let format = if self.twelve_hour {
+            "%I:%M %p"
+        } else {
+            "%H:%M"
+        };
+
+        let style = MonoTextStyle::new(&ascii::FONT_6X10, BinaryColor::On);
+        let height = 40 / self.zones.len() as i32;
+
+        for (i, zone) in self.zones.iter().enumerate() {
+            let time = now.with_timezone(&zone.timezone).format(format).to_string();
+            let metrics = style.measure_string(&time, Point::zero(), Baseline::Top);
+            let time_width = metrics.bounding_box.size.width as i32;
+
+            // Center the text within its row
+            let y = i as i32 * height + (height - 10) / 2;
+
+            // Long city names are cut off before they run into the time
+            let characters = (128 - 3 - time_width - 3 - 6) / 6;
+            let label = zone
+                .label
+                .chars()
+                .take(characters.max(0) as usize)
+                .collect::<String>();
+            Text::with_baseline(&label, Point::new(3, y), style, Baseline::Top)
+                .draw(&mut buffer)?;
+            Text::with_baseline(
+                &time,
+                Point::new(128 - 3 - time_width, y),
+                style,
+                Baseline::Top,
+            )
+            .draw(&mut buffer)?;
+        }
+
+        Ok(buffer)
+    }
+}
+
+impl ContentProvider for WorldClock {
+    type ContentStream<'a> = impl Stream<Item = Result<Content>> + 'a;
+
+    // This needs to be enabled until full GAT support is here
+    #[allow(clippy::needless_lifetimes)]
+    fn stream<'this>(&'this mut self) -> Result<Self::ContentStream<'this>> {
+        if self.zones.is_empty() {
+            return Err(anyhow!("No world clock zones configured!"));
+        }
+
+        let mut interval = time::interval(Duration::from_millis(TICK_LENGTH as u64));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        Ok(try_stream! {
+            // The times only change every minute so there's no point in rendering more often
+            let mut minute = None;
+
+            loop {
+                let now = self.clock.local().timestamp() / 60;
+                if minute == Some(now) {
+                    yield Content::Unchanged;
+                } else {
+                    yield Content::Frame(self.render()?);
+                    minute = Some(now);
+                }
+
+                interval.tick().await;
+            }
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "worldclock"
+    }
+}
@@ -0,0 +1,160 @@
+//! Album art for the music player.
+//!
+//! Fetching, decoding and dithering a picture takes long enough to cause a
+//! visible hitch, so it happens in a background task. The [`ArtworkLoader`]
+//! publishes finished pictures through a watch channel and the
+//! [`AlbumArtWidget`] draws whatever is ready without ever waiting for it.
+#[cfg(feature = "http")]
+use crate::net;
+use crate::render::dither;
+use anyhow::{anyhow, Result};
+use apex_music::file_path;
+use embedded_graphics::{geometry::Point, pixelcolor::BinaryColor, prelude::DrawTarget, Pixel};
+use image::{imageops, imageops::FilterType};
+use log::warn;
+use std::sync::Arc;
+use tokio::{sync::watch, task, task::JoinHandle};
+
+/// The artwork replaces the 24x24 play/pause icon
+pub const ART_SIZE: u32 = 24;
+
+/// A picture that has been scaled down and dithered to fit the display.
+#[derive(Debug, Clone)]
+pub struct Artwork {
+    /// The URL the picture was loaded from
+    url: String,
+    /// One entry per pixel, row by row
+    pixels: Vec<bool>,
+}
+
+type Slot = Option<Arc<Artwork>>;
+
+/// Creates a loader and a widget that shows whatever the loader finished
+/// last.
+pub fn channel(position: Point) -> (ArtworkLoader, AlbumArtWidget) {
+    let (sender, receiver) = watch::channel(None);
+    let loader = ArtworkLoader {
+        sender: Arc::new(sender),
+        url: None,
+        task: None,
+    };
+    (loader, AlbumArtWidget { receiver, position })
+}
+
+/// Loads the artwork of the current track in the background.
+#[derive(Debug)]
+pub struct ArtworkLoader {
+    sender: Arc<watch::Sender<Slot>>,
+    /// The URL of the artwork that's being loaded or was loaded last
+    url: Option<String>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl ArtworkLoader {
+    /// Starts loading the artwork for a new track. Nothing happens if the URL
+    /// didn't change, a load that's still in progress for another track is
+    /// cancelled.
+    pub fn load(&mut self, url: Option<String>) {
+        if self.url == url {
+            return;
+        }
+
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+        self.url.clone_from(&url);
+
+        let Some(url) = url else {
+            self.sender.send_replace(None);
+            return;
+        };
+
+        let sender = self.sender.clone();
+        self.task = Some(tokio::spawn(async move {
+            match process(url.clone()).await {
+                Ok(artwork) => {
+                    sender.send_replace(Some(Arc::new(artwork)));
+                }
+                Err(e) => warn!("Failed to load the artwork from {}: {}", url, e),
+            }
+        }));
+    }
+}
+
+impl Drop for ArtworkLoader {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
+
+/// Draws the artwork of the current track if it's ready and nothing
+/// otherwise, which leaves the placeholder underneath visible.
+#[derive(Debug, Clone)]
+pub struct AlbumArtWidget {
+    receiver: watch::Receiver<Slot>,
+    position: Point,
+}
+
+impl AlbumArtWidget {
+    /// Returns the artwork if the one that's ready belongs to `url`. A task
+    /// that was cancelled too late may still publish the artwork of the
+    /// previous track, which is why the URL is compared.
+    pub fn ready(&self, url: &str) -> Option<Arc<Artwork>> {
+        self.receiver
+            .borrow()
+            .as_ref()
+            .filter(|artwork| artwork.url == url)
+            .cloned()
+    }
+
+    /// Draws the artwork for `url` if it's ready.
+    pub fn draw_for<D>(&self, url: &str, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let Some(artwork) = self.ready(url) else {
+            return Ok(());
+        };
+
+        let position = self.position;
+        let pixels = artwork.pixels.iter().enumerate().map(|(i, on)| {
+            let (x, y) = (i as u32 % ART_SIZE, i as u32 / ART_SIZE);
+            Pixel(
+                position + Point::new(x as i32, y as i32),
+                BinaryColor::from(*on),
+            )
+        });
+
+        target.draw_iter(pixels)
+    }
+}
+
+async fn fetch(url: &str) -> Result<Vec<u8>> {
+    if let Some(path) = file_path(url) {
+        return Ok(task::spawn_blocking(move || std::fs::read(path)).await??);
+    }
+
+    #[cfg(feature = "http")]
+    if url.starts_with("http://") || url.starts_with("https://") {
+        let response = net::client().get(url).send().await?.error_for_status()?;
+        return Ok(response.bytes().await?.to_vec());
+    }
+
+    Err(anyhow!("Unsupported artwork URL"))
+}
+
+async fn process(url: String) -> Result<Artwork> {
+    let data = fetch(&url).await?;
+
+    task::spawn_blocking(move || {
+        let image = image::load_from_memory(&data)?.into_luma8();
+        let image = imageops::resize(&image, ART_SIZE, ART_SIZE, FilterType::Triangle);
+        Ok(Artwork {
+            url,
+            pixels: dither::dither(&image),
+        })
+    })
+    .await?
+}
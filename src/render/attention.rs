@@ -0,0 +1,203 @@
+use crate::render::scheduler::TICK_LENGTH;
+use anyhow::{anyhow, Result};
+use apex_hardware::FrameBuffer;
+use config::Config;
+use embedded_graphics::{
+    geometry::{Point, Size},
+    pixelcolor::BinaryColor,
+    prelude::Primitive,
+    primitives::{PrimitiveStyle, Rectangle},
+    Drawable,
+};
+use log::warn;
+use std::convert::TryFrom;
+use tokio::sync::mpsc;
+
+/// How often the screen flashes unless configured otherwise
+const DEFAULT_CYCLES: u32 = 5;
+/// How many milliseconds one flash takes unless configured otherwise
+const DEFAULT_CYCLE_LENGTH: u32 = 500;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+/// The way the screen is flashed to grab the user's attention
+pub enum Pattern {
+    /// The screen is cleared every other half cycle
+    Blink,
+    /// The screen is inverted every other half cycle
+    Invert,
+    /// A border is drawn around the screen every other half cycle
+    Border,
+}
+
+impl TryFrom<String> for Pattern {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "blink" => Ok(Pattern::Blink),
+            "invert" => Ok(Pattern::Invert),
+            "border" => Ok(Pattern::Border),
+            _ => Err(anyhow!(
+                "Unknown attention pattern: {}, valid choices are \"blink\", \"invert\" and \
+                 \"border\"",
+                value
+            )),
+        }
+    }
+}
+
+/// What a source asks the scheduler to do about its attention.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Request {
+    /// Switch to the source and start flashing
+    Trigger(&'static str),
+    /// Stop flashing early if it's for the source
+    Dismiss(&'static str),
+}
+
+/// How a source asks for the user's attention.
+///
+/// The scheduler hands one to every source before it starts them, see
+/// [`ContentProvider::set_attention`]. Requests go straight to the scheduler
+/// so they work whether or not the source is on the display.
+///
+/// [`ContentProvider::set_attention`]: crate::render::display::ContentProvider::set_attention
+#[derive(Debug, Clone)]
+pub struct AttentionHandle {
+    source: &'static str,
+    sender: mpsc::UnboundedSender<Request>,
+}
+
+impl AttentionHandle {
+    pub fn new(source: &'static str, sender: mpsc::UnboundedSender<Request>) -> Self {
+        Self { source, sender }
+    }
+
+    /// Switches to the source and flashes it, e.g. because a reminder is due.
+    pub fn trigger_attention(&self) {
+        // This only fails once the scheduler is gone, i.e. while shutting down
+        let _ = self.sender.send(Request::Trigger(self.source));
+    }
+
+    /// Stops flashing early, e.g. once the reminder isn't due anymore.
+    pub fn dismiss_attention(&self) {
+        let _ = self.sender.send(Request::Dismiss(self.source));
+    }
+}
+
+/// Flashes the screen for a couple of cycles once triggered.
+///
+/// The scheduler triggers it when a source asks for attention through its
+/// [`AttentionHandle`] and passes every frame it draws through `apply`. As
+/// that happens once per frame the animation automatically follows the
+/// render cadence.
+#[derive(Debug, Clone)]
+pub struct Attention {
+    pattern: Pattern,
+    /// Length of one on/off cycle in ticks
+    cycle_length: u32,
+    cycles: u32,
+    /// Ticks left until the animation is over
+    remaining: u32,
+}
+
+impl Attention {
+    pub fn new(pattern: Pattern, cycles: u32, cycle_length: u32) -> Self {
+        Self {
+            pattern,
+            cycle_length: (cycle_length / TICK_LENGTH as u32).max(2),
+            cycles,
+            remaining: 0,
+        }
+    }
+
+    /// Reads `attention.pattern`, `attention.cycles` and
+    /// `attention.cycle_length` from the config, falling back to the
+    /// defaults for invalid values.
+    pub fn from_config(config: &Config) -> Self {
+        let pattern = config
+            .get_str("attention.pattern")
+            .ok()
+            .and_then(|pattern| Pattern::try_from(pattern).map_err(|e| warn!("{}", e)).ok())
+            .unwrap_or(Pattern::Invert);
+        let cycles = config
+            .get_int("attention.cycles")
+            .map_or(DEFAULT_CYCLES, |cycles| cycles as u32);
+        let cycle_length = config
+            .get_int("attention.cycle_length")
+            .map_or(DEFAULT_CYCLE_LENGTH, |length| length as u32);
+
+        Self::new(pattern, cycles, cycle_length)
+    }
+
+    /// Starts the animation from the beginning, even if it's already running.
+    pub fn trigger_attention(&mut self) {
+        self.remaining = self.cycles * self.cycle_length;
+    }
+
+    /// Stops the animation early.
+    pub fn dismiss(&mut self) {
+        self.remaining = 0;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.remaining > 0
+    }
+
+    /// Applies the current step of the animation to `buffer` and advances it by
+    /// one tick.
+    pub fn apply(&mut self, buffer: &mut FrameBuffer) -> Result<()> {
+        if !self.is_active() {
+            return Ok(());
+        }
+
+        let elapsed = self.cycles * self.cycle_length - self.remaining;
+        self.remaining -= 1;
+
+        // Every cycle starts with the highlighted half so the animation is visible
+        // right away
+        if elapsed % self.cycle_length >= self.cycle_length / 2 {
+            return Ok(());
+        }
+
+        match self.pattern {
+            Pattern::Blink => *buffer = FrameBuffer::new(),
+            Pattern::Invert => buffer.invert(),
+            Pattern::Border => {
+                Rectangle::new(Point::zero(), Size::new(128, 40))
+                    .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+                    .draw(buffer)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inverts_every_other_half_cycle_until_it_is_over() {
+        // Two cycles of four ticks each
+        let mut attention = Attention::new(Pattern::Invert, 2, 4 * TICK_LENGTH as u32);
+        let mut lit = FrameBuffer::new();
+        lit.framebuffer.set(8, true);
+        let mut inverted = lit;
+        inverted.invert();
+
+        let mut frame = lit;
+        attention.apply(&mut frame).unwrap();
+        assert_eq!(frame.framebuffer, lit.framebuffer, "Not triggered yet");
+
+        attention.trigger_attention();
+        let mut frames = Vec::new();
+        while attention.is_active() {
+            let mut frame = lit;
+            attention.apply(&mut frame).unwrap();
+            frames.push(frame.framebuffer == inverted.framebuffer);
+        }
+        assert_eq!(frames, [true, true, false, false, true, true, false, false]);
+    }
+}
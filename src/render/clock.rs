@@ -0,0 +1,101 @@
+//! Where sources, widgets and the scheduler get the time from.
+//!
+//! Everything that depends on the time, e.g. the clock source, marquees or
+//! rotating through headlines, asks the [`Clock`] it was handed by the
+//! scheduler instead of the OS. That's the [`SystemClock`] unless the
+//! scheduler was given another one. Tests use a [`MockClock`] instead, which
+//! stops time until it's moved forward by hand so every frame of the whole
+//! pipeline can be checked, e.g. against a golden image.
+#[cfg(test)]
+use chrono::Duration as ChronoDuration;
+use chrono::{DateTime, Local};
+use std::{fmt::Debug, sync::Arc};
+#[cfg(test)]
+use tokio::time;
+use tokio::time::{Duration, Instant};
+
+/// A source of both monotonic and wall time.
+pub trait Clock: Debug + Send + Sync {
+    /// The time that animations, timeouts and the frame rate are measured in.
+    fn now(&self) -> Instant;
+
+    /// The time that's shown on the display.
+    fn local(&self) -> DateTime<Local>;
+
+    /// How much time passed since `earlier`, like [`Instant::elapsed`] but
+    /// according to this clock.
+    fn elapsed(&self, earlier: Instant) -> Duration {
+        self.now().saturating_duration_since(earlier)
+    }
+}
+
+/// A clock that's shared between the scheduler and everything it runs.
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The time according to the OS.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SystemClock;
+
+impl SystemClock {
+    pub fn shared() -> SharedClock {
+        Arc::new(Self)
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn local(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+/// Time that only moves when it's told to.
+///
+/// This follows the time of the tokio runtime, which has to be paused, e.g.
+/// with `#[tokio::test(start_paused = true)]`. That way intervals and
+/// timeouts wait for [`MockClock::advance`] just like everything that asks
+/// the clock.
+#[cfg(test)]
+#[derive(Debug, Copy, Clone)]
+pub struct MockClock {
+    started: Instant,
+    /// The wall time when the clock was created
+    wall: DateTime<Local>,
+}
+
+#[cfg(test)]
+impl MockClock {
+    /// Starts the clock at `wall`.
+    pub fn new(wall: DateTime<Local>) -> Self {
+        Self {
+            started: Instant::now(),
+            wall,
+        }
+    }
+
+    pub fn shared(&self) -> SharedClock {
+        Arc::new(*self)
+    }
+
+    /// Moves the time forward by `duration`, everything that's due on the way
+    /// gets to run.
+    pub async fn advance(&self, duration: Duration) {
+        time::advance(duration).await;
+    }
+}
+
+#[cfg(test)]
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn local(&self) -> DateTime<Local> {
+        let elapsed = ChronoDuration::from_std(self.now() - self.started)
+            .unwrap_or_else(|_| ChronoDuration::zero());
+        self.wall + elapsed
+    }
+}
@@ -1,5 +1,6 @@
 use crate::render::{
-    display::{ContentProvider, FrameBuffer},
+    clock::SharedClock,
+    display::{Content, ContentProvider, FrameBuffer},
     scheduler::{ContentWrapper, CONTENT_PROVIDERS},
 };
 use anyhow::Result;
@@ -20,10 +21,11 @@ use tokio::{
 };
 
 #[distributed_slice(CONTENT_PROVIDERS)]
-static PROVIDER_INIT: fn(&Config) -> Result<Box<dyn ContentWrapper>> = register_callback;
+static PROVIDER_INIT: fn(&Config, &SharedClock) -> Result<Box<dyn ContentWrapper>> =
+    register_callback;
 
 #[allow(clippy::unnecessary_wraps)]
-fn register_callback(_config: &Config) -> Result<Box<dyn ContentWrapper>> {
+fn register_callback(_config: &Config, _clock: &SharedClock) -> Result<Box<dyn ContentWrapper>> {
     info!("Registering dummy display source.");
     let provider = Box::new(DummyProvider {});
     Ok(provider)
@@ -32,7 +34,7 @@ fn register_callback(_config: &Config) -> Result<Box<dyn ContentWrapper>> {
 struct DummyProvider;
 
 impl ContentProvider for DummyProvider {
-    type ContentStream<'a> = impl Stream<Item = Result<FrameBuffer>> + 'a;
+    type ContentStream<'a> = impl Stream<Item = Result<Content>> + 'a;
 
     #[allow(clippy::needless_lifetimes)]
     fn stream<'this>(&'this mut self) -> Result<Self::ContentStream<'this>> {
@@ -48,7 +50,7 @@ impl ContentProvider for DummyProvider {
                 let mut display = FrameBuffer::new();
                 Line::new(Point::new(x_index, 0), Point::new(x_index, 39)).into_styled(style).draw(&mut display)?;
                 Line::new(Point::new(0, y_index), Point::new(127, y_index)).into_styled(style).draw(&mut display)?;
-                yield display;
+                yield Content::Frame(display);
                 interval.tick().await;
                 x_index = x_index.wrapping_add(1) % 128;
                 y_index = y_index.wrapping_add(1) % 40;
@@ -1,14 +1,35 @@
+use crate::render::attention::AttentionHandle;
 use anyhow::Result;
 
 pub use apex_hardware::FrameBuffer;
 use futures_core::Stream;
 
+/// What a source produced for the current tick.
+// Nearly every item is a frame, boxing it would only add an allocation per frame
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Copy, Clone)]
+pub enum Content {
+    /// A new frame that should be displayed
+    Frame(FrameBuffer),
+    /// Nothing changed since the last frame, the scheduler shows the last
+    /// frame again if it has to, e.g. after a notification
+    Unchanged,
+    /// There's nothing to show right now, e.g. because a remote is offline.
+    /// The scheduler skips over the source until it has something again
+    Hidden,
+}
+
 pub trait ContentProvider {
-    type ContentStream<'a>: Stream<Item = Result<FrameBuffer>> + 'a
+    type ContentStream<'a>: Stream<Item = Result<Content>> + 'a
     where
         Self: 'a;
 
     #[allow(clippy::needless_lifetimes)]
     fn stream<'this>(&'this mut self) -> Result<Self::ContentStream<'this>>;
     fn name(&self) -> &'static str;
+
+    /// Hands the source a way to ask for the user's attention, that works
+    /// even while something else is on the display. This is called once
+    /// before `stream`, sources that never need attention just ignore it.
+    fn set_attention(&mut self, _attention: AttentionHandle) {}
 }
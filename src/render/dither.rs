@@ -0,0 +1,35 @@
+use image::GrayImage;
+
+/// Turns a grayscale image into black and white pixels with Floyd-Steinberg
+/// dithering which keeps a surprising amount of detail on such a tiny
+/// display. The pixels are returned row by row.
+pub fn dither(image: &GrayImage) -> Vec<bool> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let mut values = image
+        .pixels()
+        .map(|pixel| f32::from(pixel[0]))
+        .collect::<Vec<_>>();
+    let mut pixels = Vec::with_capacity(values.len());
+
+    for y in 0..height {
+        for x in 0..width {
+            let old = values[x + y * width];
+            let on = old >= 128.0;
+            pixels.push(on);
+
+            let error = old - if on { 255.0 } else { 0.0 };
+            let mut spread = |dx: isize, dy: usize, weight: f32| {
+                let nx = x as isize + dx;
+                if (0..width as isize).contains(&nx) && y + dy < height {
+                    values[nx as usize + (y + dy) * width] += error * weight / 16.0;
+                }
+            };
+            spread(1, 0, 7.0);
+            spread(-1, 1, 3.0);
+            spread(0, 1, 5.0);
+            spread(1, 1, 1.0);
+        }
+    }
+
+    pixels
+}
@@ -0,0 +1,62 @@
+use embedded_graphics::{
+    geometry::{Point, Size},
+    pixelcolor::BinaryColor,
+    prelude::DrawTarget,
+    Drawable, Pixel,
+};
+
+/// A tiny bitmap that's defined right in the code, one byte per row with the
+/// most significant used bit being the leftmost pixel.
+///
+/// This is meant for icons that are only a couple of pixels large where a BMP
+/// asset would be overkill. The background is drawn as well so glyphs stay
+/// readable on top of other content.
+#[derive(Debug, Copy, Clone)]
+pub struct Glyph {
+    rows: &'static [u8],
+    width: u32,
+    position: Point,
+}
+
+impl Glyph {
+    pub const fn new(rows: &'static [u8], width: u32) -> Self {
+        Self {
+            rows,
+            width,
+            position: Point::zero(),
+        }
+    }
+
+    pub fn at(mut self, position: Point) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn size(&self) -> Size {
+        Size::new(self.width, self.rows.len() as u32)
+    }
+}
+
+impl Drawable for Glyph {
+    type Color = BinaryColor;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<Self::Output, <D as DrawTarget>::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let width = self.width as i32;
+        let pixels = self.rows.iter().enumerate().flat_map(|(y, row)| {
+            (0..width).map(move |x| {
+                let on = row & (1 << (width - 1 - x)) != 0;
+                Pixel(
+                    self.position + Point::new(x, y as i32),
+                    BinaryColor::from(on),
+                )
+            })
+        });
+
+        target.draw_iter(pixels)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+//! Helpers for tests that compare rendered widgets against committed golden
+//! images.
+//!
+//! Goldens live in `goldens/` and are plain black and white PNGs with the
+//! size of the display. Set `APEX_UPDATE_GOLDENS=1` to (re)record them after
+//! an intentional change to a widget.
+use apex_hardware::FrameBuffer;
+use embedded_graphics::{pixelcolor::BinaryColor, Drawable};
+use image::{GrayImage, Luma, Rgb, RgbImage};
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+const WIDTH: u32 = 128;
+const HEIGHT: u32 = 40;
+
+fn golden_path(name: impl AsRef<Path>) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("goldens")
+        .join(name)
+}
+
+fn is_on(buffer: &FrameBuffer, x: u32, y: u32) -> bool {
+    buffer.framebuffer[(x + y * WIDTH + 8) as usize]
+}
+
+/// Converts a frame into a grayscale image, lit pixels are white.
+fn to_image(buffer: &FrameBuffer) -> GrayImage {
+    GrayImage::from_fn(WIDTH, HEIGHT, |x, y| {
+        Luma([if is_on(buffer, x, y) { 255 } else { 0 }])
+    })
+}
+
+/// Renders `widget` into an empty frame.
+fn render<T>(widget: &T) -> FrameBuffer
+where
+    T: Drawable<Color = BinaryColor>,
+{
+    let mut buffer = FrameBuffer::new();
+    widget
+        .draw(&mut buffer)
+        .unwrap_or_else(|e| panic!("Failed to render the widget: {}", e));
+    buffer
+}
+
+/// Highlights the differences between two frames: pixels that are only lit
+/// in the golden are red, pixels that are only lit in the actual frame are
+/// green and pixels that match are gray.
+fn diff(expected: &GrayImage, actual: &FrameBuffer) -> (RgbImage, usize) {
+    let mut mismatches = 0;
+    let image = RgbImage::from_fn(WIDTH, HEIGHT, |x, y| {
+        let expected = expected.get_pixel(x, y)[0] > 127;
+        match (expected, is_on(actual, x, y)) {
+            (true, true) => Rgb([128, 128, 128]),
+            (false, false) => Rgb([0, 0, 0]),
+            (true, false) => {
+                mismatches += 1;
+                Rgb([255, 0, 0])
+            }
+            (false, true) => {
+                mismatches += 1;
+                Rgb([0, 255, 0])
+            }
+        }
+    });
+    (image, mismatches)
+}
+
+/// Renders `widget` and compares it pixel by pixel against the golden image
+/// `name` in `goldens/`. Frames are widgets too, e.g. the one a source just
+/// rendered.
+///
+/// On a mismatch the actual frame and a diff image are written next to the
+/// golden, as `<name>.actual.png` and `<name>.diff.png`, before panicking.
+pub fn assert_renders_to<T>(widget: &T, name: impl AsRef<Path>)
+where
+    T: Drawable<Color = BinaryColor>,
+{
+    let path = golden_path(name);
+    let actual = render(widget);
+
+    if env::var_os("APEX_UPDATE_GOLDENS").is_some() {
+        to_image(&actual)
+            .save(&path)
+            .unwrap_or_else(|e| panic!("Failed to record {}: {}", path.display(), e));
+        return;
+    }
+
+    let expected = image::open(&path)
+        .unwrap_or_else(|e| {
+            panic!(
+                "Failed to open {}: {}. Run with APEX_UPDATE_GOLDENS=1 to record it.",
+                path.display(),
+                e
+            )
+        })
+        .into_luma8();
+    assert_eq!(
+        expected.dimensions(),
+        (WIDTH, HEIGHT),
+        "{} doesn't have the size of the display",
+        path.display()
+    );
+
+    let (diff, mismatches) = diff(&expected, &actual);
+    if mismatches > 0 {
+        let actual_path = path.with_extension("actual.png");
+        let diff_path = path.with_extension("diff.png");
+        // These are only there to help, failing to write them shouldn't hide the
+        // mismatch
+        let _ = to_image(&actual).save(&actual_path);
+        let _ = diff.save(&diff_path);
+        panic!(
+            "{} pixels differ from {}, see {} and {}",
+            mismatches,
+            path.display(),
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
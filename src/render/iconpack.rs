@@ -0,0 +1,176 @@
+//! Icons that replace the bundled ones without recompiling.
+//!
+//! An icon pack is a directory of black and white PNGs, each named after the
+//! key it replaces, e.g. `spotify.png` for the icon in front of the player's
+//! name or `paused.png` for the status in front of the progress bar. Widgets
+//! look their icons up in the [`IconRegistry`] and fall back to the bundled
+//! ones for anything the pack doesn't have.
+#[cfg(feature = "icons")]
+use anyhow::{anyhow, Result};
+use config::Config;
+use embedded_graphics::{
+    geometry::{Point, Size},
+    pixelcolor::BinaryColor,
+    prelude::DrawTarget,
+    Pixel,
+};
+#[cfg(feature = "icons")]
+use log::info;
+use log::warn;
+#[cfg(feature = "icons")]
+use std::path::Path;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Mutex, OnceLock},
+};
+
+/// The largest icon slot is the 24x24 play/pause icon of the music player
+#[cfg(feature = "icons")]
+const MAX_SIZE: u32 = 24;
+
+static REGISTRY: OnceLock<IconRegistry> = OnceLock::new();
+
+/// A black and white icon from an icon pack.
+#[derive(Debug, Clone)]
+pub struct Icon {
+    size: Size,
+    /// One entry per pixel, row by row
+    pixels: Vec<bool>,
+}
+
+impl Icon {
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// Draws the icon with its top left corner at `position`. The background
+    /// is drawn as well, just like with glyphs.
+    pub fn draw_at<D>(&self, position: Point, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let width = self.size.width;
+        let pixels = self.pixels.iter().enumerate().map(|(i, on)| {
+            let (x, y) = (i as u32 % width, i as u32 / width);
+            Pixel(
+                position + Point::new(x as i32, y as i32),
+                BinaryColor::from(*on),
+            )
+        });
+
+        target.draw_iter(pixels)
+    }
+}
+
+/// The icons of the configured icon pack by their key.
+#[derive(Debug, Default)]
+pub struct IconRegistry {
+    icons: HashMap<String, Icon>,
+    /// Keys we already complained about, lookups happen on every frame
+    warned: Mutex<HashSet<String>>,
+}
+
+impl IconRegistry {
+    /// Loads every PNG in `display.icon_pack`. Icons that can't be used are
+    /// skipped with a warning. This has to be called before the first icon is
+    /// looked up, later calls don't change anything.
+    pub fn load(config: &Config) {
+        let registry = match config.get_str("display.icon_pack") {
+            Ok(path) => Self::from_dir(&path),
+            Err(_) => Self::default(),
+        };
+        let _ = REGISTRY.set(registry);
+    }
+
+    #[cfg(feature = "icons")]
+    fn from_dir(path: &str) -> Self {
+        let entries = match std::fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Can't read the icon pack {}: {}", path, e);
+                return Self::default();
+            }
+        };
+
+        let icons = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "png"))
+            .filter_map(|path| {
+                let key = path.file_stem()?.to_string_lossy().to_lowercase();
+                match decode(&path) {
+                    Ok(icon) => Some((key, icon)),
+                    Err(e) => {
+                        warn!("Skipping icon {}: {}", path.display(), e);
+                        None
+                    }
+                }
+            })
+            .collect::<HashMap<_, _>>();
+
+        info!("Loaded {} icons from {}", icons.len(), path);
+        Self {
+            icons,
+            warned: Mutex::default(),
+        }
+    }
+
+    #[cfg(not(feature = "icons"))]
+    fn from_dir(_path: &str) -> Self {
+        warn!("Icon packs are only supported when built with the `icons` feature");
+        Self::default()
+    }
+
+    /// Returns the icon for `key` if the pack has one that fits into `slot`.
+    pub fn get(&self, key: &str, slot: Size) -> Option<&Icon> {
+        let icon = self.icons.get(key)?;
+        if icon.size.width <= slot.width && icon.size.height <= slot.height {
+            return Some(icon);
+        }
+
+        let mut warned = self.warned.lock().ok()?;
+        if warned.insert(key.to_string()) {
+            warn!(
+                "The icon for {:?} is {}x{} but only {}x{} fit, using the bundled one instead",
+                key, icon.size.width, icon.size.height, slot.width, slot.height
+            );
+        }
+        None
+    }
+}
+
+/// The icon pack that was loaded on startup, which is empty if there's none.
+pub fn registry() -> &'static IconRegistry {
+    REGISTRY.get_or_init(IconRegistry::default)
+}
+
+/// Reads a PNG that only consists of black, white and transparent pixels.
+/// Transparent pixels are treated as black.
+#[cfg(feature = "icons")]
+fn decode(path: &Path) -> Result<Icon> {
+    let image = image::open(path)?.into_luma_alpha8();
+    let (width, height) = image.dimensions();
+    if width > MAX_SIZE || height > MAX_SIZE {
+        return Err(anyhow!(
+            "{}x{} is larger than {}x{}",
+            width,
+            height,
+            MAX_SIZE,
+            MAX_SIZE
+        ));
+    }
+
+    let pixels = image
+        .pixels()
+        .map(|pixel| match pixel.0 {
+            [_, 0] | [0, _] => Ok(false),
+            [255, _] => Ok(true),
+            _ => Err(anyhow!("Only black and white pixels are supported")),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Icon {
+        size: Size::new(width, height),
+        pixels,
+    })
+}
@@ -0,0 +1,43 @@
+use embedded_graphics::pixelcolor::BinaryColor;
+use lazy_static::lazy_static;
+use tinybmp::Bmp;
+
+static SPOTIFY_ICON: &[u8] = include_bytes!("./../../assets/icons/spotify.bmp");
+static BROWSER_ICON: &[u8] = include_bytes!("./../../assets/icons/browser.bmp");
+static VLC_ICON: &[u8] = include_bytes!("./../../assets/icons/vlc.bmp");
+static MPV_ICON: &[u8] = include_bytes!("./../../assets/icons/mpv.bmp");
+static LOLLYPOP_ICON: &[u8] = include_bytes!("./../../assets/icons/lollypop.bmp");
+
+lazy_static! {
+    static ref SPOTIFY_BMP: Bmp<'static, BinaryColor> =
+        Bmp::<BinaryColor>::from_slice(SPOTIFY_ICON)
+            .expect("Failed to parse BMP for Spotify icon!");
+    static ref BROWSER_BMP: Bmp<'static, BinaryColor> =
+        Bmp::<BinaryColor>::from_slice(BROWSER_ICON)
+            .expect("Failed to parse BMP for browser icon!");
+    static ref VLC_BMP: Bmp<'static, BinaryColor> =
+        Bmp::<BinaryColor>::from_slice(VLC_ICON).expect("Failed to parse BMP for VLC icon!");
+    static ref MPV_BMP: Bmp<'static, BinaryColor> =
+        Bmp::<BinaryColor>::from_slice(MPV_ICON).expect("Failed to parse BMP for mpv icon!");
+    static ref LOLLYPOP_BMP: Bmp<'static, BinaryColor> =
+        Bmp::<BinaryColor>::from_slice(LOLLYPOP_ICON)
+            .expect("Failed to parse BMP for Lollypop icon!");
+}
+
+/// Returns the bundled 8x8 icon for a music player if there is one.
+///
+/// # Arguments
+///
+/// * `name`: the friendly name of the player, e.g. `spotify` or `firefox`
+///
+/// returns: Option<&Bmp<BinaryColor>>
+pub fn player_icon(name: &str) -> Option<&'static Bmp<'static, BinaryColor>> {
+    match name.to_lowercase().as_str() {
+        "spotify" => Some(&*SPOTIFY_BMP),
+        "firefox" | "chromium" | "chrome" | "brave" | "msedge" => Some(&*BROWSER_BMP),
+        "vlc" => Some(&*VLC_BMP),
+        "mpv" => Some(&*MPV_BMP),
+        "lollypop" => Some(&*LOLLYPOP_BMP),
+        _ => None,
+    }
+}
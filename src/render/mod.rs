@@ -1,10 +1,32 @@
+#[cfg(feature = "art")]
+pub(crate) mod art;
+pub(crate) mod attention;
+pub(crate) mod clock;
 #[cfg(feature = "debug")]
 pub(crate) mod debug;
 pub(crate) mod display;
+#[cfg(any(feature = "art", feature = "gif"))]
+pub(crate) mod dither;
+pub(crate) mod glyph;
+#[cfg(test)]
+pub(crate) mod golden;
+// Only the music player looks up icons so far
+#[cfg_attr(
+    not(any(feature = "dbus-support", target_os = "windows")),
+    allow(dead_code)
+)]
+pub(crate) mod iconpack;
+#[cfg(any(feature = "dbus-support", target_os = "windows"))]
+pub(crate) mod icons;
 // This technically doesn't need DBus but nothing else implements it atm
 #[allow(dead_code)]
 pub(crate) mod notifications;
+pub(crate) mod pacing;
 pub mod scheduler;
+pub(crate) mod segments;
+pub(crate) mod splash;
+#[cfg(feature = "http")]
+pub(crate) mod stale;
 pub(crate) mod stream;
 pub(crate) mod text;
 pub(crate) mod util;
@@ -1,4 +1,4 @@
-use crate::render::display::ContentProvider;
+use crate::render::display::{Content, ContentProvider};
 use anyhow::{anyhow, Result};
 use async_stream::try_stream;
 use embedded_graphics::{
@@ -62,7 +62,7 @@ pub trait NotificationProvider {
 }
 
 impl ContentProvider for Notification {
-    type ContentStream<'a> = impl Stream<Item = Result<FrameBuffer>> + 'a;
+    type ContentStream<'a> = impl Stream<Item = Result<Content>> + 'a;
 
     // This needs to be enabled until full GAT support is here
     #[allow(clippy::needless_lifetimes)]
@@ -85,7 +85,7 @@ impl ContentProvider for Notification {
                 })?;
                 Text::new(&self.content, Point::new(3 + 24, 10 + 10), style).draw(&mut image)?;
                 progress.draw_at(i as f32, &mut image)?;
-                yield image;
+                yield Content::Frame(image);
                 interval.tick().await;
             }
         })
@@ -0,0 +1,100 @@
+//! Lowers the frame rate on machines that can't keep up.
+//!
+//! Every frame has a budget of one tick. If rendering it and sending it to
+//! the device takes longer than that the frames pile up, the display lags
+//! behind and key presses take ages to show. The [`FrameController`] keeps an
+//! eye on how long frames take and spaces them out further when they don't
+//! fit, once there's enough headroom again it goes back up step by step.
+use crate::render::clock::SharedClock;
+use log::{info, warn};
+use tokio::time::{Duration, Instant};
+
+/// The frame rate never drops below a fifth of the usual one
+const MAX_SLOWDOWN: u32 = 5;
+/// How many frames are measured before the frame rate is changed again, this
+/// keeps a single slow frame from changing anything
+const SETTLE_FRAMES: u32 = 40;
+/// How much of the previous average is kept for every new measurement
+const SMOOTHING: f64 = 0.9;
+
+/// Decides which frames are drawn and which are skipped.
+#[derive(Debug, Clone)]
+pub struct FrameController {
+    /// The time a frame is supposed to take
+    budget: Duration,
+    /// How many budgets there are between two frames right now
+    slowdown: u32,
+    /// The moving average of how long frames took, in milliseconds
+    average: f64,
+    /// Frames measured since the frame rate changed last
+    frames: u32,
+    next_frame: Instant,
+    enabled: bool,
+    clock: SharedClock,
+}
+
+impl FrameController {
+    pub fn new(budget: Duration, enabled: bool, clock: SharedClock) -> Self {
+        Self {
+            budget,
+            slowdown: 1,
+            average: 0.0,
+            frames: 0,
+            next_frame: clock.now(),
+            enabled,
+            clock,
+        }
+    }
+
+    /// Whether a frame that's ready now should be drawn or skipped.
+    pub fn ready(&self) -> bool {
+        !self.enabled || self.slowdown == 1 || self.clock.now() >= self.next_frame
+    }
+
+    fn fps(&self) -> u64 {
+        1000 / (self.budget * self.slowdown).as_millis().max(1) as u64
+    }
+
+    /// Records how long the frame that was just drawn took, from rendering to
+    /// the device being done with it.
+    pub fn record(&mut self, frame_time: Duration) {
+        if !self.enabled {
+            return;
+        }
+
+        // The next frame is due one interval after this one started. Sources don't
+        // deliver their frames on the dot so they get half a budget of leeway
+        let interval = (self.budget * self.slowdown).saturating_sub(self.budget / 2);
+        self.next_frame = self.clock.now() + interval.saturating_sub(frame_time);
+
+        let millis = frame_time.as_secs_f64() * 1000.0;
+        self.average = self.average * SMOOTHING + millis * (1.0 - SMOOTHING);
+
+        self.frames += 1;
+        if self.frames < SETTLE_FRAMES {
+            return;
+        }
+
+        let budget = self.budget.as_secs_f64() * 1000.0;
+        let interval = budget * f64::from(self.slowdown);
+        if self.average > interval && self.slowdown < MAX_SLOWDOWN {
+            self.slowdown += 1;
+            self.frames = 0;
+            warn!(
+                "Frames take {:.1}ms on average, lowering the frame rate to {} FPS",
+                self.average,
+                self.fps()
+            );
+        } else if self.slowdown > 1 && self.average < (interval - budget) * 0.75 {
+            // Going back up only happens with some room to spare, otherwise we'd
+            // flip between the two rates all the time
+            self.slowdown -= 1;
+            self.frames = 0;
+            info!(
+                "Frames take {:.1}ms on average, raising the frame rate to {} FPS",
+                self.average,
+                self.fps()
+            );
+        }
+    }
+}
@@ -1,29 +1,90 @@
 use anyhow::{anyhow, Result};
-use std::marker::PhantomData;
+use std::{marker::PhantomData, mem};
 
 use crate::render::{
-    display::ContentProvider,
+    attention::{Attention, AttentionHandle, Request},
+    clock::SharedClock,
+    display::{Content, ContentProvider},
+    glyph::Glyph,
     notifications::{Notification, NotificationProvider},
+    pacing::FrameController,
+    splash,
     stream::multiplex,
 };
-use apex_hardware::{AsyncDevice, FrameBuffer};
+use apex_hardware::{AsyncDevice, Disconnected, FrameBuffer, WriteStats};
 use apex_input::Command;
 use config::Config;
+use embedded_graphics::Drawable;
 use futures::{pin_mut, stream, stream::Stream, StreamExt};
 use itertools::Itertools;
+use lazy_static::lazy_static;
 use linkme::distributed_slice;
-use log::{error, info};
+use log::{error, info, warn};
 use std::sync::{
     atomic::{AtomicUsize, Ordering},
     Arc,
 };
-use tokio::sync::broadcast;
+use tokio::{
+    sync::{broadcast, mpsc, watch},
+    time,
+    time::{Duration, Instant},
+};
 
 pub const TICK_LENGTH: usize = 50;
 pub const TICKS_PER_SECOND: usize = 1000 / TICK_LENGTH;
 
+/// How many milliseconds the splash screen is shown unless configured
+/// otherwise
+const DEFAULT_SPLASH_DURATION: u64 = 2000;
+/// How often a keyboard that was disconnected is looked for
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A tiny padlock in the top left corner while the current source is pinned
+const PIN_GLYPH: Glyph = Glyph::new(
+    &[
+        0b01110, //
+        0b10001, //
+        0b11111, //
+        0b11011, //
+        0b11111, //
+    ],
+    5,
+);
+
+/// What's on the display right now, as far as anyone outside the scheduler
+/// is concerned.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct DisplayState {
+    /// The name of the current source, `None` until the scheduler started
+    pub source: Option<&'static str>,
+    pub pinned: bool,
+    /// How bright the display is in percent. The keyboard can only turn it on
+    /// and off, so this is 0 while the scheduler is asleep and 100 otherwise.
+    pub brightness: u8,
+    pub writes: WriteStats,
+}
+
+lazy_static! {
+    static ref STATE: (watch::Sender<DisplayState>, watch::Receiver<DisplayState>) =
+        watch::channel(DisplayState::default());
+}
+
+/// Returns what the scheduler is showing.
+pub fn display_state() -> DisplayState {
+    *STATE.1.borrow()
+}
+
+/// The source after or before `index`, wrapping around at both ends.
+fn neighbour(index: usize, size: usize, forward: bool) -> usize {
+    if forward {
+        index.wrapping_add(1) % size
+    } else {
+        index.checked_sub(1).unwrap_or(size - 1)
+    }
+}
+
 #[distributed_slice]
-pub static CONTENT_PROVIDERS: [fn(&Config) -> Result<Box<dyn ContentWrapper>>] = [..];
+pub static CONTENT_PROVIDERS: [fn(&Config, &SharedClock) -> Result<Box<dyn ContentWrapper>>] = [..];
 
 #[distributed_slice]
 pub static NOTIFICATION_PROVIDERS: [fn() -> Result<Box<dyn NotificationWrapper>>] = [..];
@@ -42,14 +103,15 @@ impl<T: NotificationProvider> NotificationWrapper for T {
 }
 
 pub trait ContentWrapper {
-    fn proxy_stream<'a>(&'a mut self) -> Result<Box<dyn Stream<Item = Result<FrameBuffer>> + 'a>>;
+    fn proxy_stream<'a>(&'a mut self) -> Result<Box<dyn Stream<Item = Result<Content>> + 'a>>;
     fn provider_name(&self) -> &'static str;
+    fn set_attention(&mut self, attention: AttentionHandle);
 }
 
 impl<T: ContentProvider> ContentWrapper for T {
     fn proxy_stream<'this>(
         &'this mut self,
-    ) -> Result<Box<dyn Stream<Item = Result<FrameBuffer>> + 'this>> {
+    ) -> Result<Box<dyn Stream<Item = Result<Content>> + 'this>> {
         let x = <T as ContentProvider>::stream(self)?;
         Ok(Box::new(x.fuse()))
     }
@@ -57,36 +119,90 @@ impl<T: ContentProvider> ContentWrapper for T {
     fn provider_name(&self) -> &'static str {
         self.name()
     }
+
+    fn set_attention(&mut self, attention: AttentionHandle) {
+        <T as ContentProvider>::set_attention(self, attention);
+    }
 }
 
-pub struct Scheduler<'a, T: AsyncDevice + 'a> {
+pub struct Scheduler<'a, T: AsyncDevice<Frame = FrameBuffer> + 'a> {
     device: T,
+    clock: SharedClock,
+    /// When to try again to reach a keyboard that was disconnected
+    reconnect_at: Option<Instant>,
     _marker: PhantomData<&'a T>,
 }
 
-impl<'a, T: 'a + AsyncDevice> Scheduler<'a, T> {
-    pub fn new(device: T) -> Self {
+impl<'a, T: 'a + AsyncDevice<Frame = FrameBuffer>> Scheduler<'a, T> {
+    /// Everything the scheduler starts gets the time from `clock`, that's
+    /// usually the [`SystemClock`](crate::render::clock::SystemClock).
+    pub fn new(device: T, clock: SharedClock) -> Self {
         Self {
             device,
-            _marker: std::marker::PhantomData::default(),
+            clock,
+            reconnect_at: None,
+            _marker: PhantomData,
         }
     }
 
+    /// Sends `frame` to the device or clears it if there's no frame, returns
+    /// whether that happened. A keyboard that was disconnected doesn't stop
+    /// the scheduler, drawing is skipped until the next attempt to reach it
+    /// is due and the device opens it again once it's back.
+    async fn show(&mut self, frame: Option<&FrameBuffer>) -> Result<bool> {
+        if let Some(at) = self.reconnect_at {
+            if self.clock.now() < at {
+                return Ok(false);
+            }
+        }
+
+        let result = match frame {
+            Some(frame) => self.device.draw(frame).await,
+            None => self.device.clear().await,
+        };
+        match result {
+            Ok(()) => {
+                if self.reconnect_at.take().is_some() {
+                    info!("The keyboard is back");
+                }
+                Ok(true)
+            }
+            Err(e) if e.is::<Disconnected>() => {
+                if self.reconnect_at.is_none() {
+                    warn!("{}, waiting for it to come back", e);
+                }
+                self.reconnect_at = Some(self.clock.now() + RECONNECT_INTERVAL);
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    // The main loop, splitting it up would mean passing most of its state around
+    #[allow(clippy::too_many_lines)]
     pub async fn start(
         &mut self,
         rx: broadcast::Receiver<Command>,
         mut config: Config,
     ) -> Result<()> {
+        if config.get_bool("general.splash").unwrap_or(false) {
+            let duration = config
+                .get_int("general.splash_duration")
+                .map_or(DEFAULT_SPLASH_DURATION, |millis| millis as u64);
+            self.show(Some(&splash::render()?)).await?;
+            time::sleep_until(self.clock.now() + Duration::from_millis(duration)).await;
+        }
+
         #[cfg(not(target_os = "macos"))]
         let mut providers = CONTENT_PROVIDERS
             .iter()
-            .map(|f| (f)(&mut config))
+            .map(|f| (f)(&mut config, &self.clock))
             .collect::<Result<Vec<_>>>()?;
 
         #[cfg(target_os = "macos")]
         let mut providers = [
-            crate::providers::clock::PROVIDER_INIT(&mut config)?,
-            crate::providers::coindesk::PROVIDER_INIT(&mut config)?,
+            crate::providers::clock::PROVIDER_INIT(&mut config, &self.clock)?,
+            crate::providers::coindesk::PROVIDER_INIT(&mut config, &self.clock)?,
         ];
 
         let mut notifications = NOTIFICATION_PROVIDERS
@@ -110,13 +226,21 @@ impl<'a, T: 'a + AsyncDevice> Scheduler<'a, T> {
 
         pin_mut!(rx);
 
+        // Sources ask for attention through this whether they're on the display or not
+        let (requests, mut attention_requests) = mpsc::unbounded_channel();
+
+        // Disabled sources aren't started at all, some start background work right away
         let (providers, errors): (Vec<_>, Vec<_>) = providers
             .iter_mut()
-            .map(|i| (i.provider_name(), i.proxy_stream()))
-            .filter(|(name, _)| {
-                let key = format!("{}.enabled", name);
+            .filter(|i| {
+                let key = format!("{}.enabled", i.provider_name());
                 config.get_bool(&key).unwrap_or(true)
             })
+            .map(|i| {
+                let name = i.provider_name();
+                i.set_attention(AttentionHandle::new(name, requests.clone()));
+                (name, i.proxy_stream())
+            })
             .map(|(name, i)| {
                 let key = format!("{}.priority", name);
                 let prio = config.get_int(&key).unwrap_or(99i64);
@@ -124,7 +248,8 @@ impl<'a, T: 'a + AsyncDevice> Scheduler<'a, T> {
             })
             .sorted_by_key(|(_, _, prio)| *prio)
             .map(|(name, i, _)| {
-                i.map_err(|e| anyhow!("Failed to initialize provider: {}. Error: {}", name, e))
+                i.map(|i| (name, i))
+                    .map_err(|e| anyhow!("Failed to initialize provider: {}. Error: {}", name, e))
             })
             .partition_result();
 
@@ -132,8 +257,20 @@ impl<'a, T: 'a + AsyncDevice> Scheduler<'a, T> {
             error!("{}", e);
         }
 
+        let (names, providers): (Vec<_>, Vec<_>) = providers.into_iter().unzip();
+
+        if let Ok(start) = config.get_str("general.start_source") {
+            match names.iter().position(|name| *name == start) {
+                Some(index) => current.store(index, Ordering::SeqCst),
+                None => warn!(
+                    "Can't start on {:?} as it's not an enabled source, starting on {:?} instead",
+                    start,
+                    names.first().copied().unwrap_or_default()
+                ),
+            }
+        }
+
         let providers = providers
-            .into_iter()
             .into_iter()
             .map(Box::into_pin)
             .map(futures::StreamExt::fuse)
@@ -141,46 +278,420 @@ impl<'a, T: 'a + AsyncDevice> Scheduler<'a, T> {
         let size = providers.len();
         let z = current.clone();
 
-        let mut y = multiplex(providers, move || z.load(Ordering::SeqCst));
+        // While pinned the current source stays on the display no matter what
+        let mut pinned = false;
+        // While asleep the sources keep running but nothing is drawn
+        let mut asleep = false;
+
+        // The last frame of every source so sources that report `Content::Unchanged`
+        // can be shown again after something else was drawn over them
+        let mut cache: Vec<Option<FrameBuffer>> = vec![None; size];
+        let mut dirty = false;
+        // Whether the last switch went to the next or the previous source, and how
+        // many hidden sources were skipped since something was shown
+        let mut forward = true;
+        let mut skipped = 0;
+        // Flashes the frames of the current source while it wants attention
+        let mut attention = Attention::from_config(&config);
+
+        let mut pacing = FrameController::new(
+            Duration::from_millis(TICK_LENGTH as u64),
+            config
+                .get_bool("display.adaptive_frame_rate")
+                .unwrap_or(true),
+            self.clock.clone(),
+        );
+
+        let mut y = multiplex(
+            providers,
+            move || z.load(Ordering::SeqCst),
+            self.clock.clone(),
+        );
         loop {
+            let state = DisplayState {
+                source: names.get(current.load(Ordering::SeqCst)).copied(),
+                pinned,
+                brightness: if asleep { 0 } else { 100 },
+                writes: self.device.write_stats(),
+            };
+            STATE
+                .0
+                .send_if_modified(|old| mem::replace(old, state) != state);
+
             tokio::select! {
                 cmd = rx.recv() => {
                     match cmd {
                         Ok(Command::Shutdown) => break,
-                        Ok(Command::NextSource) => {
-                            let new = current.load(Ordering::SeqCst).wrapping_add(1) % size;
-                            current.store(new, Ordering::SeqCst);
-                            self.device.clear().await?;
+                        Ok(Command::Pin) => {
+                            pinned = true;
+                            dirty = true;
                         },
-                        Ok(Command::PreviousSource) => {
-                            let new = match current.load(Ordering::SeqCst) {
-                                0 => size - 1,
-                                n => (n - 1) % size
-                            };
+                        Ok(Command::Unpin) => {
+                            pinned = false;
+                            dirty = true;
+                        },
+                        Ok(Command::TogglePin) => {
+                            pinned = !pinned;
+                            dirty = true;
+                        },
+                        Ok(Command::Sleep) if !asleep => {
+                            info!("Going to sleep");
+                            asleep = true;
+                            self.show(None).await?;
+                        },
+                        Ok(Command::Wake) if asleep => {
+                            info!("Waking up");
+                            asleep = false;
+                            dirty = true;
+                        },
+                        Ok(Command::NextSource | Command::PreviousSource) if pinned => {
+                            info!("Not switching sources while pinned");
+                        },
+                        Ok(command @ (Command::NextSource | Command::PreviousSource)) => {
+                            forward = matches!(command, Command::NextSource);
+                            skipped = 0;
+                            let new = neighbour(current.load(Ordering::SeqCst), size, forward);
                             current.store(new, Ordering::SeqCst);
-                            self.device.clear().await?;
+                            attention.dismiss();
+                            self.show(None).await?;
+                            dirty = true;
                         },
                         _ => {}
                     }
                 },
+                request = attention_requests.recv() => {
+                    let index = current.load(Ordering::SeqCst);
+                    match request {
+                        // Sleeping through it is what the user asked for
+                        Some(Request::Trigger(_)) if asleep => {},
+                        Some(Request::Trigger(source)) => {
+                            let Some(new) = names.iter().position(|name| *name == source) else {
+                                continue;
+                            };
+                            if new != index {
+                                if pinned {
+                                    info!("Not switching to {} for attention while pinned", source);
+                                    continue;
+                                }
+                                info!("Switching to {} as it wants attention", source);
+                                skipped = 0;
+                                current.store(new, Ordering::SeqCst);
+                                self.show(None).await?;
+                            }
+                            attention.trigger_attention();
+                            dirty = true;
+                        },
+                        Some(Request::Dismiss(source)) if names.get(index) == Some(&source) => {
+                            attention.dismiss();
+                            // Makes sure the last flash doesn't stay on the display
+                            dirty = true;
+                        },
+                        _ => {},
+                    }
+                },
                 notification = notifications.next(), if !notifications.is_empty() => {
                     if let Some(Ok(mut notification)) = notification {
+                        // Notifications would take the display away from the pinned source
+                        // and they shouldn't show up on a blank display either
+                        if pinned || asleep {
+                            continue;
+                        }
                         let mut stream = Box::pin(notification.stream()?);
-                        while let Some(display) = stream.next().await {
-                            self.device.draw(&display?).await?;
+                        while let Some(content) = stream.next().await {
+                            if let Content::Frame(display) = content? {
+                                self.show(Some(&display)).await?;
+                            }
                         }
+                        dirty = true;
                     }
                 }
                 content = y.next() => {
-                    if let Some(Ok(content)) = &content {
-                        self.device.draw(content).await?;
+                    let index = current.load(Ordering::SeqCst);
+                    let frame = match content {
+                        Some(Ok(Content::Frame(frame))) => {
+                            skipped = 0;
+                            cache[index] = Some(frame);
+                            Some(frame)
+                        },
+                        Some(Ok(Content::Unchanged)) => {
+                            skipped = 0;
+                            // Only draw the cached frame again if something else is on the display
+                            // or it's being flashed
+                            if dirty || attention.is_active() {
+                                cache[index]
+                            } else {
+                                None
+                            }
+                        },
+                        // Hidden sources are skipped in the direction the user was going,
+                        // unless every single one of them is hidden
+                        Some(Ok(Content::Hidden)) if !pinned && skipped < size => {
+                            skipped += 1;
+                            current.store(neighbour(index, size, forward), Ordering::SeqCst);
+                            attention.dismiss();
+                            self.show(None).await?;
+                            dirty = true;
+                            None
+                        },
+                        _ => None,
+                    };
+
+                    if let Some(mut frame) = frame {
+                        // Skipped frames are still cached, the next one that's drawn
+                        // catches up with them
+                        if asleep || !pacing.ready() {
+                            dirty = true;
+                            continue;
+                        }
+
+                        let start = self.clock.now();
+                        // The cached frame stays as it is so it can be drawn without the
+                        // flash once the animation is over
+                        let flashing = attention.is_active();
+                        attention.apply(&mut frame)?;
+                        if pinned {
+                            PIN_GLYPH.draw(&mut frame)?;
+                        }
+                        let drawn = self.show(Some(&frame)).await?;
+                        pacing.record(y.render_time() + self.clock.elapsed(start));
+                        // Frames that didn't make it to the keyboard are drawn again once
+                        // it's back
+                        dirty = flashing || !drawn;
                     }
                 }
             };
         }
 
-        self.device.clear().await?;
+        self.show(None).await?;
         self.device.shutdown().await?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::clock::MockClock;
+    use apex_hardware::Device;
+    use chrono::{Local, NaiveDate, TimeZone};
+    use config::{File, FileFormat};
+    use std::sync::{atomic::AtomicBool, Mutex};
+
+    /// Keeps the frames instead of sending them anywhere.
+    #[derive(Debug, Clone, Default)]
+    struct Recorder {
+        frames: Arc<Mutex<Vec<FrameBuffer>>>,
+        /// Every draw fails as if the keyboard was gone while this is set
+        unplugged: Arc<AtomicBool>,
+    }
+
+    impl Recorder {
+        fn last(&self) -> FrameBuffer {
+            *self
+                .frames
+                .lock()
+                .unwrap()
+                .last()
+                .expect("Nothing was drawn")
+        }
+
+        fn count(&self) -> usize {
+            self.frames.lock().unwrap().len()
+        }
+    }
+
+    impl Device for Recorder {
+        type Frame = FrameBuffer;
+
+        fn draw(&mut self, display: &FrameBuffer) -> Result<()> {
+            if self.unplugged.load(Ordering::SeqCst) {
+                return Err(Disconnected.into());
+            }
+            self.frames.lock().unwrap().push(*display);
+            Ok(())
+        }
+
+        fn clear(&mut self) -> Result<()> {
+            Device::draw(self, &FrameBuffer::new())
+        }
+
+        fn shutdown(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn config() -> Config {
+        config_with("")
+    }
+
+    /// The config of the tests with some more settings.
+    fn config_with(settings: &str) -> Config {
+        let mut config = Config::default();
+        config
+            .merge(File::from_str(
+                &format!(
+                    "general.start_source = \"clock\"\nclock.twelve_hour = false\n{}",
+                    settings
+                ),
+                FileFormat::Toml,
+            ))
+            .unwrap();
+        config
+    }
+
+    /// What the clock source draws on its own right now.
+    async fn clock_frame(clock: &SharedClock) -> FrameBuffer {
+        let mut source = crate::providers::clock::PROVIDER_INIT(&config(), clock).unwrap();
+        let mut stream = Box::into_pin(source.proxy_stream().unwrap());
+        match stream.next().await {
+            Some(Ok(Content::Frame(frame))) => frame,
+            _ => panic!("The clock didn't draw anything"),
+        }
+    }
+
+    /// Moves the time forward and lets the scheduler catch up, it runs in the
+    /// same task as the test and only gets to draw while the test waits.
+    async fn advance(clock: &MockClock, duration: Duration) {
+        clock.advance(duration).await;
+        for _ in 0..4 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn draws_the_current_source_at_the_time_of_the_clock() {
+        let wall = NaiveDate::from_ymd_opt(2023, 4, 5)
+            .and_then(|date| date.and_hms_opt(12, 34, 56))
+            .unwrap();
+        let clock = MockClock::new(Local.from_local_datetime(&wall).unwrap());
+        let device = Recorder::default();
+        let mut scheduler = Scheduler::new(device.clone(), clock.shared());
+        let (tx, rx) = broadcast::channel(8);
+
+        let steps = async {
+            let first = device.last();
+            assert_eq!(
+                first.framebuffer,
+                clock_frame(&clock.shared()).await.framebuffer
+            );
+
+            // The seconds are the only thing that changes
+            advance(&clock, Duration::from_secs(1)).await;
+            let second = clock_frame(&clock.shared()).await;
+            assert_ne!(first.framebuffer, second.framebuffer);
+            assert_eq!(device.last().framebuffer, second.framebuffer);
+
+            // Pinning draws the padlock over the source
+            tx.send(Command::Pin).unwrap();
+            advance(&clock, Duration::from_millis(TICK_LENGTH as u64)).await;
+            let mut pinned = second;
+            PIN_GLYPH.draw(&mut pinned).unwrap();
+            assert_eq!(device.last().framebuffer, pinned.framebuffer);
+
+            tx.send(Command::Shutdown).unwrap();
+        };
+
+        let (result, ()) = tokio::join!(scheduler.start(rx, config()), steps);
+        result.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shows_the_splash_screen_for_as_long_as_configured() {
+        let clock = MockClock::new(Local::now());
+        let device = Recorder::default();
+        let mut scheduler = Scheduler::new(device.clone(), clock.shared());
+        let (tx, rx) = broadcast::channel(8);
+        let config = config_with("general.splash = true\ngeneral.splash_duration = 2000");
+
+        let steps = async {
+            let splash = splash::render().unwrap();
+            assert_eq!(device.last().framebuffer, splash.framebuffer);
+            advance(&clock, Duration::from_millis(1999)).await;
+            assert_eq!(device.count(), 1);
+
+            advance(&clock, Duration::from_millis(1)).await;
+            assert_eq!(
+                device.last().framebuffer,
+                clock_frame(&clock.shared()).await.framebuffer
+            );
+
+            tx.send(Command::Shutdown).unwrap();
+        };
+
+        let (result, ()) = tokio::join!(scheduler.start(rx, config), steps);
+        result.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn keeps_running_while_the_keyboard_is_unplugged() {
+        let clock = MockClock::new(Local::now());
+        let device = Recorder::default();
+        let mut scheduler = Scheduler::new(device.clone(), clock.shared());
+        let (tx, rx) = broadcast::channel(8);
+
+        let steps = async {
+            advance(&clock, Duration::from_secs(1)).await;
+            device.unplugged.store(true, Ordering::SeqCst);
+            advance(&clock, Duration::from_secs(1)).await;
+            let drawn = device.count();
+            advance(&clock, Duration::from_secs(1)).await;
+            assert_eq!(device.count(), drawn);
+
+            // The frame that's due is drawn once the keyboard was found again
+            device.unplugged.store(false, Ordering::SeqCst);
+            advance(&clock, RECONNECT_INTERVAL).await;
+            assert!(device.count() > drawn);
+            assert_eq!(
+                device.last().framebuffer,
+                clock_frame(&clock.shared()).await.framebuffer
+            );
+
+            tx.send(Command::Shutdown).unwrap();
+        };
+
+        let (result, ()) = tokio::join!(scheduler.start(rx, config()), steps);
+        result.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn switches_to_a_source_that_wants_attention() {
+        let wall = NaiveDate::from_ymd_opt(2023, 4, 5)
+            .and_then(|date| date.and_hms_opt(12, 34, 57))
+            .unwrap();
+        let clock = MockClock::new(Local.from_local_datetime(&wall).unwrap());
+        let device = Recorder::default();
+        let mut scheduler = Scheduler::new(device.clone(), clock.shared());
+        let (tx, rx) = broadcast::channel(8);
+        let config = config_with(
+            "attention.pattern = \"border\"\n[[reminders.items]]\nlabel = \"Stand up\"\ntime = \
+             \"12:35\"",
+        );
+        // The border lights up both corners, neither the clock nor the reminder do
+        let flashed =
+            |frame: &FrameBuffer| frame.framebuffer[8] && frame.framebuffer[8 + 39 * 128 + 127];
+
+        let steps = async {
+            advance(&clock, Duration::from_secs(1)).await;
+            assert_eq!(
+                device.last().framebuffer,
+                clock_frame(&clock.shared()).await.framebuffer
+            );
+            assert!(!device.frames.lock().unwrap().iter().any(flashed));
+
+            // The reminder goes off while the clock is on the display
+            for _ in 0..3 {
+                advance(&clock, Duration::from_secs(1)).await;
+            }
+            assert!(device.frames.lock().unwrap().iter().any(flashed));
+            assert_ne!(
+                device.last().framebuffer,
+                clock_frame(&clock.shared()).await.framebuffer
+            );
+
+            tx.send(Command::Shutdown).unwrap();
+        };
+
+        let (result, ()) = tokio::join!(scheduler.start(rx, config), steps);
+        result.unwrap();
+    }
+}
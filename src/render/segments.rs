@@ -0,0 +1,209 @@
+//! Large seven segment digits, readable from across the room.
+//!
+//! The segments are plain rectangles so the digits stay crisp at any size,
+//! there's nothing to anti-alias on a panel that only knows on and off.
+use embedded_graphics::{
+    geometry::{Point, Size},
+    pixelcolor::BinaryColor,
+    prelude::{DrawTarget, Primitive},
+    primitives::{PrimitiveStyle, Rectangle},
+    Drawable,
+};
+
+/// The segments of every digit, from the most significant bit: top, top
+/// right, bottom right, bottom, bottom left, top left and middle.
+const DIGITS: [u8; 10] = [
+    0b111_1110, // 0
+    0b011_0000, // 1
+    0b110_1101, // 2
+    0b111_1001, // 3
+    0b011_0011, // 4
+    0b101_1011, // 5
+    0b101_1111, // 6
+    0b111_0000, // 7
+    0b111_1111, // 8
+    0b111_1011, // 9
+];
+
+/// Draws digits and colons with segments of a fixed thickness.
+#[derive(Debug, Copy, Clone)]
+pub struct SevenSegment {
+    /// The size of a single digit
+    digit: Size,
+    thickness: u32,
+    /// The space between two characters
+    spacing: u32,
+}
+
+impl SevenSegment {
+    pub const fn new(digit: Size, thickness: u32, spacing: u32) -> Self {
+        Self {
+            digit,
+            thickness,
+            spacing,
+        }
+    }
+
+    /// Colons are only as wide as a segment is thick.
+    fn advance(&self, c: char) -> u32 {
+        match c {
+            ':' | ' ' => self.thickness,
+            _ => self.digit.width,
+        }
+    }
+
+    /// How wide `text` is once it's drawn.
+    pub fn width(&self, text: &str) -> u32 {
+        let characters = text.chars().count() as u32;
+        let widths: u32 = text.chars().map(|c| self.advance(c)).sum();
+        widths + characters.saturating_sub(1) * self.spacing
+    }
+
+    /// The segments of a digit relative to its top left corner.
+    fn segments(&self) -> [Rectangle; 7] {
+        let Size { width, height } = self.digit;
+        let t = self.thickness;
+        // Both halves of the vertical segments are as long as each other, the middle
+        // segment sits in between
+        let half = (height - 3 * t) / 2;
+        let horizontal = Size::new(width - 2 * t, t);
+        let vertical = Size::new(t, half);
+        let (w, t, half) = (width as i32, t as i32, half as i32);
+
+        [
+            Rectangle::new(Point::new(t, 0), horizontal),
+            Rectangle::new(Point::new(w - t, t), vertical),
+            Rectangle::new(Point::new(w - t, 2 * t + half), vertical),
+            Rectangle::new(Point::new(t, 2 * t + 2 * half), horizontal),
+            Rectangle::new(Point::new(0, 2 * t + half), vertical),
+            Rectangle::new(Point::new(0, t), vertical),
+            Rectangle::new(Point::new(t, t + half), horizontal),
+        ]
+    }
+
+    /// Draws `text` with its top left corner at `position`. Digits and `:`
+    /// are drawn, a space takes up as much room as a colon so a blinking
+    /// colon doesn't move the digits. Anything else is left blank.
+    pub fn draw<D>(&self, text: &str, position: Point, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let style = PrimitiveStyle::with_fill(BinaryColor::On);
+        let segments = self.segments();
+        let mut x = position.x;
+
+        for c in text.chars() {
+            let origin = Point::new(x, position.y);
+            match c {
+                '0'..='9' => {
+                    let mask = DIGITS[c as usize - '0' as usize];
+                    for (i, segment) in segments.iter().enumerate() {
+                        if mask & (1 << (6 - i)) != 0 {
+                            Rectangle::new(origin + segment.top_left, segment.size)
+                                .into_styled(style)
+                                .draw(target)?;
+                        }
+                    }
+                }
+                ':' => {
+                    let dot = Size::new(self.thickness, self.thickness);
+                    let height = self.digit.height as i32;
+                    for y in [height / 3, height * 2 / 3] {
+                        let top = y - self.thickness as i32 / 2;
+                        Rectangle::new(origin + Point::new(0, top), dot)
+                            .into_styled(style)
+                            .draw(target)?;
+                    }
+                }
+                _ => {}
+            }
+            x += (self.advance(c) + self.spacing) as i32;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use apex_hardware::FrameBuffer;
+
+    const SMALL: SevenSegment = SevenSegment::new(Size::new(5, 7), 1, 1);
+
+    /// Draws `text` in the top left corner and returns the lit pixels as rows
+    /// of `#` as wide as the text.
+    fn render(text: &str) -> Vec<String> {
+        let mut buffer = FrameBuffer::new();
+        SMALL.draw(text, Point::zero(), &mut buffer).unwrap();
+        (0..7)
+            .map(|y| {
+                (0..SMALL.width(text))
+                    .map(|x| {
+                        if buffer.framebuffer[8 + y * 128 + x as usize] {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn draws_every_digit() {
+        assert_eq!(
+            render("01234"),
+            [
+                ".###.........###...###.......",
+                "#...#.....#.....#.....#.#...#",
+                "#...#.....#.....#.....#.#...#",
+                ".............###...###...###.",
+                "#...#.....#.#.........#.....#",
+                "#...#.....#.#.........#.....#",
+                ".###.........###...###.......",
+            ]
+        );
+        assert_eq!(
+            render("56789"),
+            [
+                ".###...###...###...###...###.",
+                "#.....#.........#.#...#.#...#",
+                "#.....#.........#.#...#.#...#",
+                ".###...###.........###...###.",
+                "....#.#...#.....#.#...#.....#",
+                "....#.#...#.....#.#...#.....#",
+                ".###...###.........###...###.",
+            ]
+        );
+    }
+
+    #[test]
+    fn a_blinking_colon_leaves_the_digits_where_they_are() {
+        assert_eq!(
+            render("1:2"),
+            [
+                ".........###.",
+                "....#.......#",
+                "....#.#.....#",
+                ".........###.",
+                "....#.#.#....",
+                "....#...#....",
+                ".........###.",
+            ]
+        );
+        assert_eq!(
+            render("1 2"),
+            [
+                ".........###.",
+                "....#.......#",
+                "....#.......#",
+                ".........###.",
+                "....#...#....",
+                "....#...#....",
+                ".........###.",
+            ]
+        );
+    }
+}
@@ -0,0 +1,41 @@
+use anyhow::Result;
+use apex_hardware::FrameBuffer;
+use embedded_graphics::{
+    geometry::Point,
+    image::Image,
+    mono_font::{ascii, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    text::{Baseline, Text},
+    Drawable,
+};
+use lazy_static::lazy_static;
+use tinybmp::Bmp;
+
+static LOGO: &[u8] = include_bytes!("./../../assets/logo.bmp");
+
+lazy_static! {
+    static ref LOGO_BMP: Bmp<'static, BinaryColor> =
+        Bmp::<BinaryColor>::from_slice(LOGO).expect("Failed to parse BMP for the logo!");
+}
+
+/// Renders the splash screen that's shown on startup, i.e. the logo along
+/// with the name and version of the application.
+pub fn render() -> Result<FrameBuffer> {
+    let mut buffer = FrameBuffer::new();
+
+    Image::new(&*LOGO_BMP, Point::new(10, 10)).draw(&mut buffer)?;
+
+    let name = MonoTextStyle::new(&ascii::FONT_8X13_BOLD, BinaryColor::On);
+    Text::with_baseline("apex-tux", Point::new(48, 9), name, Baseline::Top).draw(&mut buffer)?;
+
+    let version = MonoTextStyle::new(&ascii::FONT_6X10, BinaryColor::On);
+    Text::with_baseline(
+        concat!("v", env!("CARGO_PKG_VERSION")),
+        Point::new(48, 23),
+        version,
+        Baseline::Top,
+    )
+    .draw(&mut buffer)?;
+
+    Ok(buffer)
+}
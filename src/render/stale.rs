@@ -0,0 +1,79 @@
+use crate::render::{clock::SharedClock, glyph::Glyph};
+use embedded_graphics::{geometry::Point, pixelcolor::BinaryColor, prelude::DrawTarget, Drawable};
+use tokio::time::{Duration, Instant};
+
+/// Keeps track of when a networked source last fetched its data successfully
+/// so it can tell the user when it's showing outdated information.
+#[derive(Debug, Clone)]
+pub struct Staleness {
+    last_success: Option<Instant>,
+    clock: SharedClock,
+}
+
+impl Staleness {
+    pub fn new(clock: SharedClock) -> Self {
+        Self {
+            last_success: None,
+            clock,
+        }
+    }
+
+    /// Records a successful fetch.
+    pub fn success(&mut self) {
+        self.last_success = Some(self.clock.now());
+    }
+
+    /// Returns how old the data is or `None` if it was never fetched.
+    pub fn age(&self) -> Option<Duration> {
+        self.last_success.map(|success| self.clock.elapsed(success))
+    }
+
+    /// Returns `true` if the data is older than `max_age`. Data that was never
+    /// fetched successfully is always considered stale.
+    pub fn is_stale(&self, max_age: Duration) -> bool {
+        self.age().is_none_or(|age| age > max_age)
+    }
+}
+
+/// A tiny hourglass in the top right corner that marks the content as stale.
+#[derive(Debug, Copy, Clone)]
+pub struct StaleMarker {
+    position: Point,
+}
+
+impl StaleMarker {
+    const GLYPH: Glyph = Glyph::new(
+        &[
+            0b11111, //
+            0b01010, //
+            0b00100, //
+            0b01010, //
+            0b11111, //
+        ],
+        5,
+    );
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Default for StaleMarker {
+    fn default() -> Self {
+        Self {
+            position: Point::new(128 - Self::GLYPH.size().width as i32, 0),
+        }
+    }
+}
+
+impl Drawable for StaleMarker {
+    type Color = BinaryColor;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<Self::Output, <D as DrawTarget>::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        Self::GLYPH.at(self.position).draw(target)
+    }
+}
@@ -1,3 +1,4 @@
+use crate::render::clock::SharedClock;
 use futures::{
     stream::{FusedStream, StreamExt},
     Stream,
@@ -6,6 +7,7 @@ use pin_project_lite::pin_project;
 use std::{
     pin::Pin,
     task::{Context, Poll},
+    time::Duration,
 };
 
 pin_project! {
@@ -13,11 +15,16 @@ pin_project! {
     pub struct Multiplexer<St, F> {
         #[pin]
         inner: Vec<St>,
-        f: F
+        f: F,
+        // Time spent inside the current stream since it last produced something
+        busy: Duration,
+        // How long it took the current stream to produce its last item
+        last: Duration,
+        clock: SharedClock,
     }
 }
 
-pub fn multiplex<I, F>(streams: I, f: F) -> Multiplexer<I::Item, F>
+pub fn multiplex<I, F>(streams: I, f: F, clock: SharedClock) -> Multiplexer<I::Item, F>
 where
     I: IntoIterator,
     I::Item: Stream + Unpin + FusedStream,
@@ -29,7 +36,13 @@ where
         set.push(stream);
     }
 
-    Multiplexer { inner: set, f }
+    Multiplexer {
+        inner: set,
+        f,
+        busy: Duration::ZERO,
+        last: Duration::ZERO,
+        clock,
+    }
 }
 
 impl<St, F> Stream for Multiplexer<St, F>
@@ -44,10 +57,19 @@ where
 
         let index = (this.f)();
         let inner_vec = this.inner.get_mut();
-        inner_vec
+
+        // Sources render their frames while they're polled so this is how long
+        // rendering took, waiting for the next tick doesn't count
+        let start = this.clock.now();
+        let poll = inner_vec
             .get_mut(index)
             .expect("Bad index")
-            .poll_next_unpin(cx)
+            .poll_next_unpin(cx);
+        *this.busy += this.clock.elapsed(start);
+        if poll.is_ready() {
+            *this.last = std::mem::take(this.busy);
+        }
+        poll
     }
 }
 
@@ -67,7 +89,18 @@ where
     F: FnMut() -> usize,
 {
     #[allow(dead_code)]
-    pub fn new(futures: Vec<St>, f: F) -> Self {
-        Self { inner: futures, f }
+    pub fn new(futures: Vec<St>, f: F, clock: SharedClock) -> Self {
+        Self {
+            inner: futures,
+            f,
+            busy: Duration::ZERO,
+            last: Duration::ZERO,
+            clock,
+        }
+    }
+
+    /// How long the stream that produced the last item was busy doing so.
+    pub fn render_time(&self) -> Duration {
+        self.last
     }
 }
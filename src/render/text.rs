@@ -1,15 +1,161 @@
-use anyhow::Result;
+use crate::render::clock::SharedClock;
+use anyhow::{anyhow, Result};
 use apex_hardware::BitVec;
+use config::Config;
 use embedded_graphics::{
     draw_target::DrawTarget,
     geometry::{OriginDimensions, Point, Size},
-    mono_font::{ascii::FONT_6X10, MonoFont, MonoTextStyle, MonoTextStyleBuilder},
+    mono_font::{ascii::FONT_6X10, iso_8859_1, MonoFont, MonoTextStyle, MonoTextStyleBuilder},
     pixelcolor::BinaryColor,
-    text::{renderer::TextRenderer, Baseline, Text},
+    prelude::Primitive,
+    primitives::{PrimitiveStyle, Rectangle},
+    text::{Baseline, Text},
     Drawable, Pixel,
 };
+use log::warn;
 use num_traits::AsPrimitive;
-use std::convert::TryFrom;
+use std::{
+    convert::TryFrom,
+    sync::atomic::{AtomicU8, Ordering},
+};
+use tokio::time::Instant;
+
+/// The Latin-1 fonts, they cover accented letters and the like that the ASCII
+/// ones lack. Regular ones come first so they're picked over bold and italic
+/// ones of the same size
+const FALLBACK_FONTS: [&MonoFont; 22] = [
+    &iso_8859_1::FONT_4X6,
+    &iso_8859_1::FONT_5X7,
+    &iso_8859_1::FONT_5X8,
+    &iso_8859_1::FONT_6X9,
+    &iso_8859_1::FONT_6X10,
+    &iso_8859_1::FONT_6X12,
+    &iso_8859_1::FONT_6X13,
+    &iso_8859_1::FONT_7X13,
+    &iso_8859_1::FONT_7X14,
+    &iso_8859_1::FONT_8X13,
+    &iso_8859_1::FONT_9X15,
+    &iso_8859_1::FONT_9X18,
+    &iso_8859_1::FONT_10X20,
+    &iso_8859_1::FONT_6X13_BOLD,
+    &iso_8859_1::FONT_7X13_BOLD,
+    &iso_8859_1::FONT_7X14_BOLD,
+    &iso_8859_1::FONT_8X13_BOLD,
+    &iso_8859_1::FONT_9X15_BOLD,
+    &iso_8859_1::FONT_9X18_BOLD,
+    &iso_8859_1::FONT_6X13_ITALIC,
+    &iso_8859_1::FONT_7X13_ITALIC,
+    &iso_8859_1::FONT_8X13_ITALIC,
+];
+
+static MISSING_GLYPHS: AtomicU8 = AtomicU8::new(MissingGlyphs::Fallback as u8);
+
+/// What happens to characters the font can't draw, e.g. emoji or CJK in
+/// song titles.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MissingGlyphs {
+    /// Accented letters and the like are taken from the Latin-1 version of
+    /// the font, a box is drawn for everything else
+    Fallback,
+    /// A box is drawn for every missing character
+    Placeholder,
+    /// Missing characters are left out
+    Strip,
+}
+
+impl TryFrom<String> for MissingGlyphs {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "fallback" => Ok(MissingGlyphs::Fallback),
+            "placeholder" => Ok(MissingGlyphs::Placeholder),
+            "strip" => Ok(MissingGlyphs::Strip),
+            _ => Err(anyhow!("Unknown way to handle missing glyphs: {}", value)),
+        }
+    }
+}
+
+impl MissingGlyphs {
+    /// Reads `display.missing_glyphs` from the config. This has to be called
+    /// before the first text is rendered.
+    pub fn configure(config: &Config) {
+        let missing = config
+            .get_str("display.missing_glyphs")
+            .map_or(Ok(MissingGlyphs::Fallback), MissingGlyphs::try_from)
+            .unwrap_or_else(|e| {
+                warn!("{}", e);
+                MissingGlyphs::Fallback
+            });
+        MISSING_GLYPHS.store(missing as u8, Ordering::SeqCst);
+    }
+
+    fn current() -> Self {
+        match MISSING_GLYPHS.load(Ordering::SeqCst) {
+            x if x == MissingGlyphs::Placeholder as u8 => MissingGlyphs::Placeholder,
+            x if x == MissingGlyphs::Strip as u8 => MissingGlyphs::Strip,
+            _ => MissingGlyphs::Fallback,
+        }
+    }
+}
+
+/// Whether `font` has a glyph for `c`. Fonts draw their replacement character,
+/// a question mark, for everything they don't have, so that's what we look
+/// for.
+fn covers(font: &MonoFont, c: char) -> bool {
+    let replacement = font.glyph_mapping.index(char::MAX);
+    c == '?' || font.glyph_mapping.index(c) != replacement
+}
+
+/// A single character cell of a line of text.
+#[derive(Debug, Copy, Clone)]
+enum Cell {
+    Glyph(char, &'static MonoFont<'static>),
+    Placeholder,
+}
+
+/// Picks the font for every character of `text`, all cells have the size of
+/// the characters of `font`.
+fn layout(text: &str, font: &'static MonoFont<'static>, missing: MissingGlyphs) -> Vec<Cell> {
+    // Fonts can only be mixed if their characters are the same size
+    let fallback = FALLBACK_FONTS.iter().copied().find(|fallback| {
+        fallback.character_size == font.character_size && fallback.baseline == font.baseline
+    });
+
+    text.chars()
+        .filter_map(|c| {
+            if covers(font, c) {
+                return Some(Cell::Glyph(c, font));
+            }
+            match (missing, fallback) {
+                (MissingGlyphs::Strip, _) => None,
+                (MissingGlyphs::Fallback, Some(fallback)) if covers(fallback, c) => {
+                    Some(Cell::Glyph(c, fallback))
+                }
+                _ => Some(Cell::Placeholder),
+            }
+        })
+        .collect()
+}
+
+/// How fast text scrolls once it doesn't fit.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ScrollMode {
+    /// Always the same pace, in pixels per second
+    FixedSpeed(u32),
+    /// One full cycle takes this many seconds no matter how long the text is
+    FixedDuration(u32),
+}
+
+impl ScrollMode {
+    /// Pixels per second for a canvas of the given width.
+    fn speed(self, width: u32) -> f64 {
+        match self {
+            ScrollMode::FixedSpeed(speed) => f64::from(speed),
+            ScrollMode::FixedDuration(seconds) => f64::from(width) / f64::from(seconds.max(1)),
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct ScrollableCanvas {
@@ -67,6 +213,7 @@ pub struct ScrollableBuilder {
     position: Option<Point>,
     projection: Option<Size>,
     font: Option<&'static MonoFont<'static>>,
+    mode: Option<(ScrollMode, SharedClock)>,
     text: String,
 }
 
@@ -120,6 +267,34 @@ impl StatefulScrollable {
         }
         Ok(false)
     }
+
+    /// Like [`StatefulScrollable::update`] but keeps the text scrolling if it
+    /// didn't change, changed texts start over from the beginning.
+    pub fn update_or_scroll(&mut self, text: &str) -> Result<()> {
+        if !self.update(text)? {
+            self.text.scroll();
+        }
+        Ok(())
+    }
+
+    /// A line in the default font at `y` with a small margin on both sides.
+    pub fn row(y: i32) -> Result<Self> {
+        ScrollableBuilder::new()
+            .with_custom_spacing(10)
+            .with_position(Point::new(3, y))
+            .with_projection(Size::new(128 - 2 * 3, 10))
+            .try_into()
+    }
+
+    /// A line in `font` at `y` that spans the whole display.
+    pub fn wide_row(font: &'static MonoFont<'static>, y: i32) -> Result<Self> {
+        ScrollableBuilder::new()
+            .with_custom_font(font)
+            .with_custom_spacing(16)
+            .with_position(Point::new(0, y))
+            .with_projection(Size::new(128, font.character_size.height))
+            .try_into()
+    }
 }
 
 impl ScrollableBuilder {
@@ -147,35 +322,87 @@ impl ScrollableBuilder {
         self
     }
 
-    #[allow(dead_code)]
     pub fn with_custom_font(mut self, font: &'static MonoFont<'static>) -> Self {
         self.font = Some(font);
         self
     }
 
+    /// Scrolls based on the time that passed according to `clock` instead of
+    /// by one pixel per call to [`Scrollable::scroll`].
+    pub fn with_scroll_mode(mut self, mode: ScrollMode, clock: SharedClock) -> Self {
+        self.mode = Some((mode, clock));
+        self
+    }
+
     fn calculate_spacing(&self) -> u32 {
         self.spacing.unwrap_or(5)
     }
 
-    fn calculate_size(&self, renderer: &MonoTextStyle<BinaryColor>) -> Size {
-        let metrics = renderer.measure_string(&self.text, Point::new(0, 0), Baseline::Top);
-        metrics.bounding_box.size + Size::new(self.calculate_spacing(), 0)
+    /// The size of the text plus the spacing after it, every cell is as wide
+    /// as a character of `font` no matter which font it's drawn with.
+    fn calculate_size(&self, font: &MonoFont, cells: usize) -> Size {
+        let advance = font.character_size.width + font.character_spacing;
+        let width = (advance * cells as u32).saturating_sub(font.character_spacing);
+        let height = if cells == 0 {
+            0
+        } else {
+            font.character_size.height
+        };
+        Size::new(width + self.calculate_spacing(), height)
     }
 
     fn default_font() -> &'static MonoFont<'static> {
         &FONT_6X10
     }
 
-    pub fn build(&self) -> Result<Scrollable> {
-        let renderer = MonoTextStyleBuilder::new()
-            .font(self.font.unwrap_or_else(Self::default_font))
+    fn style(font: &'static MonoFont<'static>) -> MonoTextStyle<'static, BinaryColor> {
+        MonoTextStyleBuilder::new()
+            .font(font)
             .text_color(BinaryColor::On)
-            .build();
-        let size = self.calculate_size(&renderer);
+            .build()
+    }
+
+    pub fn build(&self) -> Result<Scrollable> {
+        let font = self.font.unwrap_or_else(Self::default_font);
+        let cells = layout(&self.text, font, MissingGlyphs::current());
+        let size = self.calculate_size(font, cells.len());
         let mut canvas = ScrollableCanvas::new(size.width, size.height);
 
-        Text::with_baseline(&self.text, Point::new(0, 0), renderer, Baseline::Top)
+        if cells
+            .iter()
+            .all(|cell| matches!(cell, Cell::Glyph(_, f) if std::ptr::eq(*f, font)))
+        {
+            Text::with_baseline(
+                &self.text,
+                Point::new(0, 0),
+                Self::style(font),
+                Baseline::Top,
+            )
             .draw(&mut canvas)?;
+        } else {
+            // Characters from different fonts have to be drawn one at a time
+            let advance = (font.character_size.width + font.character_spacing) as i32;
+            let mut buffer = [0; 4];
+            for (i, cell) in cells.iter().enumerate() {
+                let position = Point::new(i as i32 * advance, 0);
+                match *cell {
+                    Cell::Glyph(c, font) => {
+                        let c = c.encode_utf8(&mut buffer);
+                        Text::with_baseline(c, position, Self::style(font), Baseline::Top)
+                            .draw(&mut canvas)?;
+                    }
+                    Cell::Placeholder => {
+                        let size = font.character_size;
+                        Rectangle::new(
+                            position + Point::new(0, 1),
+                            Size::new(size.width - 1, font.baseline.min(size.height - 1)),
+                        )
+                        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+                        .draw(&mut canvas)?;
+                    }
+                }
+            }
+        }
 
         Ok(Scrollable {
             canvas,
@@ -183,6 +410,10 @@ impl ScrollableBuilder {
             position: self.position.unwrap_or_default(),
             spacing: self.calculate_spacing(),
             scroll: 0,
+            mode: self.mode.clone().map(|(mode, clock)| {
+                let started = clock.now();
+                (mode, clock, started)
+            }),
         })
     }
 }
@@ -194,6 +425,9 @@ pub struct Scrollable {
     pub position: Point,
     pub spacing: u32,
     pub scroll: u32,
+    /// Scrolling advances one pixel per call without a mode. With one it's
+    /// relative to when the text was rendered
+    mode: Option<(ScrollMode, SharedClock, Instant)>,
 }
 
 impl Drawable for Scrollable {
@@ -210,15 +444,19 @@ impl Drawable for Scrollable {
 }
 
 impl Scrollable {
+    /// Draws the text as it would be shown after scrolling for `tick` pixels.
+    /// The text itself is rendered in 1-bit but it can be drawn onto any
+    /// target whose color can be converted from `BinaryColor`.
     pub fn at_tick<D>(&self, target: &mut D, tick: u32) -> Result<(), <D as DrawTarget>::Error>
     where
-        D: DrawTarget<Color = <Scrollable as Drawable>::Color>,
+        D: DrawTarget,
+        D::Color: From<BinaryColor>,
     {
         // TODO: There's probably some really cool bitwise hacks to do here...
         let scroll = tick % self.canvas.width;
         let pixels = self.projection.height * self.projection.width;
         // We know exactly how many pixels we can push so we can pre-allocate exactly.
-        let mut pixels = Vec::with_capacity(pixels as usize);
+        let mut pixels: Vec<Pixel<D::Color>> = Vec::with_capacity(pixels as usize);
 
         for n in 0..self.projection.height {
             let min = scroll + n * self.canvas.width;
@@ -227,7 +465,10 @@ impl Scrollable {
             for i in min..max {
                 let coord = Point::new((i - min) as i32, n as i32);
                 let color = self.canvas.canvas[i as usize];
-                pixels.push(Pixel(self.position + coord, BinaryColor::from(color)));
+                pixels.push(Pixel(
+                    self.position + coord,
+                    BinaryColor::from(color).into(),
+                ));
             }
 
             // We've reached the end and need to render something from the start
@@ -247,7 +488,10 @@ impl Scrollable {
                     );
                     if (i as usize) < self.canvas.canvas.len() {
                         let color = self.canvas.canvas[i as usize];
-                        pixels.push(Pixel(self.position + coord, BinaryColor::from(color)));
+                        pixels.push(Pixel(
+                            self.position + coord,
+                            BinaryColor::from(color).into(),
+                        ));
                     }
                 }
             }
@@ -257,7 +501,131 @@ impl Scrollable {
         Ok(())
     }
 
+    /// Returns `true` if the rendered text is wider than the projection and
+    /// has to scroll to be readable.
+    pub fn needs_scroll(&self) -> bool {
+        // The canvas includes the spacing that separates the end of the text from its
+        // start
+        self.canvas.width - self.spacing > self.projection.width
+    }
+
+    /// Advances the text by one pixel, or as far as it should have come by
+    /// now if it has a [`ScrollMode`]. Text that fits into the projection
+    /// stays where it is.
     pub fn scroll(&mut self) {
-        self.scroll += 1;
+        if !self.needs_scroll() {
+            return;
+        }
+
+        match &self.mode {
+            Some((mode, clock, started)) => {
+                let elapsed = clock.elapsed(*started).as_secs_f64();
+                let pixels = elapsed * mode.speed(self.canvas.width);
+                self.scroll = (pixels as u64 % u64::from(self.canvas.width)) as u32;
+            }
+            None => self.scroll += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::clock::MockClock;
+    use apex_hardware::FrameBuffer;
+    use chrono::Local;
+    use tokio::time::Duration;
+
+    /// "Hello" is 30 pixels wide in the default font, the spacing comes on top
+    fn hello(width: u32) -> Scrollable {
+        ScrollableBuilder::new()
+            .with_text("Hello")
+            .with_custom_spacing(10)
+            .with_projection(Size::new(width, 10))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn text_that_fits_stays_put() {
+        for width in [30, 128] {
+            let mut text = hello(width);
+            assert!(!text.needs_scroll(), "{} pixels", width);
+            text.scroll();
+            assert_eq!(text.scroll, 0);
+        }
+    }
+
+    #[test]
+    fn text_that_overflows_scrolls_a_pixel_at_a_time() {
+        let mut text = hello(29);
+        assert!(text.needs_scroll());
+        for _ in 0..3 {
+            text.scroll();
+        }
+        assert_eq!(text.scroll, 3);
+    }
+
+    #[test]
+    fn scrolling_wraps_around_after_the_spacing() {
+        let text = hello(20);
+        let mut start = FrameBuffer::new();
+        text.at_tick(&mut start, 0).unwrap();
+        let mut wrapped = FrameBuffer::new();
+        text.at_tick(&mut wrapped, 30 + 10).unwrap();
+        let mut moved = FrameBuffer::new();
+        text.at_tick(&mut moved, 1).unwrap();
+
+        assert_eq!(start.framebuffer, wrapped.framebuffer);
+        assert_ne!(start.framebuffer, moved.framebuffer);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fixed_speed_follows_the_clock() {
+        let clock = MockClock::new(Local::now());
+        let mut text = ScrollableBuilder::new()
+            .with_text("Hello")
+            .with_custom_spacing(10)
+            .with_projection(Size::new(20, 10))
+            .with_scroll_mode(ScrollMode::FixedSpeed(10), clock.shared())
+            .build()
+            .unwrap();
+
+        // No matter how often it's asked
+        for _ in 0..5 {
+            text.scroll();
+        }
+        assert_eq!(text.scroll, 0);
+
+        clock.advance(Duration::from_millis(1500)).await;
+        text.scroll();
+        assert_eq!(text.scroll, 15);
+        // Past the end it starts over
+        clock.advance(Duration::from_secs(3)).await;
+        text.scroll();
+        assert_eq!(text.scroll, 45 - 40);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fixed_duration_takes_as_long_for_any_text() {
+        let clock = MockClock::new(Local::now());
+        let scrollable = |text: &str| {
+            ScrollableBuilder::new()
+                .with_text(text)
+                .with_custom_spacing(10)
+                .with_projection(Size::new(20, 10))
+                .with_scroll_mode(ScrollMode::FixedDuration(4), clock.shared())
+                .build()
+                .unwrap()
+        };
+        let mut short = scrollable("Hello");
+        let mut long = scrollable("Hello there, how are you?");
+
+        clock.advance(Duration::from_secs(1)).await;
+        short.scroll();
+        long.scroll();
+        // A quarter of the way through
+        assert_eq!(short.scroll, 40 / 4);
+        assert_eq!(long.scroll, (25 * 6 + 10) / 4);
     }
 }
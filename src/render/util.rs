@@ -8,18 +8,16 @@ use embedded_graphics::{
 pub struct ProgressBar {
     maximum_value: f32,
     origin: Point,
-    style: PrimitiveStyle<BinaryColor>,
 }
 
 impl ProgressBar {
     const DIAMETER: u32 = 10;
+    const STROKE_WIDTH: u32 = 2;
 
     pub fn new(origin: Point, max: impl Into<f32>) -> Self {
-        let style = PrimitiveStyle::with_stroke(BinaryColor::On, 2);
         Self {
             maximum_value: max.into(),
             origin,
-            style,
         }
     }
 
@@ -27,15 +25,35 @@ impl ProgressBar {
         (((current / self.maximum_value) * 360.0) * -1.0).deg()
     }
 
-    pub fn draw_at<T: DrawTarget<Color = BinaryColor>>(
+    pub fn draw_at<T>(
         &self,
         current: impl Into<f32>,
         target: &mut T,
-    ) -> Result<(), <T as DrawTarget>::Error> {
+    ) -> Result<(), <T as DrawTarget>::Error>
+    where
+        T: DrawTarget,
+        T::Color: From<BinaryColor>,
+    {
         let progress = self.calculate_progress(current.into());
+        let style = PrimitiveStyle::with_stroke(BinaryColor::On.into(), Self::STROKE_WIDTH);
         Arc::new(self.origin, Self::DIAMETER, 90.0_f32.deg(), progress)
-            .into_styled(self.style)
+            .into_styled(style)
             .draw(target)?;
         Ok(())
     }
 }
+
+/// Formats a count with thousands separators, e.g. `1,234,567`.
+// The sources that show counts may all be left out of the build
+#[allow(dead_code)]
+pub fn format_count(count: u64) -> String {
+    let digits = count.to_string();
+    let mut text = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i != 0 && (digits.len() - i) % 3 == 0 {
+            text.push(',');
+        }
+        text.push(digit);
+    }
+    text
+}
@@ -0,0 +1,95 @@
+//! Credentials that shouldn't live in `settings.toml`.
+//!
+//! Every option that holds a credential is either a plain string or refers
+//! to an entry in the keyring of the OS, e.g.
+//! `api_key = { keyring = "apex-tux/openweather" }`. Entries are stored with
+//! `apex-tux secret set <name>`, which makes it safe to share the settings.
+use anyhow::{anyhow, Result};
+use config::{Config, ConfigError, Value};
+#[cfg(feature = "secrets")]
+use std::io::{self, BufRead, IsTerminal};
+
+/// Every entry is stored under this service, the name is the user
+#[cfg(feature = "secrets")]
+const SERVICE: &str = "apex-tux";
+
+/// Returns the name of the keyring entry if `value` refers to one.
+pub fn reference(value: &Value) -> Option<String> {
+    let mut table = value.clone().into_table().ok()?;
+    if table.len() != 1 {
+        return None;
+    }
+    table.remove("keyring")?.into_str().ok()
+}
+
+/// Reads the credential `key`, looking it up in the keyring if that's where
+/// it's kept. Returns `Ok(None)` if the option isn't set at all.
+// The sources that take credentials may all be left out of the build
+#[allow(dead_code)]
+pub fn get(config: &Config, key: &str) -> Result<Option<String>> {
+    let value = match config.get::<Value>(key) {
+        Ok(value) => value,
+        Err(ConfigError::NotFound(_)) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    match reference(&value) {
+        Some(name) => lookup(&name).map(Some),
+        None => Ok(Some(value.into_str()?)),
+    }
+}
+
+#[cfg(feature = "secrets")]
+fn lookup(name: &str) -> Result<String> {
+    keyring::Entry::new(SERVICE, name)
+        .and_then(|entry| entry.get_password())
+        .map_err(|e| anyhow!("Couldn't read {:?} from the keyring: {}", name, e))
+}
+
+#[cfg(not(feature = "secrets"))]
+fn lookup(name: &str) -> Result<String> {
+    Err(anyhow!(
+        "{:?} is kept in the keyring which requires the `secrets` feature",
+        name
+    ))
+}
+
+/// Asks for a secret on the terminal without showing what's typed. A secret
+/// that's piped in, e.g. from a password manager, is read as it is.
+#[cfg(feature = "secrets")]
+fn read_secret(name: &str) -> Result<String> {
+    if io::stdin().is_terminal() {
+        return Ok(rpassword::prompt_password(format!(
+            "Enter the secret for {:?}: ",
+            name
+        ))?);
+    }
+
+    let mut secret = String::new();
+    io::stdin().lock().read_line(&mut secret)?;
+    Ok(secret.trim_end_matches(&['\r', '\n'][..]).to_string())
+}
+
+#[cfg(not(feature = "secrets"))]
+fn read_secret(_name: &str) -> Result<String> {
+    Err(anyhow!("Storing secrets requires the `secrets` feature"))
+}
+
+/// Asks for a secret on the terminal and stores it in the keyring as `name`.
+pub fn store(name: &str) -> Result<()> {
+    let secret = read_secret(name)?;
+    if secret.is_empty() {
+        return Err(anyhow!("The secret is empty, nothing was stored"));
+    }
+
+    #[cfg(feature = "secrets")]
+    keyring::Entry::new(SERVICE, name)
+        .and_then(|entry| entry.set_password(&secret))
+        .map_err(|e| anyhow!("Couldn't store {:?} in the keyring: {}", name, e))?;
+
+    eprintln!(
+        "Stored {:?}, refer to it with {{ keyring = {:?} }}",
+        name, name
+    );
+    Ok(())
+}
@@ -0,0 +1,345 @@
+use crate::secrets;
+use anyhow::{Context, Result};
+use config::{Config, Source, Value};
+use std::{
+    collections::{BTreeMap, HashMap},
+    mem,
+};
+
+/// The file the configuration is read from, relative to the working directory
+pub const PATH: &str = "settings.toml";
+
+/// The fully commented example configuration. It doubles as the schema for
+/// validating the user's configuration so every option that's read anywhere
+/// has to be listed in there.
+pub static EXAMPLE: &str = include_str!("../settings.toml");
+
+/// Environment variables starting with this override the settings
+const ENVIRONMENT_PREFIX: &str = "APEX_";
+
+/// Reads `settings.toml` and applies the overrides from the environment.
+pub fn load() -> Result<Config> {
+    with_environment(load_file()?)
+}
+
+/// Reads `settings.toml` on its own. Only this is checked by [`validate`],
+/// the overrides from the environment don't have to follow the schema.
+pub fn load_file() -> Result<Config> {
+    let mut settings = Config::default();
+    settings
+        // Add in `./settings.toml`
+        .merge(config::File::with_name("settings"))
+        .context("Failed to read settings.toml")?;
+    Ok(settings)
+}
+
+/// Applies the overrides from the environment to `settings`.
+pub fn with_environment(mut settings: Config) -> Result<Config> {
+    // Add in settings from the environment (with a prefix of APEX)
+    // Eg.. `APEX_DEBUG=1 ./target/app` would set the `debug` key
+    settings.merge(config::Environment::with_prefix(ENVIRONMENT_PREFIX))?;
+    Ok(settings)
+}
+
+/// Options every source understands, they're handled by the scheduler.
+const COMMON_OPTIONS: [&str; 2] = ["enabled", "priority"];
+
+/// The kind of value an option expects, taken from the example.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Kind {
+    Boolean,
+    Integer,
+    Float,
+    String,
+    Array,
+    Table,
+}
+
+impl Kind {
+    fn of(value: &toml::Value) -> Self {
+        match value {
+            toml::Value::Boolean(_) => Kind::Boolean,
+            toml::Value::Integer(_) => Kind::Integer,
+            toml::Value::Float(_) => Kind::Float,
+            toml::Value::String(_) | toml::Value::Datetime(_) => Kind::String,
+            toml::Value::Array(_) => Kind::Array,
+            toml::Value::Table(_) => Kind::Table,
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Kind::Boolean => "a boolean",
+            Kind::Integer => "a whole number",
+            Kind::Float => "a number",
+            Kind::String => "a string",
+            Kind::Array => "a list",
+            Kind::Table => "a table",
+        }
+    }
+
+    /// Checks whether `value` can be read as this kind. This is as lenient as
+    /// `Config` itself, e.g. `"true"` is a perfectly fine boolean.
+    fn accepts(self, value: &Value) -> bool {
+        let value = value.clone();
+        match self {
+            Kind::Boolean => value.into_bool().is_ok(),
+            Kind::Integer => value.into_int().is_ok(),
+            Kind::Float => value.into_float().is_ok(),
+            // Credentials may be kept in the keyring instead
+            Kind::String => secrets::reference(&value).is_some() || value.into_str().is_ok(),
+            Kind::Array => value.into_array().is_ok(),
+            Kind::Table => value.into_table().is_ok(),
+        }
+    }
+}
+
+/// All sections and their options along with the kind of value they expect,
+/// e.g. `mpris2` -> `show_source` -> `Boolean`. Lists of tables like
+/// `[[reminders.items]]` show up as their own section.
+type Schema = BTreeMap<String, BTreeMap<String, Option<Kind>>>;
+
+/// Reads the schema from the example configuration. Options that are
+/// commented out count as well, only prose is ignored.
+fn schema() -> Schema {
+    let mut schema = Schema::new();
+    let mut section = String::new();
+
+    for line in EXAMPLE.lines() {
+        let line = line.trim();
+        let line = line.strip_prefix('#').map_or(line, str::trim);
+
+        if let Some(name) = line
+            .strip_prefix("[[")
+            .and_then(|line| line.strip_suffix("]]"))
+            .or_else(|| {
+                line.strip_prefix('[')
+                    .and_then(|line| line.strip_suffix(']'))
+            })
+        {
+            section = name.to_string();
+            schema.entry(section.clone()).or_default();
+            continue;
+        }
+
+        let key = match line.split_once(" = ") {
+            Some((key, _)) if is_identifier(key) => key,
+            _ => continue,
+        };
+
+        let kind = toml::from_str::<toml::value::Table>(line)
+            .ok()
+            .and_then(|table| table.get(key).map(Kind::of));
+        schema
+            .entry(section.clone())
+            .or_default()
+            .insert(key.to_string(), kind);
+    }
+
+    schema
+}
+
+fn is_identifier(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// The Levenshtein distance between two strings, except that swapping two
+/// neighbouring letters counts as a single edit as it's such a common typo.
+fn distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    // Swapped letters need the row before the previous one
+    let mut before = Vec::new();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, x) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, y) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(x != y);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+            if i > 0 && j > 0 && *x == b[j - 1] && a[i - 1] == *y {
+                current[j + 1] = current[j + 1].min(before[j - 1] + 1);
+            }
+        }
+        before = mem::replace(&mut previous, current);
+    }
+
+    previous[b.len()]
+}
+
+/// Finds the closest match for a misspelled name, if there is a reasonable
+/// one.
+fn suggest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    candidates
+        .map(|candidate| (distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= (name.len() / 3).max(1))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn did_you_mean(suggestion: Option<&str>) -> String {
+    suggestion.map_or_else(String::new, |suggestion| {
+        format!("; did you mean '{}'?", suggestion)
+    })
+}
+
+fn check_options(
+    schema: &Schema,
+    section: &str,
+    options: HashMap<String, Value>,
+    problems: &mut Vec<String>,
+) {
+    let known = &schema[section];
+
+    for (key, value) in options {
+        if COMMON_OPTIONS.contains(&key.as_str()) {
+            continue;
+        }
+
+        match known.get(&key) {
+            Some(Some(kind)) if !kind.accepts(&value) => problems.push(format!(
+                "option '{}.{}' should be {}",
+                section,
+                key,
+                kind.describe()
+            )),
+            Some(_) => {}
+            None => problems.push(format!(
+                "option '{}.{}' unknown{}",
+                section,
+                key,
+                did_you_mean(suggest(&key, known.keys().map(String::as_str)))
+            )),
+        }
+    }
+}
+
+/// Checks the configuration for unknown sources and options as well as
+/// values of the wrong kind and returns a readable description of every
+/// problem that was found.
+pub fn validate(config: &Config) -> Vec<String> {
+    let schema = schema();
+    let mut problems = Vec::new();
+
+    let sections = match config.collect() {
+        Ok(sections) => sections,
+        Err(e) => return vec![e.to_string()],
+    };
+
+    for (name, value) in sections {
+        if !schema.contains_key(&name) {
+            let suggestion = suggest(&name, schema.keys().map(String::as_str));
+            problems.push(format!(
+                "source '{}' unknown{}",
+                name,
+                did_you_mean(suggestion)
+            ));
+            continue;
+        }
+
+        let Ok(options) = value.into_table() else {
+            problems.push(format!("'{}' should be a section", name));
+            continue;
+        };
+
+        // Lists of tables are checked entry by entry
+        let (lists, options): (HashMap<_, _>, HashMap<_, _>) = options
+            .into_iter()
+            .partition(|(key, _)| schema.contains_key(&format!("{}.{}", name, key)));
+
+        check_options(&schema, &name, options, &mut problems);
+
+        for (key, list) in lists {
+            let section = format!("{}.{}", name, key);
+            match list.into_array() {
+                Ok(entries) => {
+                    for entry in entries {
+                        match entry.into_table() {
+                            Ok(options) => {
+                                check_options(&schema, &section, options, &mut problems);
+                            }
+                            Err(_) => {
+                                problems.push(format!("entries of '{}' should be tables", section));
+                            }
+                        }
+                    }
+                }
+                Err(_) => problems.push(format!("'{}' should be a list of tables", section)),
+            }
+        }
+    }
+
+    problems.sort();
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::{File, FileFormat};
+
+    fn config(toml: &str) -> Config {
+        let mut config = Config::default();
+        config
+            .merge(File::from_str(toml, FileFormat::Toml))
+            .unwrap();
+        config
+    }
+
+    #[test]
+    fn suggests_the_closest_name() {
+        let candidates = ["clock", "coindesk", "mpris2"];
+        assert_eq!(suggest("clcok", candidates.into_iter()), Some("clock"));
+        assert_eq!(suggest("coindsk", candidates.into_iter()), Some("coindesk"));
+        assert_eq!(suggest("mpris", candidates.into_iter()), Some("mpris2"));
+    }
+
+    #[test]
+    fn swapped_letters_are_a_single_typo() {
+        assert_eq!(distance("clock", "clcok"), 1);
+        assert_eq!(distance("ab", "ba"), 1);
+        assert_eq!(distance("abc", "ca"), 3);
+        assert_eq!(distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn suggests_nothing_that_is_too_far_off() {
+        let candidates = ["clock", "coindesk", "mpris2"];
+        assert_eq!(suggest("weather", candidates.into_iter()), None);
+        // Short names only get to be off by one
+        assert_eq!(suggest("ab", ["abc", "xyz"].into_iter()), Some("abc"));
+        assert_eq!(suggest("ab", ["cd"].into_iter()), None);
+    }
+
+    #[test]
+    fn the_example_is_valid() {
+        assert_eq!(validate(&config(EXAMPLE)), Vec::<String>::new());
+    }
+
+    #[test]
+    fn overrides_from_the_environment_are_not_checked() {
+        std::env::set_var(format!("{}_DEBUG", ENVIRONMENT_PREFIX), "1");
+
+        let file = load_file().unwrap();
+        assert_eq!(validate(&file), Vec::<String>::new());
+        let settings = with_environment(file).unwrap();
+        assert_eq!(settings.get_str("debug").unwrap(), "1");
+    }
+
+    #[test]
+    fn points_out_typos() {
+        let problems = validate(&config(
+            "[clcok]\nenabled = true\n[clock]\ntwelve_huor = true",
+        ));
+        assert_eq!(
+            problems,
+            [
+                "option 'clock.twelve_huor' unknown; did you mean 'twelve_hour'?",
+                "source 'clcok' unknown; did you mean 'clock'?",
+            ]
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use crate::render::clock::SharedClock;
+use sysinfo::{CpuExt, NetworkExt, NetworksExt, System, SystemExt};
+use tokio::time::Instant;
+
+/// Keeps track of the system's resource usage.
+///
+/// Every kind of statistic has its own collector so sources only pay for what
+/// they actually display. Collectors have to be refreshed regularly, the rates
+/// they report are relative to the previous refresh.
+pub trait Collector {
+    fn refresh(&mut self, system: &mut System);
+}
+
+/// The combined usage of all CPU cores.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Cpu {
+    /// The usage in percent
+    pub usage: f32,
+}
+
+impl Collector for Cpu {
+    fn refresh(&mut self, system: &mut System) {
+        system.refresh_cpu();
+        self.usage = system.global_cpu_info().cpu_usage();
+    }
+}
+
+/// How much of the physical memory is in use.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Memory {
+    /// Used memory in bytes
+    pub used: u64,
+    /// Total memory in bytes
+    pub total: u64,
+}
+
+impl Memory {
+    /// The usage in percent
+    pub fn usage(&self) -> f32 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.used as f32 / self.total as f32 * 100.0
+        }
+    }
+}
+
+impl Collector for Memory {
+    fn refresh(&mut self, system: &mut System) {
+        system.refresh_memory();
+        self.used = system.used_memory();
+        self.total = system.total_memory();
+    }
+}
+
+/// The throughput of all network interfaces combined.
+#[derive(Debug, Clone)]
+pub struct Network {
+    /// Bytes received per second
+    pub received: u64,
+    /// Bytes sent per second
+    pub transmitted: u64,
+    last_refresh: Option<Instant>,
+    clock: SharedClock,
+}
+
+impl Network {
+    /// The rates are measured in the time of `clock`.
+    pub fn new(clock: SharedClock) -> Self {
+        Self {
+            received: 0,
+            transmitted: 0,
+            last_refresh: None,
+            clock,
+        }
+    }
+}
+
+impl Collector for Network {
+    fn refresh(&mut self, system: &mut System) {
+        if self.last_refresh.is_none() {
+            system.refresh_networks_list();
+        }
+        system.refresh_networks();
+
+        let now = self.clock.now();
+        let elapsed = match self.last_refresh.replace(now) {
+            Some(last) => now.duration_since(last).as_secs_f64(),
+            // The first refresh only establishes the baseline
+            None => return,
+        };
+
+        let (received, transmitted) =
+            system
+                .networks()
+                .iter()
+                .fold((0, 0), |(received, transmitted), (_, data)| {
+                    (received + data.received(), transmitted + data.transmitted())
+                });
+        self.received = (received as f64 / elapsed) as u64;
+        self.transmitted = (transmitted as f64 / elapsed) as u64;
+    }
+}
+
+/// Formats a number of bytes with a binary prefix, e.g. `1.2M`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 || value >= 10.0 {
+        format!("{:.0}{}", value, UNITS[unit])
+    } else {
+        format!("{:.1}{}", value, UNITS[unit])
+    }
+}
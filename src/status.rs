@@ -0,0 +1,195 @@
+//! What `apex-tux players` and `apex-tux status` report.
+//!
+//! With `--json` the structs in here are printed as they are, so scripts and
+//! other apps don't have to scrape the text. Fields may be added over time but
+//! the existing ones keep their names and meaning.
+#[cfg(any(feature = "dbus-support", target_os = "windows"))]
+use crate::backend::{self, Listing, Player};
+#[cfg(unix)]
+use crate::control;
+use anyhow::Result;
+#[cfg(any(feature = "dbus-support", target_os = "windows"))]
+use apex_music::{Metadata, PlaybackStatus};
+use config::Config;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Playback {
+    Playing,
+    Paused,
+    Stopped,
+    /// The player didn't say
+    Unknown,
+}
+
+impl Playback {
+    fn label(self) -> &'static str {
+        match self {
+            Playback::Playing => "playing",
+            Playback::Paused => "paused",
+            Playback::Stopped => "stopped",
+            Playback::Unknown => "unknown",
+        }
+    }
+}
+
+/// A music player that's open right now.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PlayerStatus {
+    pub name: String,
+    pub playback: Playback,
+    pub title: Option<String>,
+    pub artists: Vec<String>,
+    /// Whether it's the player the music source shows
+    pub displayed: bool,
+}
+
+#[cfg(any(feature = "dbus-support", target_os = "windows"))]
+impl From<Listing> for PlayerStatus {
+    fn from(listing: Listing) -> Self {
+        let playback = match listing.status {
+            Some(PlaybackStatus::Playing) => Playback::Playing,
+            Some(PlaybackStatus::Paused) => Playback::Paused,
+            Some(PlaybackStatus::Stopped) => Playback::Stopped,
+            None => Playback::Unknown,
+        };
+        let (title, artists) = match &listing.metadata {
+            Some(metadata) => (
+                metadata.title().ok(),
+                metadata.artists().unwrap_or_default(),
+            ),
+            None => (None, Vec::new()),
+        };
+
+        Self {
+            name: listing.name,
+            playback,
+            title,
+            artists,
+            displayed: listing.displayed,
+        }
+    }
+}
+
+/// What a running instance has on the display.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct InstanceStatus {
+    /// The name of the current source, as in its section of the settings
+    pub source: Option<String>,
+    pub pinned: bool,
+    /// How bright the display is in percent, 0 while it's asleep
+    #[serde(default)]
+    pub brightness: u8,
+    /// How many writes to the keyboard had to be retried
+    #[serde(default)]
+    pub retries: u64,
+    /// How often the keyboard had to be opened again
+    #[serde(default)]
+    pub reconnects: u64,
+}
+
+/// Everything `apex-tux status` reports.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Status {
+    /// Missing if no instance is running or it can't be asked
+    pub instance: Option<InstanceStatus>,
+    pub players: Vec<PlayerStatus>,
+}
+
+/// Asks the music backend which players are open, the configured preferences
+/// decide which one is marked as displayed.
+#[cfg(any(feature = "dbus-support", target_os = "windows"))]
+async fn players(config: &Config) -> Result<Vec<PlayerStatus>> {
+    let name = config.get_str("mpris2.preferred_player").ok();
+    let priority = backend::configured_priority(config);
+
+    let player = Player::auto(&backend::configured(config))
+        .await
+        .with_priority(priority);
+    Ok(player
+        .list(name.as_deref())
+        .await?
+        .into_iter()
+        .map(PlayerStatus::from)
+        .collect())
+}
+
+#[cfg(not(any(feature = "dbus-support", target_os = "windows")))]
+#[allow(clippy::unused_async)]
+async fn players(_config: &Config) -> Result<Vec<PlayerStatus>> {
+    Ok(Vec::new())
+}
+
+#[cfg(unix)]
+async fn instance(config: &Config) -> Option<InstanceStatus> {
+    let reply = control::query(config, "status").await.ok()?;
+    serde_json::from_str(&reply).ok()
+}
+
+/// Without the control socket there's no way to ask the running instance.
+#[cfg(not(unix))]
+#[allow(clippy::unused_async)]
+async fn instance(_config: &Config) -> Option<InstanceStatus> {
+    None
+}
+
+fn describe(player: &PlayerStatus) -> String {
+    let marker = if player.displayed { '*' } else { ' ' };
+    let line = format!("{} {} ({})", marker, player.name, player.playback.label());
+    match &player.title {
+        Some(title) if player.artists.is_empty() => format!("{}: {}", line, title),
+        Some(title) => format!("{}: {} - {}", line, player.artists.join(", "), title),
+        None => line,
+    }
+}
+
+/// Prints the players that are open, the one that's displayed is marked with
+/// an asterisk.
+pub async fn print_players(config: &Config, json: bool) -> Result<()> {
+    let players = players(config).await?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&players)?);
+        return Ok(());
+    }
+
+    if players.is_empty() {
+        println!("No players found");
+    }
+    for player in &players {
+        println!("{}", describe(player));
+    }
+    Ok(())
+}
+
+/// Prints what the running instance is showing along with the players.
+pub async fn print_status(config: &Config, json: bool) -> Result<()> {
+    let status = Status {
+        instance: instance(config).await,
+        players: players(config).await?,
+    };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&status)?);
+        return Ok(());
+    }
+
+    match &status.instance {
+        Some(instance) => {
+            println!(
+                "Showing {}{}",
+                instance.source.as_deref().unwrap_or("nothing yet"),
+                if instance.pinned { " (pinned)" } else { "" }
+            );
+            println!(
+                "Retried {} writes to the keyboard, reconnected {} times",
+                instance.retries, instance.reconnects
+            );
+        }
+        None => println!("apex-tux isn't running or can't be reached"),
+    }
+    match status.players.iter().find(|player| player.displayed) {
+        Some(player) => println!("{}", describe(player)),
+        None => println!("No player is displayed"),
+    }
+    Ok(())
+}